@@ -0,0 +1,212 @@
+use crate::command::Command;
+use crate::message::Message;
+use crate::tags::Tags;
+use alloc::vec::Vec;
+
+/// A closed batch and everything it contained
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batch<'a> {
+    /// The reference tag without the `+`
+    pub reference: &'a [u8],
+    /// The type of the batch: `chathistory`, `netsplit`…
+    pub kind: &'a [u8],
+    /// The parameters following the type
+    pub params: Vec<&'a [u8]>,
+    /// The tags of the `BATCH +ref` message: the `label` of a labeled
+    /// response
+    pub tags: Tags<'a>,
+    /// The messages and nested batches, in the order they were received
+    pub items: Vec<BatchItem<'a>>,
+}
+
+/// An element of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchItem<'a> {
+    Message(Message<'a>),
+    Batch(Batch<'a>),
+}
+
+/// What comes out of the collector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A message outside of any batch
+    Message(Message<'a>),
+    /// A top level batch that just closed
+    Batch(Batch<'a>),
+}
+
+/// Group the messages of the IRCv3 batches
+/// A `BATCH +ref type` opens a batch, the messages tagged with `batch=ref`
+/// are stored in it and a `BATCH -ref` closes it. A batch opened with a
+/// `batch` tag is nested in its parent and returned with it.
+#[derive(Debug, Clone, Default)]
+pub struct BatchCollector<'a> {
+    /// The open batches and the reference of their parent
+    open: Vec<(Batch<'a>, Option<Vec<u8>>)>,
+}
+
+impl<'a> BatchCollector<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return true if no batch is open
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Feed a parsed message
+    /// Return `None` while the message is kept in an open batch. Messages
+    /// referencing an unknown batch are returned as is.
+    pub fn feed(&mut self, message: Message<'a>) -> Option<Event<'a>> {
+        let parent = message.tags.get("batch").map(<[u8]>::to_vec);
+        match batch_command(&message) {
+            Some(BatchCommand::Open {
+                reference,
+                kind,
+                params,
+            }) => {
+                let batch = Batch {
+                    reference,
+                    kind,
+                    params: params.to_vec(),
+                    tags: message.tags.clone(),
+                    items: Vec::new(),
+                };
+                let parent = parent.filter(|parent| self.position(parent).is_some());
+                self.open.push((batch, parent));
+                return None;
+            }
+            Some(BatchCommand::Close(reference)) => {
+                if let Some(pos) = self.position(reference) {
+                    let (batch, parent) = self.open.remove(pos);
+                    return self.insert(parent.as_deref(), BatchItem::Batch(batch));
+                }
+            }
+            None => (),
+        }
+        match parent {
+            Some(parent) if self.position(&parent).is_some() => {
+                self.insert(Some(&parent), BatchItem::Message(message))
+            }
+            _ => Some(Event::Message(message)),
+        }
+    }
+
+    fn position(&self, reference: &[u8]) -> Option<usize> {
+        self.open
+            .iter()
+            .position(|(batch, _)| batch.reference == reference)
+    }
+
+    /// Store the item in its parent batch or return it if it's top level
+    fn insert(&mut self, parent: Option<&[u8]>, item: BatchItem<'a>) -> Option<Event<'a>> {
+        match parent.and_then(|parent| self.position(parent)) {
+            Some(pos) => {
+                self.open[pos].0.items.push(item);
+                None
+            }
+            None => Some(match item {
+                BatchItem::Message(message) => Event::Message(message),
+                BatchItem::Batch(batch) => Event::Batch(batch),
+            }),
+        }
+    }
+}
+
+enum BatchCommand<'a, 'm> {
+    /// `BATCH +ref type params…`
+    Open {
+        reference: &'a [u8],
+        kind: &'a [u8],
+        params: &'m [&'a [u8]],
+    },
+    /// `BATCH -ref`
+    Close(&'a [u8]),
+}
+
+fn batch_command<'a, 'm>(message: &'m Message<'a>) -> Option<BatchCommand<'a, 'm>> {
+    match message.command {
+        Command::Batch => (),
+        _ => return None,
+    }
+    let (reference, rest) = message.params.split_first()?;
+    match (reference.split_first()?, rest) {
+        ((b'+', reference), [kind, params @ ..]) => Some(BatchCommand::Open {
+            reference,
+            kind,
+            params,
+        }),
+        ((b'-', reference), _) => Some(BatchCommand::Close(reference)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_all;
+
+    fn events(input: &[u8]) -> Vec<Event<'_>> {
+        let mut collector = BatchCollector::new();
+        let events = parse_all(input)
+            .filter_map(|message| collector.feed(message.unwrap()))
+            .collect();
+        assert!(collector.is_empty());
+        events
+    }
+
+    #[test]
+    fn batch_simple() {
+        let input = b"BATCH +a netsplit irc.hub other.host\r\n\
+            @batch=a :n1!u@h QUIT :irc.hub other.host\r\n\
+            PING :x\r\n\
+            @batch=a :n2!u@h QUIT :irc.hub other.host\r\n\
+            BATCH -a\r\n";
+        let events = events(input);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Event::Message(m) if m.command == Command::Ping));
+        match &events[1] {
+            Event::Batch(batch) => {
+                assert_eq!(batch.reference, b"a");
+                assert_eq!(batch.kind, b"netsplit");
+                assert_eq!(batch.params, vec![&b"irc.hub"[..], b"other.host"]);
+                assert_eq!(batch.items.len(), 2);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn batch_nested() {
+        let input = b"BATCH +outer example\r\n\
+            @batch=outer BATCH +inner example\r\n\
+            @batch=inner PRIVMSG #a :1\r\n\
+            BATCH -inner\r\n\
+            @batch=outer PRIVMSG #a :2\r\n\
+            BATCH -outer\r\n";
+        let events = events(input);
+        assert_eq!(events.len(), 1);
+        let outer = match &events[0] {
+            Event::Batch(batch) => batch,
+            event => panic!("unexpected {:?}", event),
+        };
+        assert_eq!(outer.items.len(), 2);
+        match &outer.items[0] {
+            BatchItem::Batch(inner) => {
+                assert_eq!(inner.reference, b"inner");
+                assert_eq!(inner.items.len(), 1);
+            }
+            item => panic!("unexpected {:?}", item),
+        }
+        assert!(matches!(&outer.items[1], BatchItem::Message(_)));
+    }
+
+    #[test]
+    fn batch_unknown_reference() {
+        let input = b"@batch=nope PRIVMSG #a :1\r\nBATCH -nope\r\n";
+        let events = events(input);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| matches!(e, Event::Message(_))));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::command::Command;
+use crate::error::BuildError;
+use crate::message::Message;
+use crate::params::Params;
+use crate::prefix::Prefix;
+use crate::tags::{key, Tags};
+use alloc::borrow::Cow;
+use nom::combinator::all_consuming;
+
+/// The maximum number of parameters of a message
+const MAX_PARAMS: usize = 15;
+
+/// Build an outgoing message, the parts are only checked by `build`
+/// ```
+/// use irc_parser::builder::MessageBuilder;
+/// use irc_parser::command::Command;
+///
+/// let message = MessageBuilder::new(Command::Privmsg)
+///     .param("#rust")
+///     .trailing("hello world")
+///     .tag("label", "abc")
+///     .build()
+///     .unwrap();
+/// assert_eq!(message.to_string(), "@label=abc PRIVMSG #rust :hello world");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageBuilder<'a> {
+    tags: Tags<'a>,
+    prefix: Option<Prefix<'a>>,
+    command: Command<'a>,
+    params: Params<'a>,
+    after_trailing: bool,
+}
+
+impl<'a> MessageBuilder<'a> {
+    pub fn new(command: Command<'a>) -> Self {
+        MessageBuilder {
+            tags: Tags::new(),
+            prefix: None,
+            command,
+            params: Params::new(),
+            after_trailing: false,
+        }
+    }
+
+    /// Add a tag, the value is escaped when the message is serialized
+    pub fn tag<K, V>(mut self, key: &'a K, value: &'a V) -> Self
+    where
+        K: AsRef<[u8]> + ?Sized,
+        V: AsRef<[u8]> + ?Sized,
+    {
+        self.tags.push(key.as_ref(), Cow::Borrowed(value.as_ref()));
+        self
+    }
+
+    pub fn prefix(mut self, prefix: Prefix<'a>) -> Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Add a middle parameter
+    pub fn param<P: AsRef<[u8]> + ?Sized>(mut self, param: &'a P) -> Self {
+        self.after_trailing |= self.params.has_trailing();
+        self.params.push(param.as_ref());
+        self
+    }
+
+    /// Add the trailing parameter, it must be the last one
+    pub fn trailing<P: AsRef<[u8]> + ?Sized>(mut self, param: &'a P) -> Self {
+        self.after_trailing |= self.params.has_trailing();
+        self.params.push_trailing(param.as_ref());
+        self
+    }
+
+    /// Check every part and return the message
+    pub fn build(self) -> Result<Message<'a>, BuildError> {
+        if self.after_trailing {
+            return Err(BuildError::ParamAfterTrailing);
+        }
+        if self.params.len() > MAX_PARAMS {
+            return Err(BuildError::TooManyParams {
+                count: self.params.len(),
+            });
+        }
+        for (index, tag) in self.tags.iter().enumerate() {
+            if all_consuming(key)(tag.key).is_err() {
+                return Err(BuildError::InvalidTagKey { index });
+            }
+        }
+        let middles = self.params.middles().len();
+        for (index, param) in self.params.iter().enumerate() {
+            let forbidden = |c: &u8| matches!(c, 0x00 | b'\r' | b'\n');
+            let valid = if index < middles {
+                !param.is_empty()
+                    && param[0] != b':'
+                    && !param.iter().any(|c| forbidden(c) || *c == b' ')
+            } else {
+                !param.iter().any(forbidden)
+            };
+            if !valid {
+                return Err(BuildError::InvalidParam { index });
+            }
+        }
+        Ok(Message {
+            tags: self.tags,
+            prefix: self.prefix,
+            command: self.command,
+            params: self.params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn builder_message() {
+        let message = MessageBuilder::new(Command::Privmsg)
+            .prefix(Prefix::User {
+                nick: b"nick",
+                user: None,
+                host: None,
+            })
+            .param("#rust")
+            .trailing("hello world")
+            .tag("label", "abc")
+            .tag("+draft/reply", &b"a;b"[..])
+            .build()
+            .unwrap();
+        let bytes = message.to_bytes();
+        assert_eq!(
+            bytes,
+            b"@label=abc;+draft/reply=a\\:b :nick PRIVMSG #rust :hello world\r\n".to_vec()
+        );
+        assert_eq!(parse_message(&bytes), Ok(message));
+    }
+
+    #[test]
+    fn builder_invalid_params() {
+        let build = |builder: MessageBuilder<'static>| builder.build().unwrap_err();
+        assert_eq!(
+            build(MessageBuilder::new(Command::Privmsg).param("a b")),
+            BuildError::InvalidParam { index: 0 }
+        );
+        assert_eq!(
+            build(
+                MessageBuilder::new(Command::Privmsg)
+                    .param("#a")
+                    .param(":b")
+            ),
+            BuildError::InvalidParam { index: 1 }
+        );
+        assert_eq!(
+            build(MessageBuilder::new(Command::Privmsg).param("")),
+            BuildError::InvalidParam { index: 0 }
+        );
+        assert_eq!(
+            build(MessageBuilder::new(Command::Privmsg).trailing("a\r\nQUIT")),
+            BuildError::InvalidParam { index: 0 }
+        );
+        assert_eq!(
+            build(
+                MessageBuilder::new(Command::Privmsg)
+                    .trailing("a")
+                    .param("b")
+            ),
+            BuildError::ParamAfterTrailing
+        );
+        assert_eq!(
+            build(MessageBuilder::new(Command::Ping).tag("a b", "c")),
+            BuildError::InvalidTagKey { index: 0 }
+        );
+        let mut builder = MessageBuilder::new(Command::Ping);
+        for _ in 0..16 {
+            builder = builder.param("a");
+        }
+        assert_eq!(build(builder), BuildError::TooManyParams { count: 16 });
+    }
+
+    #[test]
+    fn builder_empty_trailing() {
+        let message = MessageBuilder::new(Command::Topic)
+            .param("#a")
+            .trailing("")
+            .build()
+            .unwrap();
+        assert_eq!(message.to_bytes(), b"TOPIC #a :\r\n".to_vec());
+    }
+}
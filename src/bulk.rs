@@ -0,0 +1,228 @@
+use crate::config::ParserConfig;
+use crate::message::{parse_line, Message};
+use crate::recover::Parsed;
+use crate::tags::{escaped_tags, unescape_into};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Parse big buffers of lines, like logs or the backlog of a bouncer
+/// The escaped tag values of all the lines are unescaped in the same buffer,
+/// so once it is large enough parsing a line only allocates the list of its
+/// tags and the params past the fourth one.
+/// ```
+/// use irc_parser::bulk::Parser;
+///
+/// let mut parser = Parser::new();
+/// let mut count = 0;
+/// let consumed = parser.parse_lines(b"PING :a\r\nPING :b\r\nPI", |message| {
+///     assert_eq!(message.command.as_str(), "PING");
+///     count += 1;
+/// });
+/// assert_eq!(count, 2);
+/// assert_eq!(consumed, 18);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    config: ParserConfig,
+    /// The unescaped tag values of the current line
+    scratch: Vec<u8>,
+    /// The index of the unescaped tags and their value in `scratch`
+    unescaped: Vec<(usize, Range<usize>)>,
+    skipped: usize,
+    /// The end of an overlong line is being dropped
+    skipping: bool,
+}
+
+impl Parser {
+    /// Parse with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Parser {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Give every message of `buffer` to `sink` and return the number of
+    /// bytes consumed
+    /// Only the whole lines are consumed, an unterminated last line should be
+    /// given again once the rest has been read. Malformed lines are skipped
+    /// and counted by `skipped`.
+    pub fn parse_lines(&mut self, buffer: &[u8], mut sink: impl FnMut(Message<'_>)) -> usize {
+        self.parse_or_recover_lines(buffer, |line| {
+            if let Parsed::Message(message) = line {
+                sink(message);
+            }
+        })
+    }
+
+    /// Same as `parse_lines` but the malformed lines are given to `sink` as
+    /// `Parsed::Recovered` too, they are still counted by `skipped`
+    /// Once an unterminated line is longer than the configuration allows it is
+    /// consumed and reported, and the rest of it is dropped by the next calls.
+    pub fn parse_or_recover_lines(
+        &mut self,
+        buffer: &[u8],
+        mut sink: impl FnMut(Parsed<'_>),
+    ) -> usize {
+        let mut consumed = 0;
+        if self.skipping {
+            match self.config.line_len(buffer) {
+                Some(len) => {
+                    consumed = len;
+                    self.skipping = false;
+                }
+                None => return end_of_line(buffer),
+            }
+        }
+        while let Some(len) = self.config.line_len(&buffer[consumed..]) {
+            let line = &buffer[consumed..consumed + len];
+            consumed += len;
+            let mut message = match parse_line(line, &self.config, escaped_tags) {
+                Ok(message) => message,
+                Err(error) => {
+                    self.skipped += 1;
+                    sink(Parsed::Recovered {
+                        raw_line: line,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            self.scratch.clear();
+            self.unescaped.clear();
+            for (i, tag) in message.tags.iter().enumerate() {
+                if tag.value.contains(&b'\\') {
+                    let start = self.scratch.len();
+                    unescape_into(&tag.value, &mut self.scratch);
+                    self.unescaped.push((i, start..self.scratch.len()));
+                }
+            }
+            let mut unescaped = self.unescaped.iter().peekable();
+            for (i, tag) in message.tags.iter_mut().enumerate() {
+                if let Some((_, range)) = unescaped.next_if(|(index, _)| *index == i) {
+                    tag.value = Cow::Borrowed(&self.scratch[range.clone()]);
+                }
+            }
+            sink(Parsed::Message(message));
+        }
+        let rest = &buffer[consumed..];
+        if rest.len() > self.config.max_len() {
+            let line = &rest[..end_of_line(rest)];
+            self.skipped += 1;
+            self.skipping = true;
+            sink(Parsed::Recovered {
+                raw_line: line,
+                error: self.config.too_long(line, rest.len()),
+            });
+            consumed += line.len();
+        }
+        consumed
+    }
+
+    /// Return the number of malformed lines skipped since the creation of
+    /// the parser
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+/// Return the length of an unterminated line that can be consumed, a last CR
+/// is kept since it could be the start of the line ending
+fn end_of_line(line: &[u8]) -> usize {
+    line.len() - (line.last() == Some(&b'\r')) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn bulk_parse() {
+        let input = b"@a=b\\sc;d=e :nick PRIVMSG #chan :hi\r\n\
+            :nick 12 :invalid\r\n\
+            @x=\\:\\\\ PING :token\r\n\
+            PING :incomplete";
+        let mut parser = Parser::new();
+        let mut lines = Vec::new();
+        let consumed = parser.parse_lines(input, |message| lines.push(message.to_bytes()));
+        assert_eq!(consumed, input.len() - b"PING :incomplete".len());
+        assert_eq!(parser.skipped(), 1);
+        assert_eq!(lines.len(), 2);
+        let first = parse_message(&lines[0]).unwrap();
+        assert_eq!(first.tags.get("a"), Some(&b"b c"[..]));
+        assert_eq!(first.tags.get("d"), Some(&b"e"[..]));
+        let second = parse_message(&lines[1]).unwrap();
+        assert_eq!(second.tags.get("x"), Some(&b";\\"[..]));
+    }
+
+    #[test]
+    fn bulk_same_as_parse_message() {
+        let line = b"@a=\\s;b;c=d\\ :irc.example.com 001 nick :Welcome\r\n";
+        let mut parser = Parser::new();
+        parser.parse_lines(line, |message| {
+            assert_eq!(message, parse_message(line).unwrap());
+        });
+    }
+
+    #[test]
+    fn bulk_recover() {
+        let input = b"PING :a\r\n:nick 12 :invalid\r\n@a=\\s PING :b\r\n";
+        let mut parser = Parser::new();
+        let mut lines = Vec::new();
+        parser.parse_or_recover_lines(input, |line| {
+            lines.push(match line {
+                Parsed::Message(message) => Ok(message.to_bytes()),
+                Parsed::Recovered { raw_line, error } => Err((raw_line.to_vec(), error)),
+            })
+        });
+        assert_eq!(parser.skipped(), 1);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            Err((
+                b":nick 12 :invalid\r\n".to_vec(),
+                crate::ParseError::InvalidCommand { offset: 8 }
+            ))
+        );
+        assert_eq!(lines[2], Ok(b"@a=\\s PING :b\r\n".to_vec()));
+    }
+
+    #[test]
+    fn bulk_recover_too_long() {
+        let mut input = b"PRIVMSG #chan :".to_vec();
+        input.resize(9000, b'a');
+        input.push(b'\r');
+        let mut parser = Parser::new();
+        let mut errors = Vec::new();
+        let consumed = parser.parse_or_recover_lines(&input, |line| {
+            if let Parsed::Recovered { raw_line, error } = line {
+                errors.push((raw_line.len(), error));
+            }
+        });
+        assert_eq!(consumed, 9000);
+        assert_eq!(
+            errors,
+            [(
+                9000,
+                crate::ParseError::LineTooLong {
+                    offset: 0,
+                    len: 9001,
+                    max: 512
+                }
+            )]
+        );
+        assert_eq!(parser.parse_lines(b"\raaa", |_| panic!()), 4);
+        let mut lines = Vec::new();
+        let consumed =
+            parser.parse_lines(b"\r\nPING :a\r\n", |message| lines.push(message.to_bytes()));
+        assert_eq!(consumed, 11);
+        assert_eq!(lines, [b"PING :a\r\n".to_vec()]);
+        assert_eq!(parser.skipped(), 1);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::command::Command;
+use crate::message::Message;
+use alloc::vec::Vec;
+
+/// A capability as found in the list of a CAP message: `sasl=PLAIN,EXTERNAL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capability<'a> {
+    /// The name without the `-` modifier: `sasl`
+    pub name: &'a [u8],
+    /// The value sent by CAP LS 302 and CAP NEW: `PLAIN,EXTERNAL`
+    pub value: Option<&'a [u8]>,
+    /// The capability is prefixed by `-` in a REQ or ACK: it's disabled
+    pub disable: bool,
+}
+
+/// A parsed CAP message, sent by a client or by a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cap<'a> {
+    /// `CAP LS [version]` sent by a client
+    LsRequest { version: Option<&'a [u8]> },
+    /// `CAP LIST` sent by a client
+    ListRequest,
+    /// `CAP REQ :caps` sent by a client
+    Req { caps: Vec<Capability<'a>> },
+    /// `CAP END` sent by a client
+    End,
+    /// `CAP <target> LS [*] :caps` sent by a server
+    /// `more` is true when the list continues on the next message.
+    Ls {
+        target: &'a [u8],
+        more: bool,
+        caps: Vec<Capability<'a>>,
+    },
+    /// `CAP <target> LIST [*] :caps` sent by a server
+    List {
+        target: &'a [u8],
+        more: bool,
+        caps: Vec<Capability<'a>>,
+    },
+    /// `CAP <target> ACK :caps` sent by a server
+    Ack {
+        target: &'a [u8],
+        caps: Vec<Capability<'a>>,
+    },
+    /// `CAP <target> NAK :caps` sent by a server
+    Nak {
+        target: &'a [u8],
+        caps: Vec<Capability<'a>>,
+    },
+    /// `CAP <target> NEW :caps` sent by a server
+    New {
+        target: &'a [u8],
+        caps: Vec<Capability<'a>>,
+    },
+    /// `CAP <target> DEL :caps` sent by a server
+    Del {
+        target: &'a [u8],
+        caps: Vec<Capability<'a>>,
+    },
+}
+
+const SUBCOMMANDS: [&[u8]; 8] = [
+    b"LS", b"LIST", b"REQ", b"ACK", b"NAK", b"NEW", b"DEL", b"END",
+];
+
+fn is_subcommand(param: &[u8]) -> bool {
+    SUBCOMMANDS
+        .iter()
+        .any(|sub| sub.eq_ignore_ascii_case(param))
+}
+
+impl<'a> Cap<'a> {
+    /// Interpret a CAP message
+    /// A message whose second parameter is a subcommand comes from a server,
+    /// otherwise it comes from a client.
+    /// Return `None` if it's not a CAP message or if it's malformed.
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        match message.command {
+            Command::Cap => (),
+            _ => return None,
+        }
+        let params = &message.params;
+        if params.len() >= 2 && is_subcommand(params[1]) {
+            Self::from_server(params[0], params[1], &params[2..])
+        } else {
+            Self::from_client(params.first()?, &params[1..])
+        }
+    }
+
+    fn from_client(subcommand: &'a [u8], params: &[&'a [u8]]) -> Option<Self> {
+        let subcommand = subcommand.to_ascii_uppercase();
+        Some(match (&subcommand[..], params) {
+            (b"LS", [version]) => Cap::LsRequest {
+                version: Some(version),
+            },
+            (b"LS", []) => Cap::LsRequest { version: None },
+            (b"LIST", []) => Cap::ListRequest,
+            (b"REQ", [caps]) => Cap::Req {
+                caps: capabilities(caps),
+            },
+            (b"END", []) => Cap::End,
+            _ => return None,
+        })
+    }
+
+    fn from_server(target: &'a [u8], subcommand: &'a [u8], params: &[&'a [u8]]) -> Option<Self> {
+        let subcommand = subcommand.to_ascii_uppercase();
+        let (more, caps) = match params {
+            [b"*", caps] => (true, capabilities(caps)),
+            [caps] => (false, capabilities(caps)),
+            _ => return None,
+        };
+        Some(match (&subcommand[..], more) {
+            (b"LS", _) => Cap::Ls { target, more, caps },
+            (b"LIST", _) => Cap::List { target, more, caps },
+            (b"ACK", false) => Cap::Ack { target, caps },
+            (b"NAK", false) => Cap::Nak { target, caps },
+            (b"NEW", false) => Cap::New { target, caps },
+            (b"DEL", false) => Cap::Del { target, caps },
+            _ => return None,
+        })
+    }
+}
+
+/// Split a space separated list of capabilities
+pub fn capabilities(list: &[u8]) -> Vec<Capability<'_>> {
+    list.split(|&c| c == b' ')
+        .filter(|cap| !cap.is_empty())
+        .map(|cap| {
+            let (disable, cap) = match cap.strip_prefix(b"-") {
+                Some(cap) => (true, cap),
+                None => (false, cap),
+            };
+            match cap.iter().position(|&c| c == b'=') {
+                Some(eq) => Capability {
+                    name: &cap[..eq],
+                    value: Some(&cap[eq + 1..]),
+                    disable,
+                },
+                None => Capability {
+                    name: cap,
+                    value: None,
+                    disable,
+                },
+            }
+        })
+        .collect()
+}
+
+/// A list of owned `(name, value)` capabilities
+pub type CapabilityList = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+/// Accumulate the capabilities of a multiline `CAP LS` reply
+/// The capabilities are copied, so the messages don't need to outlive the
+/// accumulator.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapLsAccumulator {
+    caps: CapabilityList,
+}
+
+impl CapLsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a CAP message, anything else than a server `CAP LS` is ignored
+    /// Return the complete list of `(name, value)` once the last line arrived
+    /// and start a new list.
+    pub fn feed(&mut self, cap: &Cap<'_>) -> Option<CapabilityList> {
+        match cap {
+            Cap::Ls { more, caps, .. } => {
+                self.caps.extend(
+                    caps.iter()
+                        .map(|cap| (cap.name.to_vec(), cap.value.map(<[u8]>::to_vec))),
+                );
+                if *more {
+                    None
+                } else {
+                    Some(core::mem::take(&mut self.caps))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    fn cap(line: &[u8]) -> Option<Cap<'_>> {
+        Cap::from_message(&parse_message(line).unwrap())
+    }
+
+    fn capability<'a>(name: &'a [u8], value: Option<&'a [u8]>) -> Capability<'a> {
+        Capability {
+            name,
+            value,
+            disable: false,
+        }
+    }
+
+    #[test]
+    fn cap_not_cap() {
+        assert_eq!(cap(b"PRIVMSG #chan :CAP LS\r\n"), None);
+        assert_eq!(cap(b"CAP\r\n"), None);
+        assert_eq!(cap(b"CAP FOO\r\n"), None);
+    }
+
+    #[test]
+    fn cap_client() {
+        assert_eq!(
+            cap(b"CAP LS 302\r\n"),
+            Some(Cap::LsRequest {
+                version: Some(&b"302"[..])
+            })
+        );
+        assert_eq!(cap(b"cap ls\r\n"), Some(Cap::LsRequest { version: None }));
+        assert_eq!(cap(b"CAP LIST\r\n"), Some(Cap::ListRequest));
+        assert_eq!(cap(b"CAP END\r\n"), Some(Cap::End));
+        assert_eq!(
+            cap(b"CAP REQ :multi-prefix -away-notify\r\n"),
+            Some(Cap::Req {
+                caps: vec![
+                    capability(b"multi-prefix", None),
+                    Capability {
+                        name: b"away-notify",
+                        value: None,
+                        disable: true
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn cap_server_ls() {
+        assert_eq!(
+            cap(b":irc.example.com CAP * LS * :multi-prefix sasl=PLAIN,EXTERNAL\r\n"),
+            Some(Cap::Ls {
+                target: b"*",
+                more: true,
+                caps: vec![
+                    capability(b"multi-prefix", None),
+                    capability(b"sasl", Some(b"PLAIN,EXTERNAL")),
+                ]
+            })
+        );
+        assert_eq!(
+            cap(b":irc.example.com CAP nick LS :server-time\r\n"),
+            Some(Cap::Ls {
+                target: b"nick",
+                more: false,
+                caps: vec![capability(b"server-time", None)]
+            })
+        );
+    }
+
+    #[test]
+    fn cap_server_others() {
+        assert_eq!(
+            cap(b"CAP nick ACK :sasl\r\n"),
+            Some(Cap::Ack {
+                target: b"nick",
+                caps: vec![capability(b"sasl", None)]
+            })
+        );
+        assert_eq!(
+            cap(b"CAP nick NAK :sasl\r\n"),
+            Some(Cap::Nak {
+                target: b"nick",
+                caps: vec![capability(b"sasl", None)]
+            })
+        );
+        assert_eq!(
+            cap(b"CAP nick NEW :batch=\r\n"),
+            Some(Cap::New {
+                target: b"nick",
+                caps: vec![capability(b"batch", Some(b""))]
+            })
+        );
+        assert_eq!(
+            cap(b"CAP nick DEL :batch\r\n"),
+            Some(Cap::Del {
+                target: b"nick",
+                caps: vec![capability(b"batch", None)]
+            })
+        );
+        assert_eq!(
+            cap(b"CAP nick LIST :\r\n"),
+            Some(Cap::List {
+                target: b"nick",
+                more: false,
+                caps: vec![]
+            })
+        );
+        assert_eq!(cap(b"CAP nick ACK * :sasl\r\n"), None);
+    }
+
+    #[test]
+    fn accumulate_ls() {
+        let mut acc = CapLsAccumulator::new();
+        assert_eq!(acc.feed(&cap(b"CAP * LS * :a b=1\r\n").unwrap()), None);
+        assert_eq!(acc.feed(&cap(b"CAP * ACK :a\r\n").unwrap()), None);
+        assert_eq!(
+            acc.feed(&cap(b"CAP * LS :c\r\n").unwrap()),
+            Some(vec![
+                (b"a".to_vec(), None),
+                (b"b".to_vec(), Some(b"1".to_vec())),
+                (b"c".to_vec(), None),
+            ])
+        );
+        assert_eq!(
+            acc.feed(&cap(b"CAP * LS :d\r\n").unwrap()),
+            Some(vec![(b"d".to_vec(), None)])
+        );
+    }
+}
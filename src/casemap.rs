@@ -0,0 +1,274 @@
+//! The case mappings of the nicknames and channel names
+//! Because of IRC's Scandinavian origin, the characters `{}|~` are
+//! considered to be the lower case equivalents of the characters `[]\^` by
+//! the default `rfc1459` mapping.
+//! ```
+//! use irc_parser::casemap::{eq_ignore_case, to_lower};
+//!
+//! assert_eq!(to_lower(b"Nick[Away]"), &b"nick{away}"[..]);
+//! assert!(eq_ignore_case(b"#Rust^", b"#rUST~"));
+//! ```
+
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::borrow::Cow;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+/// The case mapping advertised by the server in the CASEMAPPING token of
+/// RPL_ISUPPORT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CaseMapping {
+    /// `ascii`: only the letters have a case
+    Ascii,
+    /// `rfc1459`: `[]\^` are the upper case of `{}|~`
+    #[default]
+    Rfc1459,
+    /// `strict-rfc1459`: `[]\` are the upper case of `{}|`
+    StrictRfc1459,
+}
+
+impl CaseMapping {
+    /// Parse the value of the CASEMAPPING token
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        match value {
+            b"ascii" => Some(CaseMapping::Ascii),
+            b"rfc1459" => Some(CaseMapping::Rfc1459),
+            b"strict-rfc1459" => Some(CaseMapping::StrictRfc1459),
+            _ => None,
+        }
+    }
+
+    /// Find the CASEMAPPING token of a RPL_ISUPPORT (005)
+    /// `:server 005 me CHANTYPES=# CASEMAPPING=ascii :are supported`
+    pub fn from_message(message: &Message) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplISupport) {
+            return None;
+        }
+        let params = message.params.middles();
+        params
+            .iter()
+            .skip(1)
+            .find_map(|token| token.strip_prefix(b"CASEMAPPING="))
+            .and_then(Self::parse)
+    }
+
+    /// The last upper case byte, the lower case of a byte is 32 after it
+    fn last_upper(self) -> u8 {
+        match self {
+            CaseMapping::Ascii => b'Z',
+            CaseMapping::Rfc1459 => b'^',
+            CaseMapping::StrictRfc1459 => b']',
+        }
+    }
+
+    /// Return the lower case equivalent of a byte
+    pub fn lower(self, c: u8) -> u8 {
+        match c {
+            b'A'..=b'^' if c <= self.last_upper() => c + 32,
+            _ => c,
+        }
+    }
+
+    /// Return the upper case equivalent of a byte
+    pub fn upper(self, c: u8) -> u8 {
+        match c {
+            b'a'..=b'~' if c - 32 <= self.last_upper() => c - 32,
+            _ => c,
+        }
+    }
+
+    /// Lower the case of the input, only allocates if a byte changes
+    pub fn to_lower(self, input: &[u8]) -> Cow<'_, [u8]> {
+        map_bytes(input, |c| self.lower(c))
+    }
+
+    /// Upper the case of the input, only allocates if a byte changes
+    pub fn to_upper(self, input: &[u8]) -> Cow<'_, [u8]> {
+        map_bytes(input, |c| self.upper(c))
+    }
+
+    /// Return true if both inputs are equal once their case is lowered
+    pub fn eq_ignore_case(self, a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(&a, &b)| self.lower(a) == self.lower(b))
+    }
+}
+
+/// Lower the case of the input with the `rfc1459` case mapping
+pub fn to_lower(input: &[u8]) -> Cow<'_, [u8]> {
+    CaseMapping::Rfc1459.to_lower(input)
+}
+
+/// Upper the case of the input with the `rfc1459` case mapping
+pub fn to_upper(input: &[u8]) -> Cow<'_, [u8]> {
+    CaseMapping::Rfc1459.to_upper(input)
+}
+
+/// Compare both inputs with the `rfc1459` case mapping
+pub fn eq_ignore_case(a: &[u8], b: &[u8]) -> bool {
+    CaseMapping::Rfc1459.eq_ignore_case(a, b)
+}
+
+/// A nick or a channel name compared, hashed and ordered with a case mapping
+/// so it can be used as the key of a collection without lowering it first
+/// ```
+/// use irc_parser::casemap::{CaseMapping, IrcKey};
+/// use std::collections::HashMap;
+///
+/// let mut topics = HashMap::new();
+/// topics.insert(IrcKey::new(&b"#Rust[fr]"[..], CaseMapping::Rfc1459), "hello");
+/// let key = IrcKey::new(&b"#rust{FR}"[..], CaseMapping::Rfc1459);
+/// assert_eq!(topics.get(&key), Some(&"hello"));
+/// // the original case is kept
+/// assert_eq!(topics.keys().next().unwrap().as_bytes(), b"#Rust[fr]");
+/// ```
+#[derive(Debug, Clone)]
+pub struct IrcKey<'a> {
+    bytes: Cow<'a, [u8]>,
+    mapping: CaseMapping,
+}
+
+impl<'a> IrcKey<'a> {
+    pub fn new(bytes: impl Into<Cow<'a, [u8]>>, mapping: CaseMapping) -> Self {
+        IrcKey {
+            bytes: bytes.into(),
+            mapping,
+        }
+    }
+
+    /// Return the name as it was given
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn mapping(&self) -> CaseMapping {
+        self.mapping
+    }
+
+    pub fn into_owned(self) -> IrcKey<'static> {
+        IrcKey {
+            bytes: Cow::Owned(self.bytes.into_owned()),
+            mapping: self.mapping,
+        }
+    }
+
+    fn lowered(&self) -> impl Iterator<Item = u8> + '_ {
+        self.bytes.iter().map(move |&c| self.mapping.lower(c))
+    }
+}
+
+/// Use the `rfc1459` case mapping
+impl<'a> From<&'a [u8]> for IrcKey<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        IrcKey::new(bytes, CaseMapping::default())
+    }
+}
+
+/// Use the `rfc1459` case mapping
+impl<'a> From<&'a str> for IrcKey<'a> {
+    fn from(name: &'a str) -> Self {
+        IrcKey::new(name.as_bytes(), CaseMapping::default())
+    }
+}
+
+impl PartialEq for IrcKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lowered().eq(other.lowered())
+    }
+}
+
+impl Eq for IrcKey<'_> {}
+
+impl PartialOrd for IrcKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IrcKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.lowered().cmp(other.lowered())
+    }
+}
+
+impl Hash for IrcKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.bytes.len());
+        self.lowered().for_each(|c| state.write_u8(c));
+    }
+}
+
+fn map_bytes(input: &[u8], f: impl Fn(u8) -> u8) -> Cow<'_, [u8]> {
+    if input.iter().all(|&c| f(c) == c) {
+        return Cow::Borrowed(input);
+    }
+    Cow::Owned(input.iter().map(|&c| f(c)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn rfc1459_case() {
+        assert_eq!(to_lower(b"ABC[]\\^xyz"), &b"abc{}|~xyz"[..]);
+        assert_eq!(to_upper(b"abc{}|~XYZ"), &b"ABC[]\\^XYZ"[..]);
+        assert!(matches!(to_lower(b"nick_"), Cow::Borrowed(_)));
+        assert!(eq_ignore_case(b"[Nick]", b"{nICK}"));
+        assert!(!eq_ignore_case(b"nick", b"nick_"));
+        assert!(!eq_ignore_case(b"nick@", b"nick`"));
+    }
+
+    #[test]
+    fn case_mappings() {
+        let ascii = CaseMapping::Ascii;
+        let strict = CaseMapping::StrictRfc1459;
+        assert_eq!(ascii.to_lower(b"AZ[]\\^"), &b"az[]\\^"[..]);
+        assert_eq!(strict.to_lower(b"AZ[]\\^"), &b"az{}|^"[..]);
+        assert_eq!(strict.to_upper(b"az{}|~"), &b"AZ[]\\~"[..]);
+        assert!(!strict.eq_ignore_case(b"a^", b"a~"));
+        assert!(ascii.eq_ignore_case(b"Nick", b"nICK"));
+        assert!(!ascii.eq_ignore_case(b"[a]", b"{a}"));
+    }
+
+    #[test]
+    fn case_mapping_isupport() {
+        let message = parse_message(
+            b":irc.example.com 005 me CHANTYPES=# CASEMAPPING=ascii :are supported\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            CaseMapping::from_message(&message),
+            Some(CaseMapping::Ascii)
+        );
+        let message =
+            parse_message(b":irc.example.com 005 me CHANTYPES=# :are supported\r\n").unwrap();
+        assert_eq!(CaseMapping::from_message(&message), None);
+        assert_eq!(
+            CaseMapping::parse(b"strict-rfc1459"),
+            Some(CaseMapping::StrictRfc1459)
+        );
+        assert_eq!(CaseMapping::parse(b"rfc7613"), None);
+    }
+
+    #[test]
+    fn irc_keys() {
+        use alloc::collections::BTreeSet;
+
+        let mut keys = BTreeSet::new();
+        for &name in ["#b", "#A", "#a", "#[x]", "#{X}"].iter() {
+            keys.insert(IrcKey::from(name));
+        }
+        // the first of the equal keys is kept
+        let names: Vec<_> = keys.iter().map(IrcKey::as_bytes).collect();
+        assert_eq!(names, [&b"#A"[..], b"#b", b"#[x]"]);
+        assert!(keys.contains(&IrcKey::from("#{x}")));
+        let ascii = IrcKey::new(&b"#[x]"[..], CaseMapping::Ascii);
+        assert_ne!(ascii, IrcKey::new(&b"#{x}"[..], CaseMapping::Ascii));
+        assert_eq!(ascii.clone().into_owned(), ascii);
+    }
+}
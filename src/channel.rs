@@ -0,0 +1,175 @@
+use crate::config::ParserConfig;
+use alloc::string::String;
+use core::fmt;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1, take_while_m_n};
+use nom::combinator::{opt, recognize};
+use nom::sequence::{pair, preceded};
+use nom::{error::ErrorKind, Err, IResult};
+
+/// A valid channel name, prefix char included: `#rust`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Channel<'a>(&'a [u8]);
+
+impl<'a> Channel<'a> {
+    /// Return the whole channel name
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Return the prefix char: `#`, `&`, `+` or `!`
+    pub fn prefix(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Return the name without its prefix char
+    pub fn name(&self) -> &'a [u8] {
+        &self.0[1..]
+    }
+
+    /// Return true for `!` channels, whose name starts with a 5 chars id
+    pub fn is_safe(&self) -> bool {
+        self.prefix() == b'!'
+    }
+}
+
+impl<'a> fmt::Display for Channel<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(self.0))
+    }
+}
+
+/// <channel>    ::= ( '#' | '+' | ( '!' <channelid> ) | '&' ) <chanstring>
+///                  [ ':' <chanstring> ]
+/// Extract a channel of at most 50 chars as required by RFC 2812
+/// Return an error if the input does not start with a channel:
+/// Err( (input, ErrorKind) )
+/// Or if the channel is longer than 50 chars: Err( (input, TooLarge) )
+pub fn channel(input: &[u8]) -> IResult<&[u8], Channel<'_>> {
+    channel_max(50)(input)
+}
+
+/// Same as `channel` but allow channels of up to `max_len` chars
+pub fn channel_max(max_len: usize) -> impl Fn(&[u8]) -> IResult<&[u8], Channel<'_>> {
+    move |input: &[u8]| {
+        let (rest, channel) = recognize(pair(
+            alt((
+                tag("#"),
+                tag("+"),
+                tag("&"),
+                recognize(preceded(tag("!"), channelid)),
+            )),
+            pair(chanstring, opt(preceded(tag(":"), chanstring))),
+        ))(input)?;
+        if channel.len() > max_len {
+            return Err(Err::Error((input, ErrorKind::TooLarge)));
+        }
+        Ok((rest, Channel(channel)))
+    }
+}
+
+/// Same as `channel` with the channel prefixes and the maximum length of the
+/// configuration, a `!` prefix is still followed by a channel id
+pub fn channel_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Channel<'_>> {
+    let chantypes = config.chantypes.clone();
+    let max_len = config.max_channel_len;
+    move |input: &[u8]| {
+        let (after, prefix) = take_while_m_n(1, 1, |c| chantypes.contains(&c))(input)?;
+        let after = match prefix {
+            b"!" => channelid(after)?.0,
+            _ => after,
+        };
+        let (rest, _) = pair(chanstring, opt(preceded(tag(":"), chanstring)))(after)?;
+        let channel = &input[..input.len() - rest.len()];
+        if channel.len() > max_len {
+            return Err(Err::Error((input, ErrorKind::TooLarge)));
+        }
+        Ok((rest, Channel(channel)))
+    }
+}
+
+/// <chanstring> ::= <any octet except NUL, BELL, CR, LF, ' ', ',' and ':'>
+pub fn chanstring(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|c| !matches!(c, 0x00 | 0x07 | b'\r' | b'\n' | b' ' | b',' | b':'))(input)
+}
+
+/// <channelid>  ::= 5( 'A' ... 'Z' | '0' ... '9' )
+pub fn channelid(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while_m_n(5, 5, |c: u8| c.is_ascii_uppercase() || c.is_ascii_digit())(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(channel(empty), Err(Err::Error((empty, ErrorKind::Tag))));
+    }
+
+    #[test]
+    fn channel_prefixes() {
+        for c in &[&b"#rust"[..], b"&local", b"+modeless", b"!12ABCsafe"] {
+            assert_eq!(channel(c), Ok((&b""[..], Channel(c))));
+        }
+        let c: &[u8] = b"!ABCsafe";
+        assert!(channel(c).is_err());
+        let c: &[u8] = b"rust";
+        assert!(channel(c).is_err());
+        let c: &[u8] = b"#";
+        assert_eq!(
+            channel(c),
+            Err(Err::Error((&b""[..], ErrorKind::TakeWhile1)))
+        );
+    }
+
+    #[test]
+    fn channel_forbidden_chars() {
+        let c: &[u8] = b"#a,#b";
+        assert_eq!(channel(c), Ok((&b",#b"[..], Channel(&b"#a"[..]))));
+        let c: &[u8] = b"#a b";
+        assert_eq!(channel(c), Ok((&b" b"[..], Channel(&b"#a"[..]))));
+        let c: &[u8] = b"#a\x07b";
+        assert_eq!(channel(c), Ok((&b"\x07b"[..], Channel(&b"#a"[..]))));
+    }
+
+    #[test]
+    fn channel_mask() {
+        let c: &[u8] = b"#chan:*.fr";
+        assert_eq!(channel(c), Ok((&b""[..], Channel(c))));
+    }
+
+    #[test]
+    fn channel_too_long() {
+        let mut c = b"#".to_vec();
+        c.resize(51, b'a');
+        assert_eq!(channel(&c), Err(Err::Error((&c[..], ErrorKind::TooLarge))));
+        assert!(channel_max(200)(&c).is_ok());
+    }
+
+    #[test]
+    fn channel_accessors() {
+        let (_, c) = channel(b"!12ABCsafe").unwrap();
+        assert_eq!(c.prefix(), b'!');
+        assert_eq!(c.name(), &b"12ABCsafe"[..]);
+        assert!(c.is_safe());
+        assert_eq!(c.as_bytes(), &b"!12ABCsafe"[..]);
+    }
+
+    #[test]
+    fn channel_config() {
+        let config = ParserConfig::default().chantypes(b"#!").max_channel_len(10);
+        let channel = channel_with(&config);
+        assert_eq!(channel(b"#rust,").unwrap().0, b",");
+        assert!(channel(b"&rust").is_err());
+        assert!(channel(b"!rust").is_err());
+        assert_eq!(channel(b"!ABCDEchan").unwrap().1.name(), b"ABCDEchan");
+        assert_eq!(
+            channel(b"#rustaceans"),
+            Err(Err::Error((&b"#rustaceans"[..], ErrorKind::TooLarge)))
+        );
+        let config = ParserConfig::default().chantypes(b"");
+        assert!(channel_with(&config)(b"#rust").is_err());
+    }
+}
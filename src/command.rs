@@ -0,0 +1,284 @@
+use crate::config::ParserConfig;
+use crate::output::Output;
+#[cfg(feature = "std")]
+use crate::output::Writer;
+use crate::parser::{letter, letters, number, numbers};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use nom::branch::alt;
+use nom::combinator::{map, not};
+use nom::error::ErrorKind;
+use nom::{Err, IResult};
+
+macro_rules! commands {
+    ($($variant:ident => $name:literal,)*) => {
+        /// The command of a message
+        /// The commands of RFC 1459, RFC 2812 and the common IRCv3 extensions
+        /// have their own variant, anything else made of letters is `Unknown`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Command<'a> {
+            $(
+                #[doc = concat!("`", $name, "`")]
+                $variant,
+            )*
+            /// A numeric reply made of three digits: `001`
+            Numeric(u16),
+            /// Any other command made of letters, as it was received
+            Unknown(&'a [u8]),
+        }
+
+        impl<'a> Command<'a> {
+            /// Return the command named `word`, the comparison is case insensitive
+            pub fn from_word(word: &'a [u8]) -> Self {
+                $(
+                    if word.eq_ignore_ascii_case($name.as_bytes()) {
+                        return Command::$variant;
+                    }
+                )*
+                Command::Unknown(word)
+            }
+
+            /// Return the command as sent on the wire: `PRIVMSG` or `001`
+            /// Known commands are in uppercase, unknown ones keep their case.
+            pub fn as_str(&self) -> Cow<'a, str> {
+                match self {
+                    $(Command::$variant => Cow::Borrowed($name),)*
+                    Command::Numeric(code) => Cow::Owned(format!("{:03}", code)),
+                    Command::Unknown(word) => String::from_utf8_lossy(word),
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Pass => "PASS",
+    Nick => "NICK",
+    User => "USER",
+    Oper => "OPER",
+    Mode => "MODE",
+    Service => "SERVICE",
+    Quit => "QUIT",
+    Squit => "SQUIT",
+    Join => "JOIN",
+    Part => "PART",
+    Topic => "TOPIC",
+    Names => "NAMES",
+    List => "LIST",
+    Invite => "INVITE",
+    Kick => "KICK",
+    Privmsg => "PRIVMSG",
+    Notice => "NOTICE",
+    Motd => "MOTD",
+    Lusers => "LUSERS",
+    Version => "VERSION",
+    Stats => "STATS",
+    Links => "LINKS",
+    Time => "TIME",
+    Connect => "CONNECT",
+    Trace => "TRACE",
+    Admin => "ADMIN",
+    Info => "INFO",
+    Servlist => "SERVLIST",
+    Squery => "SQUERY",
+    Who => "WHO",
+    Whois => "WHOIS",
+    Whowas => "WHOWAS",
+    Kill => "KILL",
+    Ping => "PING",
+    Pong => "PONG",
+    Error => "ERROR",
+    Away => "AWAY",
+    Rehash => "REHASH",
+    Die => "DIE",
+    Restart => "RESTART",
+    Summon => "SUMMON",
+    Users => "USERS",
+    Wallops => "WALLOPS",
+    Userhost => "USERHOST",
+    Ison => "ISON",
+    Server => "SERVER",
+    Cap => "CAP",
+    Authenticate => "AUTHENTICATE",
+    Batch => "BATCH",
+    Tagmsg => "TAGMSG",
+    Account => "ACCOUNT",
+    Chghost => "CHGHOST",
+    Setname => "SETNAME",
+    Monitor => "MONITOR",
+    Watch => "WATCH",
+    Metadata => "METADATA",
+    Ack => "ACK",
+    Sid => "SID",
+    Uid => "UID",
+    Euid => "EUID",
+    Sjoin => "SJOIN",
+    Tmode => "TMODE",
+}
+
+impl<'a> Command<'a> {
+    /// Write the command as sent on the wire without allocating
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.encode(&mut Writer(writer))
+    }
+
+    pub(crate) fn encode<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        match self {
+            Command::Numeric(code) => {
+                // at least 3 digits: `001`
+                let mut digits = [b'0'; 5];
+                let mut code = *code;
+                let mut start = digits.len();
+                while code > 0 {
+                    start -= 1;
+                    digits[start] = b'0' + (code % 10) as u8;
+                    code /= 10;
+                }
+                out.put(&digits[start.min(2)..])
+            }
+            Command::Unknown(word) => out.put(word),
+            known => out.put(known.as_str().as_bytes()),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Command<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.as_str())
+    }
+}
+
+/// <command>  ::= <letter> { <letter> } | <number> <number> <number>
+/// The command must not be directly followed by a letter or a number, so
+/// mixed forms like `PR1VMSG` or numerics longer than three digits are
+/// rejected.
+/// Return an error if the input does not start with a command:
+/// Err( (input, ErrorKind) )
+pub fn command(input: &[u8]) -> IResult<&[u8], Command<'_>> {
+    command_with(&ParserConfig::default())(input)
+}
+
+/// Same as `command` but accept numerics of any length if the configuration
+/// is not strict about them
+pub fn command_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Command<'_>> {
+    let numeric = if config.strict_numerics {
+        numeric
+    } else {
+        any_numeric
+    };
+    move |input: &[u8]| {
+        let (input, command) = alt((word, numeric))(input)?;
+        let (input, _) = not(alt((letter, number)))(input)?;
+        Ok((input, command))
+    }
+}
+
+fn word(input: &[u8]) -> IResult<&[u8], Command<'_>> {
+    map(letters, Command::from_word)(input)
+}
+
+fn numeric(input: &[u8]) -> IResult<&[u8], Command<'_>> {
+    let len = input
+        .iter()
+        .take(3)
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    if len < 3 {
+        return Err(Err::Error((&input[len..], ErrorKind::Char)));
+    }
+    let code = input[..3]
+        .iter()
+        .fold(0, |acc, digit| acc * 10 + u16::from(digit - b'0'));
+    Ok((&input[3..], Command::Numeric(code)))
+}
+
+/// <number> { <number> }
+/// Return an error if the numeric does not fit in a `u16`:
+/// Err( (input, TooLarge) )
+fn any_numeric(input: &[u8]) -> IResult<&[u8], Command<'_>> {
+    let (rest, digits) = numbers(input)?;
+    let code = digits.iter().try_fold(0u16, |acc, digit| {
+        acc.checked_mul(10)?.checked_add(u16::from(digit - b'0'))
+    });
+    match code {
+        Some(code) => Ok((rest, Command::Numeric(code))),
+        None => Err(Err::Error((input, ErrorKind::TooLarge))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+    use nom::Err;
+
+    #[test]
+    fn command_empty() {
+        let empty: &[u8] = b"";
+        assert!(command(empty).is_err());
+    }
+
+    #[test]
+    fn command_word() {
+        let c: &[u8] = b"PRIVMSG #chan";
+        assert_eq!(command(c), Ok((&b" #chan"[..], Command::Privmsg)));
+        let c: &[u8] = b"quit";
+        assert_eq!(command(c), Ok((&b""[..], Command::Quit)));
+        let c: &[u8] = b"Foo";
+        assert_eq!(command(c), Ok((&b""[..], Command::Unknown(&b"Foo"[..]))));
+    }
+
+    #[test]
+    fn command_as_str() {
+        assert_eq!(Command::from_word(b"privmsg").as_str(), "PRIVMSG");
+        assert_eq!(Command::Numeric(1).as_str(), "001");
+        assert_eq!(Command::Unknown(b"Foo").as_str(), "Foo");
+    }
+
+    #[test]
+    fn command_numeric() {
+        let c: &[u8] = b"001 nick";
+        assert_eq!(command(c), Ok((&b" nick"[..], Command::Numeric(1))));
+        let c: &[u8] = b"433\r\n";
+        assert_eq!(command(c), Ok((&b"\r\n"[..], Command::Numeric(433))));
+    }
+
+    #[test]
+    fn command_mixed() {
+        let c: &[u8] = b"PR1VMSG";
+        assert_eq!(command(c), Err(Err::Error((&b"1VMSG"[..], ErrorKind::Not))));
+        let c: &[u8] = b"00A";
+        assert!(command(c).is_err());
+    }
+
+    #[test]
+    fn command_bad_numeric_length() {
+        let c: &[u8] = b"01 nick";
+        assert!(command(c).is_err());
+        let c: &[u8] = b"0012";
+        assert_eq!(command(c), Err(Err::Error((&b"2"[..], ErrorKind::Not))));
+    }
+
+    #[test]
+    fn command_lenient_numeric() {
+        let config = ParserConfig::default().strict_numerics(false);
+        let c: &[u8] = b"1 nick";
+        assert_eq!(
+            command_with(&config)(c),
+            Ok((&b" nick"[..], Command::Numeric(1)))
+        );
+        let c: &[u8] = b"0012";
+        assert_eq!(
+            command_with(&config)(c),
+            Ok((&b""[..], Command::Numeric(12)))
+        );
+        let c: &[u8] = b"65536";
+        assert_eq!(
+            command_with(&config)(c),
+            Err(Err::Error((c, ErrorKind::TooLarge)))
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::casemap::CaseMapping;
+use crate::error::ParseError;
+use crate::isupport::ISupport;
+use crate::scan::{find_byte, find_crlf};
+use alloc::vec::Vec;
+
+/// The RFC whose grammar is followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rfc {
+    /// The original grammar of RFC 1459
+    Rfc1459,
+    /// The updated grammar of RFC 2812: `_` and `|` are allowed in nicks and
+    /// there is at most 15 params
+    Rfc2812,
+}
+
+/// Options changing how the messages are parsed
+/// The default accepts IRCv3 tags and long nicks but otherwise follows
+/// RFC 1459, start from a preset and chain the setters to change it:
+/// ```
+/// use irc_parser::{parse_message_with, ParserConfig};
+///
+/// let config = ParserConfig::modern().max_line_len(4096);
+/// assert!(parse_message_with(b"PING :a\n", &config).is_ok());
+/// let config = ParserConfig::strict_rfc1459();
+/// assert!(parse_message_with(b"@a=b PING :a\r\n", &config).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// The maximum length of a line, crlf included and tags excluded
+    /// The RFC limit is 512 bytes but a lot of networks allow more.
+    pub max_line_len: usize,
+    /// The maximum length of the tags, the leading '@' and the space
+    /// following them included
+    /// The message-tags specification limit is 8191 bytes.
+    pub max_tags_len: usize,
+    /// Accept lines terminated by a lone LF in addition to CR LF
+    /// Some servers and clients forget the CR.
+    pub allow_bare_lf: bool,
+    /// The grammar used for nicks and params
+    pub rfc: Rfc,
+    /// Only accept numerics of exactly 3 digits, otherwise any number of
+    /// digits fitting in a `u16` is a numeric: `1` or `0001`
+    pub strict_numerics: bool,
+    /// Reject the messages whose parameters are not valid UTF-8 with
+    /// `ParseError::InvalidUtf8`
+    /// Only checked by the `parse_*` functions, not the nom parsers.
+    pub require_utf8: bool,
+    /// The maximum length of the nick of a prefix
+    /// The RFC limit is 9 chars but most networks allow longer nicks.
+    pub max_nick_len: usize,
+    /// The maximum length of a channel name, its prefix char included
+    /// The RFC 2812 limit is 50 chars.
+    pub max_channel_len: usize,
+    /// The chars starting a channel name
+    pub chantypes: Vec<u8>,
+    /// The membership prefixes allowed before a channel target to only
+    /// message some of its members: `@#chan`
+    pub statusmsg: Vec<u8>,
+    /// How the case of the nicks and channel names is ignored
+    pub casemapping: CaseMapping,
+}
+
+impl ParserConfig {
+    /// Follow RFC 1459 to the letter: crlf line endings, no tags, nicks of
+    /// at most 9 chars
+    pub fn strict_rfc1459() -> Self {
+        ParserConfig {
+            max_line_len: 512,
+            max_tags_len: 0,
+            allow_bare_lf: false,
+            rfc: Rfc::Rfc1459,
+            strict_numerics: true,
+            require_utf8: false,
+            max_nick_len: 9,
+            max_channel_len: 200,
+            chantypes: b"#&".to_vec(),
+            statusmsg: Vec::new(),
+            casemapping: CaseMapping::Rfc1459,
+        }
+    }
+
+    /// Follow RFC 2812: its nick grammar, at most 15 params, channels of at
+    /// most 50 chars and no tags
+    pub fn rfc2812() -> Self {
+        ParserConfig {
+            rfc: Rfc::Rfc2812,
+            max_channel_len: 50,
+            chantypes: b"#&+!".to_vec(),
+            ..Self::strict_rfc1459()
+        }
+    }
+
+    /// Accept what current servers send: IRCv3 tags, lone LF line endings,
+    /// the RFC 2812 nicks without length limit, and require UTF-8 as most
+    /// networks do
+    pub fn modern() -> Self {
+        ParserConfig {
+            max_line_len: 512,
+            max_tags_len: 8191,
+            allow_bare_lf: true,
+            rfc: Rfc::Rfc2812,
+            strict_numerics: true,
+            require_utf8: true,
+            max_nick_len: usize::MAX,
+            max_channel_len: 50,
+            chantypes: b"#&+!".to_vec(),
+            statusmsg: Vec::new(),
+            casemapping: CaseMapping::Rfc1459,
+        }
+    }
+
+    /// Adopt the limits advertised by the server in RPL_ISUPPORT on top of
+    /// the default configuration, see `isupport`
+    /// ```
+    /// use irc_parser::isupport::ISupport;
+    /// use irc_parser::ParserConfig;
+    ///
+    /// let mut isupport = ISupport::new();
+    /// isupport.apply(b"NICKLEN=30");
+    /// isupport.apply(b"CHANTYPES=#");
+    /// let config = ParserConfig::from_isupport(&isupport);
+    /// assert_eq!(config.max_nick_len, 30);
+    /// assert_eq!(config.chantypes, b"#");
+    /// ```
+    pub fn from_isupport(isupport: &ISupport) -> Self {
+        Self::default().isupport(isupport)
+    }
+
+    /// Replace the limits of the configuration by the ones advertised in the
+    /// NICKLEN, CHANNELLEN, CHANTYPES, STATUSMSG, LINELEN and CASEMAPPING
+    /// tokens, the others are kept
+    pub fn isupport(mut self, isupport: &ISupport) -> Self {
+        if let Some(nicklen) = isupport.nicklen {
+            self.max_nick_len = nicklen;
+        }
+        if let Some(channellen) = isupport.channellen {
+            self.max_channel_len = channellen;
+        }
+        if let Some(chantypes) = &isupport.chantypes {
+            self.chantypes = chantypes.clone();
+        }
+        if let Some(statusmsg) = &isupport.statusmsg {
+            self.statusmsg = statusmsg.clone();
+        }
+        if let Some(linelen) = isupport.linelen {
+            self.max_line_len = linelen;
+        }
+        if let Some(casemapping) = isupport.casemapping {
+            self.casemapping = casemapping;
+        }
+        self
+    }
+
+    pub fn max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    pub fn max_tags_len(mut self, max_tags_len: usize) -> Self {
+        self.max_tags_len = max_tags_len;
+        self
+    }
+
+    pub fn allow_bare_lf(mut self, allow_bare_lf: bool) -> Self {
+        self.allow_bare_lf = allow_bare_lf;
+        self
+    }
+
+    pub fn rfc(mut self, rfc: Rfc) -> Self {
+        self.rfc = rfc;
+        self
+    }
+
+    pub fn strict_numerics(mut self, strict_numerics: bool) -> Self {
+        self.strict_numerics = strict_numerics;
+        self
+    }
+
+    pub fn require_utf8(mut self, require_utf8: bool) -> Self {
+        self.require_utf8 = require_utf8;
+        self
+    }
+
+    pub fn max_nick_len(mut self, max_nick_len: usize) -> Self {
+        self.max_nick_len = max_nick_len;
+        self
+    }
+
+    pub fn max_channel_len(mut self, max_channel_len: usize) -> Self {
+        self.max_channel_len = max_channel_len;
+        self
+    }
+
+    pub fn chantypes(mut self, chantypes: &[u8]) -> Self {
+        self.chantypes = chantypes.to_vec();
+        self
+    }
+
+    pub fn statusmsg(mut self, statusmsg: &[u8]) -> Self {
+        self.statusmsg = statusmsg.to_vec();
+        self
+    }
+
+    pub fn casemapping(mut self, casemapping: CaseMapping) -> Self {
+        self.casemapping = casemapping;
+        self
+    }
+
+    /// Return the length of the first line of the input, line ending
+    /// included, or `None` if the line is not terminated
+    pub(crate) fn line_len(&self, input: &[u8]) -> Option<usize> {
+        if self.allow_bare_lf {
+            find_byte(b'\n', input).map(|pos| pos + 1)
+        } else {
+            find_crlf(input).map(|pos| pos + 2)
+        }
+    }
+
+    /// Return the longest a line can be, tags and line ending included
+    pub(crate) fn max_len(&self) -> usize {
+        self.max_tags_len + self.max_line_len
+    }
+
+    /// Return the error of a line of `len` bytes going over the limits, of
+    /// which only the start `line` was kept
+    pub(crate) fn too_long(&self, line: &[u8], len: usize) -> ParseError {
+        let tags_len = self.tags_len(line);
+        if tags_len > self.max_tags_len {
+            ParseError::TagsTooLong {
+                len: tags_len,
+                max: self.max_tags_len,
+            }
+        } else {
+            ParseError::LineTooLong {
+                offset: tags_len,
+                len: len - tags_len,
+                max: self.max_line_len,
+            }
+        }
+    }
+
+    /// Return the length of the tags of a line, the leading '@' and the
+    /// following space included, or 0 if there is no tag
+    pub(crate) fn tags_len(&self, line: &[u8]) -> usize {
+        if line.first() != Some(&b'@') {
+            return 0;
+        }
+        match find_byte(b' ', line) {
+            Some(pos) => pos + 1,
+            None => line.len(),
+        }
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            max_line_len: 512,
+            max_tags_len: 8191,
+            allow_bare_lf: false,
+            rfc: Rfc::Rfc1459,
+            strict_numerics: true,
+            require_utf8: false,
+            max_nick_len: usize::MAX,
+            max_channel_len: 50,
+            chantypes: b"#&+!".to_vec(),
+            statusmsg: Vec::new(),
+            casemapping: CaseMapping::Rfc1459,
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! Client-To-Client Protocol, the queries and replies hidden in the text of
+//! a `PRIVMSG` or a `NOTICE`: `\x01ACTION waves\x01`
+
+use crate::builder::MessageBuilder;
+use crate::command::Command;
+use crate::error::BuildError;
+use crate::message::Message;
+use crate::output::to_vec;
+use crate::owned::OwnedMessage;
+use crate::prefix::Prefix;
+use crate::tags::ServerTime;
+use crate::typed::{Notice, Privmsg};
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::vec::Vec;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::combinator::{all_consuming, opt};
+use nom::sequence::preceded;
+use nom::IResult;
+
+/// The delimiter wrapping a CTCP message
+pub const DELIM: u8 = 0x01;
+
+/// The low-level quote char, M-QUOTE
+pub const M_QUOTE: u8 = 0x10;
+
+/// The CTCP-level quote char, X-QUOTE
+pub const X_QUOTE: u8 = b'\\';
+
+/// The command of a CTCP message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtcpCommand<'a> {
+    /// `ACTION`: a `/me` message
+    Action,
+    /// `CLIENTINFO`: the list of the supported CTCP commands
+    Clientinfo,
+    /// `DCC`: a direct connection request
+    Dcc,
+    /// `ERRMSG`: an error reply
+    Errmsg,
+    /// `FINGER`: information about the user
+    Finger,
+    /// `PING`: a latency check, the reply echoes the params
+    Ping,
+    /// `SOURCE`: where to get the client
+    Source,
+    /// `TIME`: the local time of the client
+    Time,
+    /// `USERINFO`: a string set by the user
+    Userinfo,
+    /// `VERSION`: the name and version of the client
+    Version,
+    /// Any other command, as it was received
+    Unknown(&'a [u8]),
+}
+
+impl<'a> CtcpCommand<'a> {
+    /// Return the command named `word`, the comparison is case insensitive
+    pub fn from_word(word: &'a [u8]) -> Self {
+        const COMMANDS: [(&[u8], CtcpCommand<'static>); 10] = [
+            (b"ACTION", CtcpCommand::Action),
+            (b"CLIENTINFO", CtcpCommand::Clientinfo),
+            (b"DCC", CtcpCommand::Dcc),
+            (b"ERRMSG", CtcpCommand::Errmsg),
+            (b"FINGER", CtcpCommand::Finger),
+            (b"PING", CtcpCommand::Ping),
+            (b"SOURCE", CtcpCommand::Source),
+            (b"TIME", CtcpCommand::Time),
+            (b"USERINFO", CtcpCommand::Userinfo),
+            (b"VERSION", CtcpCommand::Version),
+        ];
+        COMMANDS
+            .iter()
+            .find(|(name, _)| word.eq_ignore_ascii_case(name))
+            .map_or(CtcpCommand::Unknown(word), |&(_, command)| command)
+    }
+
+    /// Return the command as sent, known commands are in uppercase
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            CtcpCommand::Action => b"ACTION",
+            CtcpCommand::Clientinfo => b"CLIENTINFO",
+            CtcpCommand::Dcc => b"DCC",
+            CtcpCommand::Errmsg => b"ERRMSG",
+            CtcpCommand::Finger => b"FINGER",
+            CtcpCommand::Ping => b"PING",
+            CtcpCommand::Source => b"SOURCE",
+            CtcpCommand::Time => b"TIME",
+            CtcpCommand::Userinfo => b"USERINFO",
+            CtcpCommand::Version => b"VERSION",
+            CtcpCommand::Unknown(word) => word,
+        }
+    }
+}
+
+/// A CTCP query, in a `PRIVMSG`, or reply, in a `NOTICE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ctcp<'a> {
+    pub command: CtcpCommand<'a>,
+    /// Everything after the command and its space, the text of an `ACTION`
+    /// or the space separated arguments of the other commands
+    pub params: &'a [u8],
+}
+
+impl<'a> Ctcp<'a> {
+    /// Return the space separated arguments
+    pub fn args(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.params
+            .split(|&c| c == b' ')
+            .filter(|arg| !arg.is_empty())
+    }
+
+    /// Return the params with both levels of quoting removed
+    /// Only allocates if the params contain a quote char.
+    pub fn dequoted_params(&self) -> Cow<'a, [u8]> {
+        match low_level_dequote(self.params) {
+            Cow::Borrowed(params) => ctcp_dequote(params),
+            Cow::Owned(params) => Cow::Owned(ctcp_dequote(&params).into_owned()),
+        }
+    }
+
+    /// Same as `to_bytes` but quote the params first, so they can contain
+    /// any byte, NUL, CR, LF and `\x01` included
+    pub fn to_quoted_bytes(&self) -> Vec<u8> {
+        let params = ctcp_quote(self.params);
+        let ctcp = Ctcp {
+            command: self.command,
+            params: &params,
+        };
+        low_level_quote(&ctcp.to_bytes()).into_owned()
+    }
+
+    /// Serialize the message as the text of a `PRIVMSG` or a `NOTICE`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_vec(|bytes| {
+            bytes.push(DELIM);
+            bytes.extend_from_slice(self.command.as_bytes());
+            if !self.params.is_empty() {
+                bytes.push(b' ');
+                bytes.extend_from_slice(self.params);
+            }
+            bytes.push(DELIM);
+            Ok(())
+        })
+    }
+}
+
+/// <ctcp>    ::= 0x01 <command> [ ' ' <params> ] [ 0x01 ]
+/// The final delimiter may be omitted.
+/// Return `None` if the text is not a CTCP message.
+pub fn ctcp(text: &[u8]) -> Option<Ctcp<'_>> {
+    all_consuming(ctcp_message)(text).ok().map(|(_, ctcp)| ctcp)
+}
+
+fn ctcp_message(input: &[u8]) -> IResult<&[u8], Ctcp<'_>> {
+    let (input, _) = tag([DELIM])(input)?;
+    let (input, command) = take_while1(|c| c != b' ' && c != DELIM)(input)?;
+    let (input, params) = opt(preceded(tag(" "), take_while(|c| c != DELIM)))(input)?;
+    let (input, _) = opt(tag([DELIM]))(input)?;
+    Ok((
+        input,
+        Ctcp {
+            command: CtcpCommand::from_word(command),
+            params: params.unwrap_or_default(),
+        },
+    ))
+}
+
+/// Quote the NUL, CR, LF and M-QUOTE chars of a message text with M-QUOTE:
+/// NUL becomes `\x100`, LF `\x10n`, CR `\x10r` and M-QUOTE `\x10\x10`
+/// Only allocates if the text contains one of them.
+pub fn low_level_quote(text: &[u8]) -> Cow<'_, [u8]> {
+    quote(text, M_QUOTE, |c| match c {
+        0x00 => Some(b'0'),
+        b'\n' => Some(b'n'),
+        b'\r' => Some(b'r'),
+        M_QUOTE => Some(M_QUOTE),
+        _ => None,
+    })
+}
+
+/// The reverse of `low_level_quote`
+/// A M-QUOTE followed by any other char is dropped, a trailing one too.
+pub fn low_level_dequote(text: &[u8]) -> Cow<'_, [u8]> {
+    dequote(text, M_QUOTE, |c| match c {
+        b'0' => 0x00,
+        b'n' => b'\n',
+        b'r' => b'\r',
+        c => c,
+    })
+}
+
+/// Quote the `\x01` and X-QUOTE chars of CTCP params with X-QUOTE:
+/// `\x01` becomes `\a` and `\` becomes `\\`
+/// Only allocates if the params contain one of them.
+pub fn ctcp_quote(params: &[u8]) -> Cow<'_, [u8]> {
+    quote(params, X_QUOTE, |c| match c {
+        DELIM => Some(b'a'),
+        X_QUOTE => Some(X_QUOTE),
+        _ => None,
+    })
+}
+
+/// The reverse of `ctcp_quote`
+/// A X-QUOTE followed by any other char is dropped, a trailing one too.
+pub fn ctcp_dequote(params: &[u8]) -> Cow<'_, [u8]> {
+    dequote(params, X_QUOTE, |c| match c {
+        b'a' => DELIM,
+        c => c,
+    })
+}
+
+/// Replace every char for which `sequence` returns a char by `quote`
+/// followed by this char
+fn quote(value: &[u8], quote: u8, sequence: fn(u8) -> Option<u8>) -> Cow<'_, [u8]> {
+    if !value.iter().any(|&c| sequence(c).is_some()) {
+        return Cow::Borrowed(value);
+    }
+    let mut quoted = Vec::with_capacity(value.len() + 8);
+    for &c in value {
+        match sequence(c) {
+            Some(c) => quoted.extend_from_slice(&[quote, c]),
+            None => quoted.push(c),
+        }
+    }
+    Cow::Owned(quoted)
+}
+
+/// Replace every `quote` and the char following it by `unquote` of this char
+fn dequote(value: &[u8], quote: u8, unquote: fn(u8) -> u8) -> Cow<'_, [u8]> {
+    if !value.contains(&quote) {
+        return Cow::Borrowed(value);
+    }
+    let mut dequoted = Vec::with_capacity(value.len());
+    let mut chars = value.iter();
+    while let Some(&c) = chars.next() {
+        if c != quote {
+            dequoted.push(c);
+        } else if let Some(&c) = chars.next() {
+            dequoted.push(unquote(c));
+        }
+    }
+    Cow::Owned(dequoted)
+}
+
+impl<'a> Privmsg<'a> {
+    /// Return the CTCP query carried by the text
+    pub fn as_ctcp(&self) -> Option<Ctcp<'a>> {
+        ctcp(self.text)
+    }
+}
+
+impl<'a> Notice<'a> {
+    /// Return the CTCP reply carried by the text
+    pub fn as_ctcp(&self) -> Option<Ctcp<'a>> {
+        ctcp(self.text)
+    }
+}
+
+/// The `NOTICE` answering a CTCP query, sent back to the nick of its prefix
+/// ```
+/// use irc_parser::ctcp::CtcpReply;
+/// use irc_parser::parse_message;
+///
+/// let request = parse_message(b":nick!u@h PRIVMSG me :\x01PING 1234\x01\r\n").unwrap();
+/// let reply = CtcpReply::ping(&request).unwrap().to_message().unwrap();
+/// assert_eq!(reply.to_bytes(), b"NOTICE nick :\x01PING 1234\x01\r\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtcpReply<'a> {
+    pub target: &'a [u8],
+    pub command: CtcpCommand<'a>,
+    pub params: Vec<u8>,
+}
+
+impl<'a> CtcpReply<'a> {
+    /// Answer a `VERSION` query with `version`, the name and version of the
+    /// client
+    /// Return `None` if `request` is not a `VERSION` query sent by a user,
+    /// the same goes for the other constructors.
+    pub fn version(request: &Message<'a>, version: &[u8]) -> Option<Self> {
+        Self::answer(request, CtcpCommand::Version, version.to_vec())
+    }
+
+    /// Answer a `PING` query by echoing its params
+    pub fn ping(request: &Message<'a>) -> Option<Self> {
+        let params = request.as_ctcp()?.params.to_vec();
+        Self::answer(request, CtcpCommand::Ping, params)
+    }
+
+    /// Answer a `TIME` query with `time` formatted like `ctime`:
+    /// `Thu Feb 28 19:30:01 2019 UTC`
+    pub fn time(request: &Message<'a>, time: ServerTime) -> Option<Self> {
+        const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        // the unix epoch is a thursday
+        let days = time.unix_millis().div_euclid(86_400_000);
+        let params = format!(
+            "{} {} {:02} {:02}:{:02}:{:02} {} UTC",
+            DAYS[days.rem_euclid(7) as usize],
+            MONTHS[usize::from(time.month.clamp(1, 12)) - 1],
+            time.day,
+            time.hour,
+            time.minute,
+            time.second,
+            time.year
+        );
+        Self::answer(request, CtcpCommand::Time, params.into_bytes())
+    }
+
+    /// Answer a `CLIENTINFO` query with the list of the supported commands
+    pub fn clientinfo(request: &Message<'a>, commands: &[CtcpCommand<'_>]) -> Option<Self> {
+        let params = commands
+            .iter()
+            .map(CtcpCommand::as_bytes)
+            .collect::<Vec<_>>()
+            .join(&b' ');
+        Self::answer(request, CtcpCommand::Clientinfo, params)
+    }
+
+    fn answer(request: &Message<'a>, command: CtcpCommand<'a>, params: Vec<u8>) -> Option<Self> {
+        match (request.command, request.prefix, request.as_ctcp()) {
+            (Command::Privmsg, Some(Prefix::User { nick, .. }), Some(query))
+                if query.command == command =>
+            {
+                Some(CtcpReply {
+                    target: nick,
+                    command,
+                    params,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Build the `NOTICE`, fail if the params contain a NUL, CR or LF
+    pub fn to_message(&self) -> Result<OwnedMessage, BuildError> {
+        let text = Ctcp {
+            command: self.command,
+            params: &self.params,
+        }
+        .to_bytes();
+        let message = MessageBuilder::new(Command::Notice)
+            .param(self.target)
+            .trailing(&text)
+            .build()?;
+        Ok(message.into_owned())
+    }
+}
+
+impl Message<'_> {
+    /// Build a `PRIVMSG` carrying a CTCP `ACTION`, what `/me` sends
+    /// ```
+    /// use irc_parser::Message;
+    ///
+    /// let action = Message::action(b"#rust", b"waves").unwrap();
+    /// assert_eq!(action.to_bytes(), b"PRIVMSG #rust :\x01ACTION waves\x01\r\n");
+    /// ```
+    pub fn action(target: &[u8], text: &[u8]) -> Result<OwnedMessage, BuildError> {
+        let text = Ctcp {
+            command: CtcpCommand::Action,
+            params: text,
+        }
+        .to_bytes();
+        let message = MessageBuilder::new(Command::Privmsg)
+            .param(target)
+            .trailing(&text)
+            .build()?;
+        Ok(message.into_owned())
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Return the CTCP query or reply of a `PRIVMSG` or a `NOTICE`
+    pub fn as_ctcp(&self) -> Option<Ctcp<'a>> {
+        match (self.command, &self.params[..]) {
+            (Command::Privmsg, &[_, text]) | (Command::Notice, &[_, text]) => ctcp(text),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn ctcp_action() {
+        let m = parse_message(b":nick PRIVMSG #chan :\x01ACTION waves  hello\x01\r\n").unwrap();
+        let ctcp = m.as_ctcp().unwrap();
+        assert_eq!(ctcp.command, CtcpCommand::Action);
+        assert_eq!(ctcp.params, b"waves  hello");
+        assert_eq!(ctcp.to_bytes(), b"\x01ACTION waves  hello\x01");
+        let privmsg = Privmsg {
+            target: m.params[0],
+            text: m.params[1],
+        };
+        assert_eq!(privmsg.as_ctcp(), Some(ctcp));
+    }
+
+    #[test]
+    fn ctcp_reply() {
+        let m = parse_message(b"NOTICE nick :\x01PING 1234 5678\r\n").unwrap();
+        let reply = m.as_ctcp().unwrap();
+        assert_eq!(reply.command, CtcpCommand::Ping);
+        let args: Vec<_> = reply.args().collect();
+        assert_eq!(args, [&b"1234"[..], b"5678"]);
+        let version = ctcp(b"\x01version\x01").unwrap();
+        assert_eq!(version.command, CtcpCommand::Version);
+        assert_eq!(version.params, b"");
+        assert_eq!(
+            ctcp(b"\x01FOO bar\x01").unwrap().command,
+            CtcpCommand::Unknown(b"FOO")
+        );
+    }
+
+    #[test]
+    fn ctcp_invalid() {
+        assert_eq!(ctcp(b"hello"), None);
+        assert_eq!(ctcp(b"\x01\x01"), None);
+        assert_eq!(ctcp(b"\x01ACTION a\x01 b"), None);
+        let m = parse_message(b"JOIN :\x01ACTION\x01\r\n").unwrap();
+        assert_eq!(m.as_ctcp(), None);
+    }
+
+    #[test]
+    fn ctcp_quoting() {
+        let text: &[u8] = b"a\0b\r\nc\x10d";
+        let quoted = low_level_quote(text);
+        assert_eq!(&quoted[..], b"a\x100b\x10r\x10nc\x10\x10d");
+        assert_eq!(low_level_dequote(&quoted), text);
+        assert_eq!(low_level_dequote(b"\x10x\x10"), &b"x"[..]);
+        let params: &[u8] = b"a\x01b\\c";
+        let quoted = ctcp_quote(params);
+        assert_eq!(&quoted[..], b"a\\ab\\\\c");
+        assert_eq!(ctcp_dequote(&quoted), params);
+        assert!(matches!(ctcp_quote(b"plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn ctcp_quoted_roundtrip() {
+        let payload: &[u8] = b"\x01\0\r\n\\\x10 end";
+        let ctcp = Ctcp {
+            command: CtcpCommand::Unknown(b"DATA"),
+            params: payload,
+        };
+        let text = ctcp.to_quoted_bytes();
+        assert!(!text.iter().any(|c| matches!(c, 0x00 | b'\r' | b'\n')));
+        let mut line = b"PRIVMSG nick :".to_vec();
+        line.extend_from_slice(&text);
+        line.extend_from_slice(b"\r\n");
+        let m = parse_message(&line).unwrap();
+        let parsed = m.as_ctcp().unwrap();
+        assert_eq!(parsed.command, CtcpCommand::Unknown(b"DATA"));
+        assert_eq!(parsed.dequoted_params(), payload);
+    }
+
+    #[test]
+    fn ctcp_build_action() {
+        let action = Message::action(b"nick", b"waves  back").unwrap();
+        let m = action.as_message();
+        assert_eq!(m.command, Command::Privmsg);
+        assert_eq!(m.params[0], b"nick");
+        assert_eq!(m.as_ctcp().unwrap().params, b"waves  back");
+        assert_eq!(
+            Message::action(b"#a b", b"waves"),
+            Err(BuildError::InvalidParam { index: 0 })
+        );
+    }
+
+    #[test]
+    fn ctcp_replies() {
+        let request = parse_message(b":nick!u@h PRIVMSG me :\x01VERSION\x01\r\n").unwrap();
+        let reply = CtcpReply::version(&request, b"irc_parser 0.1").unwrap();
+        assert_eq!(
+            reply.to_message().unwrap().to_bytes(),
+            b"NOTICE nick :\x01VERSION irc_parser 0.1\x01\r\n"
+        );
+        assert_eq!(CtcpReply::ping(&request), None);
+        assert_eq!(
+            CtcpReply::version(&request, b"bad\r\n")
+                .unwrap()
+                .to_message(),
+            Err(BuildError::InvalidParam { index: 1 })
+        );
+
+        let request = parse_message(b":nick PRIVMSG me :\x01TIME\x01\r\n").unwrap();
+        let time = ServerTime::parse(b"2019-02-28T19:30:01.727Z").unwrap();
+        let reply = CtcpReply::time(&request, time).unwrap();
+        assert_eq!(reply.params, b"Thu Feb 28 19:30:01 2019 UTC");
+
+        let request = parse_message(b":nick PRIVMSG me :\x01CLIENTINFO\x01\r\n").unwrap();
+        let commands = [
+            CtcpCommand::Action,
+            CtcpCommand::Ping,
+            CtcpCommand::Unknown(b"X"),
+        ];
+        let reply = CtcpReply::clientinfo(&request, &commands).unwrap();
+        assert_eq!(reply.params, b"ACTION PING X");
+
+        // a server can't be answered and a reply is not a query
+        let request = parse_message(b":irc.example.com PRIVMSG me :\x01TIME\x01\r\n").unwrap();
+        assert_eq!(CtcpReply::time(&request, time), None);
+        let request = parse_message(b":nick NOTICE me :\x01TIME\x01\r\n").unwrap();
+        assert_eq!(CtcpReply::time(&request, time), None);
+    }
+}
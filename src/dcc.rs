@@ -0,0 +1,460 @@
+//! Direct Client-to-Client offers, sent as the params of a CTCP `DCC`
+//! A passive offer has a port 0 and a token, the receiver answers with the
+//! same offer but its own address and port. A transfer is resumed by
+//! answering an offer with `RESUME`, which the sender confirms with `ACCEPT`.
+
+use crate::ctcp::{Ctcp, CtcpCommand};
+use crate::parser::{numbers, space};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, SocketAddr};
+use core::str::{self, FromStr};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_while, take_while1};
+use nom::combinator::{all_consuming, map_opt, opt, verify};
+use nom::sequence::{delimited, preceded, terminated};
+use nom::IResult;
+
+/// A DCC offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dcc<'a> {
+    Send(DccSend<'a>),
+    Chat(DccChat<'a>),
+    /// The receiver of a `SEND` asks to start the transfer at `position`
+    Resume(DccResume<'a>),
+    /// The sender agrees to a `RESUME`
+    Accept(DccResume<'a>),
+}
+
+impl<'a> Dcc<'a> {
+    /// Serialize the offer as the text of a `PRIVMSG`: `\x01DCC SEND ...\x01`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = self.to_params();
+        Ctcp {
+            command: CtcpCommand::Dcc,
+            params: &params,
+        }
+        .to_bytes()
+    }
+
+    /// Serialize the params of the CTCP `DCC`
+    /// The IPv4 addresses are sent as an integer and the filenames containing
+    /// a space are quoted.
+    pub fn to_params(&self) -> Vec<u8> {
+        let mut params = Vec::new();
+        match self {
+            Dcc::Send(send) => {
+                params.extend_from_slice(b"SEND ");
+                push_filename(&mut params, send.filename);
+                push_addr(&mut params, send.addr, send.port);
+                if let Some(size) = send.size {
+                    params.extend_from_slice(format!(" {}", size).as_bytes());
+                }
+                push_token(&mut params, send.token);
+            }
+            Dcc::Chat(chat) => {
+                params.extend_from_slice(b"CHAT chat");
+                push_addr(&mut params, chat.addr, chat.port);
+                push_token(&mut params, chat.token);
+            }
+            Dcc::Resume(resume) | Dcc::Accept(resume) => {
+                params.extend_from_slice(match self {
+                    Dcc::Resume(_) => b"RESUME ",
+                    _ => b"ACCEPT ",
+                });
+                push_filename(&mut params, resume.filename);
+                let position = format!(" {} {}", resume.port, resume.position);
+                params.extend_from_slice(position.as_bytes());
+                push_token(&mut params, resume.token);
+            }
+        }
+        params
+    }
+}
+
+/// `DCC RESUME <filename> <port> <position> [<token>]` and
+/// `DCC ACCEPT <filename> <port> <position> [<token>]`
+/// The port, or the token of a passive offer, identifies the transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DccResume<'a> {
+    pub filename: &'a [u8],
+    pub port: u16,
+    /// The number of bytes already received
+    pub position: u64,
+    pub token: Option<&'a [u8]>,
+}
+
+impl<'a> DccResume<'a> {
+    /// Build the `ACCEPT` answering this `RESUME`
+    pub fn accept(&self) -> Dcc<'a> {
+        Dcc::Accept(*self)
+    }
+}
+
+/// `DCC SEND <filename> <ip> <port> [<size> [<token>]]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DccSend<'a> {
+    /// The name of the file, without the quotes if it had some
+    pub filename: &'a [u8],
+    pub addr: IpAddr,
+    pub port: u16,
+    /// The size of the file in bytes
+    pub size: Option<u64>,
+    /// The token of a passive offer
+    pub token: Option<&'a [u8]>,
+}
+
+impl<'a> DccSend<'a> {
+    /// Return true if the sender can't accept connections and asks the
+    /// receiver to listen instead: the port is 0 and there is a token
+    pub fn is_passive(&self) -> bool {
+        self.port == 0 && self.token.is_some()
+    }
+
+    /// Return the address to connect to
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.addr, self.port)
+    }
+
+    /// Build the answer to a passive offer, telling the sender where to
+    /// connect
+    /// Return `None` if the offer is not passive.
+    pub fn passive_reply(&self, addr: IpAddr, port: u16) -> Option<Dcc<'a>> {
+        if !self.is_passive() {
+            return None;
+        }
+        Some(Dcc::Send(DccSend {
+            addr,
+            port,
+            ..*self
+        }))
+    }
+
+    /// Build the `RESUME` asking to start the transfer at `position`
+    pub fn resume(&self, position: u64) -> Dcc<'a> {
+        Dcc::Resume(DccResume {
+            filename: self.filename,
+            port: self.port,
+            position,
+            token: self.token,
+        })
+    }
+}
+
+/// `DCC CHAT chat <ip> <port> [<token>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DccChat<'a> {
+    pub addr: IpAddr,
+    pub port: u16,
+    /// The token of a passive offer
+    pub token: Option<&'a [u8]>,
+}
+
+impl<'a> DccChat<'a> {
+    /// Return the address to connect to
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.addr, self.port)
+    }
+
+    /// Same as `DccSend::is_passive`
+    pub fn is_passive(&self) -> bool {
+        self.port == 0 && self.token.is_some()
+    }
+}
+
+impl<'a> Ctcp<'a> {
+    /// Return the offer carried by a CTCP `DCC`
+    pub fn as_dcc(&self) -> Option<Dcc<'a>> {
+        match self.command {
+            CtcpCommand::Dcc => dcc(self.params),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the params of a CTCP `DCC`
+/// Return `None` if they are not a `SEND` or `CHAT` offer, nor a `RESUME` or
+/// `ACCEPT` of a transfer.
+pub fn dcc(params: &[u8]) -> Option<Dcc<'_>> {
+    all_consuming(terminated(
+        alt((send, chat, resume)),
+        take_while(|c| c == b' '),
+    ))(params)
+    .ok()
+    .map(|(_, dcc)| dcc)
+}
+
+fn send(input: &[u8]) -> IResult<&[u8], Dcc<'_>> {
+    let (input, _) = terminated(tag_no_case("SEND"), space)(input)?;
+    let (input, filename) = terminated(filename, space)(input)?;
+    let (input, addr) = terminated(addr, space)(input)?;
+    let (input, port) = port(input)?;
+    let (input, size) = opt(preceded(space, map_opt(numbers, parse_number)))(input)?;
+    let (input, token) = match size {
+        Some(_) => opt(preceded(space, word))(input)?,
+        None => (input, None),
+    };
+    Ok((
+        input,
+        Dcc::Send(DccSend {
+            filename,
+            addr,
+            port,
+            size,
+            token,
+        }),
+    ))
+}
+
+fn chat(input: &[u8]) -> IResult<&[u8], Dcc<'_>> {
+    let (input, _) = terminated(tag_no_case("CHAT"), space)(input)?;
+    let (input, _) = terminated(tag_no_case("chat"), space)(input)?;
+    let (input, addr) = terminated(addr, space)(input)?;
+    let (input, port) = port(input)?;
+    let (input, token) = opt(preceded(space, word))(input)?;
+    Ok((input, Dcc::Chat(DccChat { addr, port, token })))
+}
+
+fn resume(input: &[u8]) -> IResult<&[u8], Dcc<'_>> {
+    let (input, kind) =
+        terminated(alt((tag_no_case("RESUME"), tag_no_case("ACCEPT"))), space)(input)?;
+    let (input, filename) = terminated(filename, space)(input)?;
+    let (input, port) = terminated(port, space)(input)?;
+    let (input, position) = map_opt(numbers, parse_number)(input)?;
+    let (input, token) = opt(preceded(space, word))(input)?;
+    let resume = DccResume {
+        filename,
+        port,
+        position,
+        token,
+    };
+    if kind.eq_ignore_ascii_case(b"RESUME") {
+        Ok((input, Dcc::Resume(resume)))
+    } else {
+        Ok((input, Dcc::Accept(resume)))
+    }
+}
+
+fn push_filename(params: &mut Vec<u8>, filename: &[u8]) {
+    if filename.contains(&b' ') {
+        params.push(b'"');
+        params.extend_from_slice(filename);
+        params.push(b'"');
+    } else {
+        params.extend_from_slice(filename);
+    }
+}
+
+fn push_addr(params: &mut Vec<u8>, addr: IpAddr, port: u16) {
+    params.push(b' ');
+    params.extend_from_slice(format_socket_addr(SocketAddr::new(addr, port)).as_bytes());
+}
+
+fn push_token(params: &mut Vec<u8>, token: Option<&[u8]>) {
+    if let Some(token) = token {
+        params.push(b' ');
+        params.extend_from_slice(token);
+    }
+}
+
+/// <filename> ::= '"' { <any char except '"'> } '"' | <word not starting with '"'>
+fn filename(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((
+        delimited(tag("\""), take_while1(|c| c != b'"'), tag("\"")),
+        verify(word, |word: &[u8]| word[0] != b'"'),
+    ))(input)
+}
+
+/// <addr> ::= <IPv4 as a decimal integer> | <ip4addr> | <ip6addr>
+fn addr(input: &[u8]) -> IResult<&[u8], IpAddr> {
+    map_opt(word, parse_addr)(input)
+}
+
+/// Parse an address in its DCC form: an IPv4 as a decimal integer,
+/// `3232235777` for `192.168.1.1`, or an IPv6 as a literal, `2001:db8::1`
+/// The dotted IPv4 form sent by some clients is accepted too.
+pub fn parse_addr(addr: &[u8]) -> Option<IpAddr> {
+    if addr.iter().all(u8::is_ascii_digit) {
+        parse_number(addr).map(|addr: u32| IpAddr::V4(Ipv4Addr::from(addr)))
+    } else {
+        parse_number(addr)
+    }
+}
+
+/// Same as `parse_addr` but with the port too
+pub fn parse_socket_addr(addr: &[u8], port: &[u8]) -> Option<SocketAddr> {
+    Some(SocketAddr::new(parse_addr(addr)?, parse_number(port)?))
+}
+
+/// Format an address in its DCC form, the reverse of `parse_addr`
+pub fn format_addr(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr).to_string(),
+        IpAddr::V6(addr) => addr.to_string(),
+    }
+}
+
+/// Format an address and its port as they are sent in an offer:
+/// `3232235777 5000`
+pub fn format_socket_addr(addr: SocketAddr) -> String {
+    format!("{} {}", format_addr(addr.ip()), addr.port())
+}
+
+fn port(input: &[u8]) -> IResult<&[u8], u16> {
+    map_opt(numbers, parse_number)(input)
+}
+
+fn word(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|c| c != b' ')(input)
+}
+
+fn parse_number<T: FromStr>(input: &[u8]) -> Option<T> {
+    str::from_utf8(input).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+    use core::net::Ipv6Addr;
+
+    #[test]
+    fn dcc_send() {
+        let m = parse_message(b"PRIVMSG nick :\x01DCC SEND file.txt 3232235777 5000 1024\x01\r\n")
+            .unwrap();
+        assert_eq!(
+            m.as_ctcp().unwrap().as_dcc(),
+            Some(Dcc::Send(DccSend {
+                filename: b"file.txt",
+                addr: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+                port: 5000,
+                size: Some(1024),
+                token: None,
+            }))
+        );
+        let offer = dcc(b"SEND \"my  file.txt\" 127.0.0.1 5000").unwrap();
+        assert!(matches!(
+            offer,
+            Dcc::Send(DccSend {
+                filename: b"my  file.txt",
+                size: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn dcc_passive() {
+        match dcc(b"SEND file 2001:db8::1 0 42 17").unwrap() {
+            Dcc::Send(send) => {
+                assert_eq!(
+                    send.addr,
+                    IpAddr::V6("2001:db8::1".parse::<Ipv6Addr>().unwrap())
+                );
+                assert_eq!(send.token, Some(&b"17"[..]));
+                assert!(send.is_passive());
+            }
+            offer => panic!("unexpected {:?}", offer),
+        }
+    }
+
+    #[test]
+    fn dcc_chat() {
+        assert_eq!(
+            dcc(b"CHAT chat 2130706433 4000"),
+            Some(Dcc::Chat(DccChat {
+                addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                port: 4000,
+                token: None,
+            }))
+        );
+        let offer = dcc(b"chat chat 0 0 7").unwrap();
+        assert!(matches!(offer, Dcc::Chat(chat) if chat.is_passive()));
+    }
+
+    #[test]
+    fn dcc_invalid() {
+        assert_eq!(dcc(b"SEND file 127.0.0.1 70000"), None);
+        assert_eq!(dcc(b"SEND file 4294967296 5000"), None);
+        assert_eq!(dcc(b"SEND \"unterminated 127.0.0.1 5000"), None);
+        assert_eq!(dcc(b"XMIT file 5000 1024"), None);
+        assert_eq!(dcc(b"RESUME file 5000"), None);
+        assert_eq!(dcc(b"CHAT 127.0.0.1 4000"), None);
+    }
+
+    #[test]
+    fn dcc_resume() {
+        let offer = match dcc(b"SEND \"a b.txt\" 3232235777 5000 1024").unwrap() {
+            Dcc::Send(send) => send,
+            offer => panic!("unexpected {:?}", offer),
+        };
+        let resume = offer.resume(512);
+        assert_eq!(
+            resume.to_bytes(),
+            b"\x01DCC RESUME \"a b.txt\" 5000 512\x01"
+        );
+        let params = resume.to_params();
+        let resume = match dcc(&params).unwrap() {
+            Dcc::Resume(resume) => resume,
+            dcc => panic!("unexpected {:?}", dcc),
+        };
+        assert_eq!(resume.position, 512);
+        let accept = resume.accept();
+        assert_eq!(accept.to_params(), b"ACCEPT \"a b.txt\" 5000 512");
+        assert_eq!(
+            dcc(b"accept file 5000 512"),
+            Some(Dcc::Accept(DccResume {
+                filename: b"file",
+                port: 5000,
+                position: 512,
+                token: None,
+            }))
+        );
+        assert_eq!(
+            offer.passive_reply(IpAddr::V4(Ipv4Addr::LOCALHOST), 4000),
+            None
+        );
+    }
+
+    #[test]
+    fn dcc_passive_reply() {
+        let offer = match dcc(b"SEND file 3232235777 0 1024 17").unwrap() {
+            Dcc::Send(send) => send,
+            offer => panic!("unexpected {:?}", offer),
+        };
+        let reply = offer
+            .passive_reply(IpAddr::V4(Ipv4Addr::LOCALHOST), 4000)
+            .unwrap();
+        assert_eq!(reply.to_params(), b"SEND file 2130706433 4000 1024 17");
+        let resume = offer.resume(10);
+        assert_eq!(resume.to_params(), b"RESUME file 0 10 17");
+        let chat = Dcc::Chat(DccChat {
+            addr: IpAddr::V6(Ipv6Addr::LOCALHOST),
+            port: 0,
+            token: Some(b"8"),
+        });
+        assert_eq!(chat.to_params(), b"CHAT chat ::1 0 8");
+        assert_eq!(dcc(&chat.to_params()), Some(chat));
+    }
+
+    #[test]
+    fn dcc_addr_codec() {
+        let v4 = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(format_addr(v4), "3232235777");
+        assert_eq!(parse_addr(b"3232235777"), Some(v4));
+        assert_eq!(parse_addr(b"192.168.1.1"), Some(v4));
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        assert_eq!(format_addr(v6), "::1");
+        assert_eq!(parse_addr(b"::1"), Some(v6));
+        assert_eq!(parse_addr(b"4294967296"), None);
+        assert_eq!(parse_addr(b"host"), None);
+        let socket = SocketAddr::new(v4, 5000);
+        assert_eq!(format_socket_addr(socket), "3232235777 5000");
+        assert_eq!(parse_socket_addr(b"3232235777", b"5000"), Some(socket));
+        match dcc(b"SEND file 3232235777 5000").unwrap() {
+            Dcc::Send(send) => assert_eq!(send.socket_addr(), socket),
+            offer => panic!("unexpected {:?}", offer),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::ops::Range;
+use nom::error::ErrorKind;
+use nom::Err;
+
+/// The error returned by `parse_message`
+/// The offsets are relative to the start of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input does not contain a whole line terminated by a crlf, the
+    /// `offset` is the end of the input
+    UnterminatedLine { offset: usize },
+    /// The line is `len` bytes long, tags excluded, while only `max` bytes
+    /// are allowed, the tags end at `offset`
+    LineTooLong {
+        offset: usize,
+        len: usize,
+        max: usize,
+    },
+    /// The tags are `len` bytes long while only `max` bytes are allowed
+    TagsTooLong { len: usize, max: usize },
+    /// The tags are malformed at `offset`
+    InvalidTags { offset: usize },
+    /// The prefix is neither a servername nor a `nick!user@host` at `offset`
+    InvalidPrefix { offset: usize },
+    /// The nick of the prefix contains a forbidden char at `offset`
+    InvalidNickChar { offset: usize },
+    /// The nick of the prefix starting at `offset` is `len` chars long while
+    /// only `max` are allowed
+    NickTooLong {
+        offset: usize,
+        len: usize,
+        max: usize,
+    },
+    /// There is nothing where the command should be
+    MissingCommand { offset: usize },
+    /// The command is neither a word nor a 3-digit numeric, `offset` is the
+    /// first unexpected byte
+    InvalidCommand { offset: usize },
+    /// A NUL, a CR or a LF is in the middle of the parameters
+    IllegalByteInParam { offset: usize, byte: u8 },
+    /// The parameters are not valid UTF-8 from `offset` while the
+    /// configuration requires it
+    InvalidUtf8 { offset: usize },
+    /// A message was parsed but the input continues after its crlf,
+    /// `&input[offset..]` is the leftover
+    TrailingData { offset: usize },
+    /// The JSON representation of a message is malformed at `offset`
+    InvalidJson { offset: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseError::UnterminatedLine { .. } => {
+                write!(f, "the line is not terminated by a crlf")
+            }
+            ParseError::LineTooLong { len, max, .. } => write!(
+                f,
+                "the line is {} bytes long while at most {} are allowed",
+                len, max
+            ),
+            ParseError::TagsTooLong { len, max } => write!(
+                f,
+                "the tags are {} bytes long while at most {} are allowed",
+                len, max
+            ),
+            ParseError::InvalidTags { offset } => write!(f, "invalid tags at byte {}", offset),
+            ParseError::InvalidPrefix { offset } => {
+                write!(f, "invalid prefix at byte {}", offset)
+            }
+            ParseError::InvalidNickChar { offset } => {
+                write!(f, "invalid char in the nick at byte {}", offset)
+            }
+            ParseError::NickTooLong { len, max, .. } => write!(
+                f,
+                "the nick is {} chars long while at most {} are allowed",
+                len, max
+            ),
+            ParseError::MissingCommand { offset } => {
+                write!(f, "missing command at byte {}", offset)
+            }
+            ParseError::InvalidCommand { offset } => {
+                write!(f, "invalid command at byte {}", offset)
+            }
+            ParseError::IllegalByteInParam { offset, byte } => write!(
+                f,
+                "illegal byte {:#04x} in the parameters at byte {}",
+                byte, offset
+            ),
+            ParseError::InvalidUtf8 { offset } => {
+                write!(f, "invalid UTF-8 in the parameters at byte {}", offset)
+            }
+            ParseError::TrailingData { offset } => {
+                write!(f, "unexpected data after the crlf at byte {}", offset)
+            }
+            ParseError::InvalidJson { offset } => write!(f, "invalid JSON at byte {}", offset),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Return the bytes of the input the error is about, an empty range when
+    /// something is missing
+    pub fn span(&self) -> Range<usize> {
+        match *self {
+            ParseError::UnterminatedLine { offset }
+            | ParseError::MissingCommand { offset }
+            | ParseError::TrailingData { offset } => offset..offset,
+            ParseError::LineTooLong { offset, len, max } => offset + max..offset + len,
+            ParseError::TagsTooLong { len, max } => max..len,
+            ParseError::NickTooLong { offset, len, max } => offset + max..offset + len,
+            ParseError::InvalidTags { offset }
+            | ParseError::InvalidPrefix { offset }
+            | ParseError::InvalidNickChar { offset }
+            | ParseError::InvalidCommand { offset }
+            | ParseError::IllegalByteInParam { offset, .. }
+            | ParseError::InvalidUtf8 { offset }
+            | ParseError::InvalidJson { offset } => offset..offset + 1,
+        }
+    }
+
+    /// Return the grammar element that was expected at the start of `span`
+    pub fn expected(&self) -> &'static str {
+        match self {
+            ParseError::UnterminatedLine { .. } => "a crlf",
+            ParseError::LineTooLong { .. } => "the end of the line",
+            ParseError::TagsTooLong { .. } => "the end of the tags",
+            ParseError::InvalidTags { .. } => "a tag or a space",
+            ParseError::InvalidPrefix { .. } => "a servername or nick!user@host",
+            ParseError::InvalidNickChar { .. } => "a letter, a digit or a special char",
+            ParseError::NickTooLong { .. } => "the end of the nick",
+            ParseError::MissingCommand { .. } | ParseError::InvalidCommand { .. } => {
+                "a command or a 3-digit numeric"
+            }
+            ParseError::IllegalByteInParam { .. } => "a parameter or a crlf",
+            ParseError::InvalidUtf8 { .. } => "UTF-8 text",
+            ParseError::TrailingData { .. } => "the end of the input",
+            ParseError::InvalidJson { .. } => "a JSON message",
+        }
+    }
+
+    /// Show where the error is in `input`, the line that failed to parse:
+    /// ```text
+    /// :nick 12 :hello
+    ///         ^ expected a command or a 3-digit numeric
+    /// ```
+    /// The line is converted lossily to UTF-8 and stops at the first CR or LF.
+    pub fn caret(&self, input: &[u8]) -> String {
+        let end = input
+            .iter()
+            .position(|&c| c == b'\r' || c == b'\n')
+            .unwrap_or(input.len());
+        let span = self.span();
+        let start = span.start.min(end);
+        let line = String::from_utf8_lossy(&input[..end]);
+        // count chars rather than bytes to align the caret
+        let column = String::from_utf8_lossy(&input[..start]).chars().count();
+        let width = String::from_utf8_lossy(&input[start..span.end.clamp(start, end)])
+            .chars()
+            .count()
+            .max(1);
+        format!(
+            "{}\n{}{} expected {}",
+            line,
+            " ".repeat(column),
+            "^".repeat(width),
+            self.expected()
+        )
+    }
+}
+
+/// Return where a nom parser called on `input` failed
+pub(crate) fn error_offset(input: &[u8], err: Err<(&[u8], ErrorKind)>) -> usize {
+    match err {
+        Err::Incomplete(_) => input.len(),
+        Err::Error((rest, _)) | Err::Failure((rest, _)) => input.len() - rest.len(),
+    }
+}
+
+/// The error returned by `MessageReader::read_message`
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading from the underlying reader failed
+    Io(std::io::Error),
+    /// The line was read but is not a valid message
+    Parse(ParseError),
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ReadError {
+    fn from(err: std::io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ParseError> for ReadError {
+    fn from(err: ParseError) -> Self {
+        ReadError::Parse(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "failed to read a line: {}", err),
+            ReadError::Parse(err) => write!(f, "failed to parse a line: {}", err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            ReadError::Parse(err) => Some(err),
+        }
+    }
+}
+
+/// The error returned by `Message::encode_into`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The message doesn't fit in the buffer
+    BufferTooSmall,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::BufferTooSmall => write!(f, "the buffer is too small for the message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// The error returned by `MessageBuilder::build`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The parameter at `index` contains a NUL, CR or LF, or is a middle
+    /// parameter that is empty, starts with ':' or contains a space
+    InvalidParam { index: usize },
+    /// A parameter was added after the trailing one
+    ParamAfterTrailing,
+    /// There are `count` parameters while only 15 are allowed
+    TooManyParams { count: usize },
+    /// The key of the tag at `index` is not a valid tag key
+    InvalidTagKey { index: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BuildError::InvalidParam { index } => write!(f, "invalid parameter {}", index),
+            BuildError::ParamAfterTrailing => {
+                write!(f, "a parameter follows the trailing parameter")
+            }
+            BuildError::TooManyParams { count } => write!(
+                f,
+                "there are {} parameters while at most 15 are allowed",
+                count
+            ),
+            BuildError::InvalidTagKey { index } => write!(f, "invalid key for tag {}", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_errors() {
+        assert_eq!(
+            BuildError::TooManyParams { count: 16 }.to_string(),
+            "there are 16 parameters while at most 15 are allowed"
+        );
+        assert_eq!(
+            EncodeError::BufferTooSmall.to_string(),
+            "the buffer is too small for the message"
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_error_source() {
+        use std::error::Error;
+
+        let err = ReadError::from(ParseError::InvalidCommand { offset: 8 });
+        assert_eq!(
+            err.to_string(),
+            "failed to parse a line: invalid command at byte 8"
+        );
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "invalid command at byte 8"
+        );
+        let err = ReadError::from(std::io::Error::other("closed"));
+        assert_eq!(err.to_string(), "failed to read a line: closed");
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,167 @@
+//! The extended bans matching something else than a hostmask
+//! `$a:account` bans a services account and `$~a` every user logged in.
+//! ```
+//! use irc_parser::extban::{ExtBan, ExtBanSupport};
+//!
+//! let support = ExtBanSupport::parse(b"~,qjncrRa").unwrap();
+//! let ban = support.ban(b"~q:~a:account").unwrap();
+//! assert_eq!(ban.kind, b'q');
+//! let inner = ban.stacked(&support).unwrap();
+//! assert_eq!(
+//!     inner,
+//!     ExtBan {
+//!         negated: false,
+//!         kind: b'a',
+//!         mask: Some(b"account"),
+//!     }
+//! );
+//! ```
+
+use crate::message::Message;
+use crate::numeric::Numeric;
+
+/// The extended bans supported by a server, advertised in the EXTBAN token
+/// of RPL_ISUPPORT as `EXTBAN=<prefix>,<types>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtBanSupport<'a> {
+    /// The char starting every extended ban, `$` or `~`
+    pub prefix: u8,
+    /// The letters of the supported types
+    pub types: &'a [u8],
+}
+
+impl<'a> ExtBanSupport<'a> {
+    /// Parse the value of the EXTBAN token: `$,ajrxz`
+    /// Some servers omit the prefix: `,ajrxz` then uses `$`.
+    pub fn parse(value: &'a [u8]) -> Option<Self> {
+        match value {
+            [b',', types @ ..] => Some(ExtBanSupport {
+                prefix: b'$',
+                types,
+            }),
+            [prefix, b',', types @ ..] => Some(ExtBanSupport {
+                prefix: *prefix,
+                types,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Find the EXTBAN token of a RPL_ISUPPORT (005)
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplISupport) {
+            return None;
+        }
+        message
+            .params
+            .middles()
+            .iter()
+            .skip(1)
+            .find_map(|token| token.strip_prefix(b"EXTBAN="))
+            .and_then(Self::parse)
+    }
+
+    /// Parse an entry of a ban list as an extended ban
+    /// Return `None` if it is a hostmask or an unsupported type.
+    /// <extban> ::= <prefix> [ '~' ] <type> [ ':' <mask> ]
+    /// The '~' negates the ban, it is not allowed when it is the prefix.
+    pub fn ban(&self, ban: &'a [u8]) -> Option<ExtBan<'a>> {
+        let rest = ban.strip_prefix(&[self.prefix])?;
+        let (negated, rest) = match rest.strip_prefix(b"~") {
+            Some(rest) if self.prefix != b'~' => (true, rest),
+            _ => (false, rest),
+        };
+        let (&kind, rest) = rest.split_first()?;
+        if !self.types.contains(&kind) {
+            return None;
+        }
+        let mask = match rest {
+            [] => None,
+            [b':', mask @ ..] => Some(mask),
+            _ => return None,
+        };
+        Some(ExtBan {
+            negated,
+            kind,
+            mask,
+        })
+    }
+}
+
+/// The extended bans of charybdis and its forks
+impl Default for ExtBanSupport<'_> {
+    fn default() -> Self {
+        ExtBanSupport {
+            prefix: b'$',
+            types: b"acjorsxz",
+        }
+    }
+}
+
+/// An extended ban: `$~a:account`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtBan<'a> {
+    /// The ban applies to everyone not matching it
+    pub negated: bool,
+    /// The letter of the type: `a` for an account, `r` for a realname, ...
+    pub kind: u8,
+    /// The argument following the ':', it depends on the type
+    pub mask: Option<&'a [u8]>,
+}
+
+impl<'a> ExtBan<'a> {
+    /// Parse the mask as an extended ban itself, for the types applying a
+    /// ban to something else like `~q:~a:account`
+    pub fn stacked(&self, support: &ExtBanSupport<'a>) -> Option<ExtBan<'a>> {
+        support.ban(self.mask?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn extban_parse() {
+        let support = ExtBanSupport::default();
+        assert_eq!(
+            support.ban(b"$r:*real*name*"),
+            Some(ExtBan {
+                negated: false,
+                kind: b'r',
+                mask: Some(b"*real*name*"),
+            })
+        );
+        assert_eq!(
+            support.ban(b"$~a"),
+            Some(ExtBan {
+                negated: true,
+                kind: b'a',
+                mask: None,
+            })
+        );
+        // hostmasks and unsupported types
+        assert_eq!(support.ban(b"*!*@host"), None);
+        assert_eq!(support.ban(b"$w:x"), None);
+        assert_eq!(support.ban(b"$ax"), None);
+        assert_eq!(support.ban(b"$"), None);
+        // a '~' prefix can't be negated
+        let unreal = ExtBanSupport::parse(b"~,qa").unwrap();
+        assert_eq!(unreal.ban(b"~~a"), None);
+    }
+
+    #[test]
+    fn extban_isupport() {
+        let message =
+            parse_message(b":irc.example.com 005 me EXTBAN=,ajr :are supported\r\n").unwrap();
+        assert_eq!(
+            ExtBanSupport::from_message(&message),
+            Some(ExtBanSupport {
+                prefix: b'$',
+                types: b"ajr",
+            })
+        );
+        assert_eq!(ExtBanSupport::parse(b"$"), None);
+    }
+}
@@ -0,0 +1,800 @@
+//! The mIRC formatting codes of message texts: bold, colors, ...
+//! ```
+//! use irc_parser::format::{spans, Color, Style};
+//!
+//! let spans: Vec<_> = spans(b"hello \x02\x0304,1world\x0f!").collect();
+//! assert_eq!(spans[0].text, b"hello ");
+//! assert_eq!(spans[1].text, b"world");
+//! assert!(spans[1].style.bold);
+//! assert_eq!(spans[1].style.foreground, Some(Color::Index(4)));
+//! assert_eq!(spans[1].style.background, Some(Color::Index(1)));
+//! assert_eq!(spans[2].style, Style::default());
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use nom::bytes::complete::{tag, take_while_m_n};
+use nom::combinator::{map, opt};
+use nom::sequence::{pair, preceded};
+use nom::IResult;
+
+pub const BOLD: u8 = 0x02;
+pub const ITALIC: u8 = 0x1d;
+pub const UNDERLINE: u8 = 0x1f;
+pub const STRIKETHROUGH: u8 = 0x1e;
+pub const MONOSPACE: u8 = 0x11;
+pub const REVERSE: u8 = 0x16;
+pub const RESET: u8 = 0x0f;
+pub const COLOR: u8 = 0x03;
+pub const HEX_COLOR: u8 = 0x04;
+
+/// The style of a piece of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub monospace: bool,
+    /// Swap the foreground and the background colors
+    pub reverse: bool,
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+}
+
+/// A color of the text or of its background
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// An index in the mIRC palette, 0 to 15 are the standard colors and 16
+    /// to 98 the extended ones, 99 is the default color
+    Index(u8),
+    /// A `0xRRGGBB` value set by a hex color code
+    Rgb(u32),
+}
+
+impl Color {
+    /// Return the RGB value of the color, `None` for the default color
+    pub fn rgb(self) -> Option<u32> {
+        match self {
+            Color::Index(index) => rgb_color(index),
+            Color::Rgb(rgb) => Some(rgb),
+        }
+    }
+
+    /// Return the ANSI SGR parameters of the color, `layer` is 38 for the
+    /// foreground and 48 for the background
+    /// The indexes use the 256 colors palette and the RGB values the 24-bit
+    /// colors.
+    fn ansi(self, layer: u8) -> Option<String> {
+        match self {
+            Color::Index(index) => ansi_color(index).map(|n| format!("{};5;{}", layer, n)),
+            Color::Rgb(rgb) => Some(format!(
+                "{};2;{};{};{}",
+                layer,
+                rgb >> 16,
+                (rgb >> 8) & 0xff,
+                rgb & 0xff
+            )),
+        }
+    }
+}
+
+impl Style {
+    /// Return the ANSI SGR escape sequence setting this style from the
+    /// default one: `\x1b[0;1;38;5;9m` for bold red
+    /// Monospace has no equivalent and is ignored.
+    pub fn to_ansi(&self) -> String {
+        let mut sgr = String::from("\x1b[0");
+        let flags = [
+            (self.bold, ";1"),
+            (self.italic, ";3"),
+            (self.underline, ";4"),
+            (self.reverse, ";7"),
+            (self.strikethrough, ";9"),
+        ];
+        for (set, code) in flags.iter() {
+            if *set {
+                sgr.push_str(code);
+            }
+        }
+        let colors = [(self.foreground, 38), (self.background, 48)];
+        for (color, layer) in colors.iter() {
+            if let Some(params) = color.and_then(|color| color.ansi(*layer)) {
+                sgr.push(';');
+                sgr.push_str(&params);
+            }
+        }
+        sgr.push('m');
+        sgr
+    }
+
+    /// Return the codes setting this style from the default one before
+    /// `text`
+    /// The palette indexes are written with two digits so a text starting
+    /// with a digit is not read as part of the color. The indexes above 99
+    /// can't be written and are dropped.
+    pub(crate) fn codes(&self, text: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let flags = [
+            (self.bold, BOLD),
+            (self.italic, ITALIC),
+            (self.underline, UNDERLINE),
+            (self.strikethrough, STRIKETHROUGH),
+            (self.monospace, MONOSPACE),
+            (self.reverse, REVERSE),
+        ];
+        bytes.extend(flags.iter().filter(|(set, _)| *set).map(|(_, code)| code));
+        let valid = |color| match color {
+            Some(Color::Index(index)) if index > 99 => None,
+            color => color,
+        };
+        // whether the color code ends with a background
+        let closed = match (valid(self.foreground), valid(self.background)) {
+            (None, None) => true,
+            (Some(Color::Index(foreground)), None) => {
+                bytes.push(COLOR);
+                bytes.extend_from_slice(format!("{:02}", foreground).as_bytes());
+                false
+            }
+            (None, Some(Color::Index(background)))
+            | (Some(Color::Index(99)), Some(Color::Index(background))) => {
+                bytes.push(COLOR);
+                bytes.extend_from_slice(format!("99,{:02}", background).as_bytes());
+                true
+            }
+            (Some(Color::Index(foreground)), Some(Color::Index(background))) => {
+                bytes.push(COLOR);
+                bytes.extend_from_slice(format!("{:02},{:02}", foreground, background).as_bytes());
+                true
+            }
+            (foreground, background) => {
+                let foreground = foreground.and_then(Color::rgb);
+                let background = background.and_then(Color::rgb);
+                match (foreground, background) {
+                    (Some(foreground), Some(background)) => {
+                        bytes.push(HEX_COLOR);
+                        bytes.extend_from_slice(
+                            format!("{:06X},{:06X}", foreground, background).as_bytes(),
+                        );
+                        true
+                    }
+                    (Some(foreground), None) => {
+                        bytes.push(HEX_COLOR);
+                        bytes.extend_from_slice(format!("{:06X}", foreground).as_bytes());
+                        false
+                    }
+                    (None, _) => true,
+                }
+            }
+        };
+        // a comma following a color without background would be read as
+        // the start of the background, toggling bold twice splits them
+        if !closed && text.starts_with(b",") {
+            bytes.extend_from_slice(&[BOLD, BOLD]);
+        }
+        bytes
+    }
+
+    /// Return the style once the codes of `text` are applied to this one
+    pub(crate) fn apply(self, text: &[u8]) -> Style {
+        let mut spans = Spans { text, style: self };
+        spans.by_ref().for_each(drop);
+        spans.style
+    }
+}
+
+/// The names of the 16 standard colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamedColor {
+    White = 0,
+    Black = 1,
+    Blue = 2,
+    Green = 3,
+    Red = 4,
+    Brown = 5,
+    Magenta = 6,
+    Orange = 7,
+    Yellow = 8,
+    LightGreen = 9,
+    Cyan = 10,
+    LightCyan = 11,
+    LightBlue = 12,
+    Pink = 13,
+    Grey = 14,
+    LightGrey = 15,
+}
+
+impl From<NamedColor> for Color {
+    fn from(color: NamedColor) -> Self {
+        Color::Index(color as u8)
+    }
+}
+
+/// The closest color of the xterm 256 colors palette for each mIRC color
+const ANSI_COLORS: [u8; 99] = [
+    // the 16 standard colors
+    15, 0, 4, 2, 9, 1, 5, 208, 11, 10, 6, 14, 12, 13, 8, 7, //
+    // the extended colors
+    52, 94, 100, 58, 22, 29, 23, 24, 17, 54, 53, 89, //
+    88, 130, 142, 64, 28, 35, 30, 25, 18, 91, 90, 125, //
+    124, 166, 184, 106, 34, 49, 37, 33, 19, 129, 127, 161, //
+    196, 208, 226, 154, 46, 86, 51, 75, 21, 171, 201, 198, //
+    203, 215, 227, 191, 83, 122, 87, 111, 63, 177, 207, 205, //
+    217, 223, 229, 193, 157, 158, 159, 153, 147, 183, 219, 212, //
+    16, 233, 235, 237, 239, 241, 244, 247, 250, 254, 231,
+];
+
+/// Return the xterm 256 colors index of a mIRC color
+/// Return `None` for 99, the default color, and the invalid indexes.
+pub fn ansi_color(color: u8) -> Option<u8> {
+    ANSI_COLORS.get(usize::from(color)).copied()
+}
+
+/// Replace the formatting codes of the text by ANSI escape sequences
+/// The text ends with the default style if it was changed.
+/// ```
+/// use irc_parser::format::to_ansi;
+///
+/// assert_eq!(to_ansi(b"a \x02b"), b"a \x1b[0;1mb\x1b[0m");
+/// assert_eq!(to_ansi(b"\x034red"), b"\x1b[0;38;5;9mred\x1b[0m");
+/// ```
+pub fn to_ansi(text: &[u8]) -> Vec<u8> {
+    let mut ansi = Vec::with_capacity(text.len());
+    let mut current = Style::default();
+    for span in spans(text) {
+        if span.style != current {
+            ansi.extend_from_slice(span.style.to_ansi().as_bytes());
+            current = span.style;
+        }
+        ansi.extend_from_slice(span.text);
+    }
+    if current != Style::default() {
+        ansi.extend_from_slice(b"\x1b[0m");
+    }
+    ansi
+}
+
+/// The RGB value of each mIRC color
+const RGB_COLORS: [u32; 99] = [
+    // the 16 standard colors
+    0xffffff, 0x000000, 0x00007f, 0x009300, 0xff0000, 0x7f0000, 0x9c009c, 0xfc7f00, //
+    0xffff00, 0x00fc00, 0x009393, 0x00ffff, 0x0000fc, 0xff00ff, 0x7f7f7f, 0xd2d2d2, //
+    // the extended colors
+    0x470000, 0x472100, 0x474700, 0x324700, 0x004700, 0x00472c, //
+    0x004747, 0x002747, 0x000047, 0x2e0047, 0x470047, 0x47002a, //
+    0x740000, 0x743a00, 0x747400, 0x517400, 0x007400, 0x007449, //
+    0x007474, 0x004074, 0x000074, 0x4b0074, 0x740074, 0x740045, //
+    0xb50000, 0xb56300, 0xb5b500, 0x7db500, 0x00b500, 0x00b571, //
+    0x00b5b5, 0x0063b5, 0x0000b5, 0x7500b5, 0xb500b5, 0xb5006b, //
+    0xff0000, 0xff8c00, 0xffff00, 0xb2ff00, 0x00ff00, 0x00ffa0, //
+    0x00ffff, 0x008cff, 0x0000ff, 0xa500ff, 0xff00ff, 0xff0098, //
+    0xff5959, 0xffb459, 0xffff71, 0xcfff60, 0x6fff6f, 0x65ffc9, //
+    0x6dffff, 0x59b4ff, 0x5959ff, 0xc459ff, 0xff66ff, 0xff59bc, //
+    0xff9c9c, 0xffd39c, 0xffff9c, 0xe2ff9c, 0x9cff9c, 0x9cffdb, //
+    0x9cffff, 0x9cd3ff, 0x9c9cff, 0xdc9cff, 0xff9cff, 0xff94d3, //
+    0x000000, 0x131313, 0x282828, 0x363636, 0x4d4d4d, 0x656565, //
+    0x818181, 0x9f9f9f, 0xbcbcbc, 0xe2e2e2, 0xffffff,
+];
+
+/// Return the RGB value of a mIRC color as `0xRRGGBB`
+/// Return `None` for 99, the default color, and the invalid indexes.
+pub fn rgb_color(color: u8) -> Option<u32> {
+    RGB_COLORS.get(usize::from(color)).copied()
+}
+
+/// Render the formatted text as HTML
+/// Every styled span is wrapped in its own elements so the tags are always
+/// balanced, and the text is escaped. The colors are set with a
+/// `<span style="...">`, swapped if the text is reversed. Invalid UTF-8 is
+/// replaced by U+FFFD.
+/// ```
+/// use irc_parser::format::to_html;
+///
+/// assert_eq!(
+///     to_html(b"\x02<b>\x1d\x034,1 & co"),
+///     "<b>&lt;b&gt;</b><b><i><span style=\"color:#ff0000;background-color:#000000\"> &amp; co</span></i></b>"
+/// );
+/// ```
+pub fn to_html(text: &[u8]) -> String {
+    let mut html = String::with_capacity(text.len());
+    for span in spans(text) {
+        let style = span.style;
+        let (foreground, background) = match style.reverse {
+            false => (style.foreground, style.background),
+            true => (style.background, style.foreground),
+        };
+        let foreground = foreground.and_then(Color::rgb);
+        let background = background.and_then(Color::rgb);
+        let elements = [
+            (style.bold, "b"),
+            (style.italic, "i"),
+            (style.underline, "u"),
+            (style.strikethrough, "s"),
+            (style.monospace, "code"),
+        ];
+        for (_, element) in elements.iter().filter(|(set, _)| *set) {
+            html.push_str(&format!("<{}>", element));
+        }
+        let colored = foreground.is_some() || background.is_some();
+        if colored {
+            html.push_str("<span style=\"");
+            if let Some(rgb) = foreground {
+                html.push_str(&format!("color:#{:06x}", rgb));
+            }
+            if let (Some(_), Some(_)) = (foreground, background) {
+                html.push(';');
+            }
+            if let Some(rgb) = background {
+                html.push_str(&format!("background-color:#{:06x}", rgb));
+            }
+            html.push_str("\">");
+        }
+        escape_html(&String::from_utf8_lossy(span.text), &mut html);
+        if colored {
+            html.push_str("</span>");
+        }
+        for (_, element) in elements.iter().rev().filter(|(set, _)| *set) {
+            html.push_str(&format!("</{}>", element));
+        }
+    }
+    html
+}
+
+fn escape_html(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Build a formatted text, every styled piece is followed by a reset
+/// ```
+/// use irc_parser::format::{FormattedText, NamedColor::*};
+///
+/// let text = FormattedText::new()
+///     .bold("hi")
+///     .text(" ")
+///     .color(Red, None, "warning")
+///     .color(Black, Some(Yellow), "!");
+/// assert_eq!(
+///     text.as_bytes(),
+///     b"\x02hi\x0f \x0304warning\x0f\x0301,08!\x0f"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormattedText {
+    bytes: Vec<u8>,
+}
+
+impl FormattedText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a text without formatting
+    pub fn text<T: AsRef<[u8]> + ?Sized>(mut self, text: &T) -> Self {
+        self.bytes.extend_from_slice(text.as_ref());
+        self
+    }
+
+    pub fn bold<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                bold: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    pub fn italic<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                italic: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    pub fn underline<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                underline: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    pub fn strikethrough<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                strikethrough: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    pub fn monospace<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                monospace: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    pub fn reverse<T: AsRef<[u8]> + ?Sized>(self, text: &T) -> Self {
+        self.styled(
+            Style {
+                reverse: true,
+                ..Style::default()
+            },
+            text,
+        )
+    }
+
+    /// Add a colored text, with the default background if it is `None`
+    pub fn color<C, T>(self, foreground: C, background: Option<C>, text: &T) -> Self
+    where
+        C: Into<Color>,
+        T: AsRef<[u8]> + ?Sized,
+    {
+        let style = Style {
+            foreground: Some(foreground.into()),
+            background: background.map(Into::into),
+            ..Style::default()
+        };
+        self.styled(style, text)
+    }
+
+    /// Add a text with any style
+    /// A hex color can't be mixed with an index, the index is then written
+    /// as its RGB value, and a hex background can't follow the default
+    /// foreground, it is dropped. So are the indexes above 99.
+    pub fn styled<T: AsRef<[u8]> + ?Sized>(mut self, style: Style, text: &T) -> Self {
+        let text = text.as_ref();
+        if style == Style::default() {
+            return self.text(text);
+        }
+        self.bytes.extend_from_slice(&style.codes(text));
+        self.bytes.extend_from_slice(text);
+        self.bytes.push(RESET);
+        self
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// A piece of text sharing the same style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span<'a> {
+    pub style: Style,
+    pub text: &'a [u8],
+}
+
+/// Return true if `c` starts a formatting code
+pub fn is_format_char(c: u8) -> bool {
+    matches!(
+        c,
+        BOLD | ITALIC | UNDERLINE | STRIKETHROUGH | MONOSPACE | REVERSE | RESET | COLOR | HEX_COLOR
+    )
+}
+
+/// Split the text into its styled spans, the formatting codes are removed
+/// and the empty spans are skipped
+pub fn spans(text: &[u8]) -> Spans<'_> {
+    Spans {
+        text,
+        style: Style::default(),
+    }
+}
+
+/// Iterator over the styled spans of a text, created by `spans`
+#[derive(Debug, Clone)]
+pub struct Spans<'a> {
+    text: &'a [u8],
+    style: Style,
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let len = self
+                .text
+                .iter()
+                .position(|&c| is_format_char(c))
+                .unwrap_or(self.text.len());
+            if len > 0 {
+                let (text, rest) = self.text.split_at(len);
+                self.text = rest;
+                return Some(Span {
+                    style: self.style,
+                    text,
+                });
+            }
+            let (&code, rest) = self.text.split_first()?;
+            self.text = rest;
+            let style = &mut self.style;
+            match code {
+                BOLD => style.bold = !style.bold,
+                ITALIC => style.italic = !style.italic,
+                UNDERLINE => style.underline = !style.underline,
+                STRIKETHROUGH => style.strikethrough = !style.strikethrough,
+                MONOSPACE => style.monospace = !style.monospace,
+                REVERSE => style.reverse = !style.reverse,
+                RESET => *style = Style::default(),
+                _ => {
+                    let parsed = match code {
+                        COLOR => colors(self.text),
+                        _ => hex_colors(self.text),
+                    };
+                    let (rest, colors) = parsed.unwrap_or((self.text, None));
+                    self.text = rest;
+                    match colors {
+                        Some((foreground, background)) => {
+                            style.foreground = Some(foreground);
+                            style.background = background.or(style.background);
+                        }
+                        // a lone color code resets the colors
+                        None => {
+                            style.foreground = None;
+                            style.background = None;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The foreground and the optional background set by a color code
+type Colors = Option<(Color, Option<Color>)>;
+
+/// Return the length of the formatting code starting the text, 0 if it
+/// doesn't start with one
+pub(crate) fn code_len(text: &[u8]) -> usize {
+    let parsed = match text.split_first() {
+        Some((&COLOR, rest)) => colors(rest),
+        Some((&HEX_COLOR, rest)) => hex_colors(rest),
+        Some((&c, _)) if is_format_char(c) => return 1,
+        _ => return 0,
+    };
+    match parsed {
+        Ok((rest, _)) => text.len() - rest.len(),
+        Err(_) => 1,
+    }
+}
+
+/// <colors> ::= <color> [ ',' <color> ]
+/// <color>  ::= <digit> [ <digit> ]
+/// The comma is part of the text if no digit follows it.
+fn colors(input: &[u8]) -> IResult<&[u8], Colors> {
+    opt(pair(color, opt(preceded(tag(","), color))))(input)
+}
+
+fn color(input: &[u8]) -> IResult<&[u8], Color> {
+    map(
+        take_while_m_n(1, 2, |c: u8| c.is_ascii_digit()),
+        |digits: &[u8]| Color::Index(digits.iter().fold(0, |acc, d| acc * 10 + (d - b'0'))),
+    )(input)
+}
+
+/// <hex_colors> ::= <hex_color> [ ',' <hex_color> ]
+/// <hex_color>  ::= 6 * <hexdigit>
+/// The comma is part of the text if no hex color follows it.
+fn hex_colors(input: &[u8]) -> IResult<&[u8], Colors> {
+    opt(pair(hex_color, opt(preceded(tag(","), hex_color))))(input)
+}
+
+fn hex_color(input: &[u8]) -> IResult<&[u8], Color> {
+    map(
+        take_while_m_n(6, 6, |c: u8| c.is_ascii_hexdigit()),
+        |digits: &[u8]| {
+            Color::Rgb(digits.iter().fold(0, |acc, &d| {
+                acc * 16 + char::from(d).to_digit(16).unwrap_or(0)
+            }))
+        },
+    )(input)
+}
+
+/// Remove the formatting codes, colors included
+/// The digits of a color code are removed with it: at most two for the
+/// foreground, then a comma and at most two for the background only if a
+/// digit follows the comma. A hex color code takes six hex digits instead.
+/// Only allocates if the text contains a code.
+pub fn strip_formatting(text: &[u8]) -> Cow<'_, [u8]> {
+    if !text.iter().any(|&c| is_format_char(c)) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(spans(text).flat_map(|span| span.text).copied().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_toggle() {
+        let spans: Vec<_> = spans(b"a\x02b\x1d\x1fc\x02d\x0fe").collect();
+        let texts: Vec<_> = spans.iter().map(|span| span.text).collect();
+        assert_eq!(texts, [&b"a"[..], b"b", b"c", b"d", b"e"]);
+        assert!(spans[1].style.bold);
+        assert!(spans[2].style.bold && spans[2].style.italic && spans[2].style.underline);
+        assert!(!spans[3].style.bold && spans[3].style.italic);
+        assert_eq!(spans[4].style, Style::default());
+        let spans: Vec<_> = super::spans(b"\x1e\x11\x16x").collect();
+        assert!(spans[0].style.strikethrough && spans[0].style.monospace && spans[0].style.reverse);
+    }
+
+    #[test]
+    fn spans_colors() {
+        let spans: Vec<_> = spans(b"\x035a\x03,1b\x0312,05c\x03,d\x03e").collect();
+        let colors: Vec<_> = spans
+            .iter()
+            .map(|span| (span.text, span.style.foreground, span.style.background))
+            .collect();
+        assert_eq!(
+            colors,
+            [
+                (&b"a"[..], Some(Color::Index(5)), None),
+                // no foreground, the comma is text
+                (b",1b", None, None),
+                (b"c", Some(Color::Index(12)), Some(Color::Index(5))),
+                (b",d", None, None),
+                (b"e", None, None),
+            ]
+        );
+        // the background is kept when only the foreground changes
+        let spans: Vec<_> = super::spans(b"\x031,2a\x034b\x03123").collect();
+        assert_eq!(spans[1].style.background, Some(Color::Index(2)));
+        assert_eq!(spans[2].text, b"3");
+        assert_eq!(spans[2].style.foreground, Some(Color::Index(12)));
+    }
+
+    #[test]
+    fn strip_codes() {
+        assert_eq!(
+            strip_formatting(b"\x02bold\x02 \x0304red\x03 plain"),
+            &b"bold red plain"[..]
+        );
+        assert!(matches!(
+            strip_formatting(b"plain"),
+            Cow::Borrowed(b"plain")
+        ));
+        assert_eq!(strip_formatting(b""), &b""[..]);
+        // at most two digits per color and the comma only goes with a digit
+        assert_eq!(strip_formatting(b"\x03123"), &b"3"[..]);
+        assert_eq!(strip_formatting(b"\x031,234"), &b"4"[..]);
+        assert_eq!(strip_formatting(b"\x0312,x"), &b",x"[..]);
+        assert_eq!(strip_formatting(b"\x03,12"), &b",12"[..]);
+        assert_eq!(strip_formatting(b"!cmd\x0f \x1dargs"), &b"!cmd args"[..]);
+    }
+
+    #[test]
+    fn ansi_escapes() {
+        let style = Style {
+            bold: true,
+            underline: true,
+            reverse: true,
+            foreground: Some(Color::Index(52)),
+            background: Some(Color::Index(99)),
+            ..Style::default()
+        };
+        assert_eq!(style.to_ansi(), "\x1b[0;1;4;7;38;5;196m");
+        assert_eq!(ansi_color(0), Some(15));
+        assert_eq!(ansi_color(98), Some(231));
+        assert_eq!(ansi_color(99), None);
+        // the style is only written when it changes
+        assert_eq!(to_ansi(b"\x02a\x02\x02b\x0fc"), b"\x1b[0;1mab\x1b[0mc");
+        assert_eq!(to_ansi(b"plain"), b"plain");
+    }
+
+    #[test]
+    fn html_render() {
+        assert_eq!(to_html(b"plain 'text'"), "plain &#39;text&#39;");
+        assert_eq!(
+            to_html(b"\x1f\x1e\x11a\x0f\x16\x0399,2b\xff"),
+            "<u><s><code>a</code></s></u><span style=\"color:#00007f\">b\u{fffd}</span>"
+        );
+        assert_eq!(rgb_color(52), Some(0xff0000));
+        assert_eq!(rgb_color(98), Some(0xffffff));
+        assert_eq!(rgb_color(99), None);
+    }
+
+    #[test]
+    fn spans_hex_colors() {
+        let spans: Vec<_> = spans(b"\x04FF8000a\x04,00ff00b\x0400fF00,1a2B3cc\x04d").collect();
+        let colors: Vec<_> = spans
+            .iter()
+            .map(|span| (span.text, span.style.foreground, span.style.background))
+            .collect();
+        assert_eq!(
+            colors,
+            [
+                (&b"a"[..], Some(Color::Rgb(0xff8000)), None),
+                (b",00ff00b", None, None),
+                (b"c", Some(Color::Rgb(0x00ff00)), Some(Color::Rgb(0x1a2b3c))),
+                (b"d", None, None),
+            ]
+        );
+        // the comma only goes with six hex digits
+        assert_eq!(strip_formatting(b"\x04123456,12345x"), &b",12345x"[..]);
+        assert_eq!(strip_formatting(b"\x0412345x"), &b"12345x"[..]);
+        assert_eq!(
+            to_ansi(b"\x04ff8000,0a0b0cx"),
+            b"\x1b[0;38;2;255;128;0;48;2;10;11;12mx\x1b[0m"
+        );
+        assert_eq!(
+            to_html(b"\x04ff8000,\x034x"),
+            "<span style=\"color:#ff8000\">,</span><span style=\"color:#ff0000\">x</span>"
+        );
+    }
+
+    #[test]
+    fn formatted_text() {
+        let style = Style {
+            bold: true,
+            italic: true,
+            foreground: Some(Color::Rgb(0xff8000)),
+            background: Some(NamedColor::Blue.into()),
+            ..Style::default()
+        };
+        let text = FormattedText::new()
+            .styled(style, "a")
+            .styled(Style::default(), "b")
+            .color(NamedColor::Green, None, "1")
+            .color(Color::Rgb(0x123abc), None, ",")
+            .underline("c");
+        assert_eq!(
+            text.as_bytes(),
+            &b"\x02\x1d\x04FF8000,00007Fa\x0fb\x03031\x0f\x04123ABC\x02\x02,\x0f\x1fc\x0f"[..]
+        );
+        // the builder and the parser agree
+        let parsed: Vec<_> = spans(text.as_bytes()).collect();
+        let background = Some(Color::Rgb(0x00007f));
+        assert_eq!(
+            parsed[0].style,
+            Style {
+                background,
+                ..style
+            }
+        );
+        assert_eq!(parsed[2].text, b"1");
+        assert_eq!(parsed[3].text, b",");
+        assert_eq!(parsed[3].style.foreground, Some(Color::Rgb(0x123abc)));
+        assert_eq!(strip_formatting(&text.into_bytes()), &b"ab1,c"[..]);
+    }
+
+    #[test]
+    fn formatted_background() {
+        let text = FormattedText::new()
+            .color(Color::Index(99), Some(Color::Index(3)), "a")
+            .color(Color::Index(99), Some(Color::Rgb(0)), ",b");
+        assert_eq!(text.as_bytes(), b"\x0399,03a\x0f,b\x0f");
+        // 150 would be read as 15 followed by a 0
+        let text = FormattedText::new()
+            .color(Color::Index(150), None, "a")
+            .color(Color::Index(4), Some(Color::Index(150)), "b");
+        assert_eq!(text.as_bytes(), b"a\x0f\x0304b\x0f");
+    }
+}
@@ -0,0 +1,244 @@
+use crate::parser::{letter, number};
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::str::{self, FromStr};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::combinator::{map, map_res, not, recognize};
+use nom::multi::separated_nonempty_list;
+use nom::sequence::preceded;
+use nom::IResult;
+
+/// A host as found in prefixes and replies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Host<'a> {
+    /// A dotted hostname: `irc.example.com`
+    Hostname(&'a [u8]),
+    /// An IPv4 address: `127.0.0.1`
+    Ipv4(Ipv4Addr),
+    /// An IPv6 address without brackets: `2001:db8::1`
+    Ipv6(Ipv6Addr),
+}
+
+/// <host>       ::= <hostname> | <ip4addr> | <ip6addr>
+/// Extract a host, the addresses are checked to be valid IPs.
+/// Return an error if the input does not start with any of them:
+/// Err( (input, ErrorKind) )
+pub fn host(input: &[u8]) -> IResult<&[u8], Host<'_>> {
+    let (input, host) = alt((
+        map(ip6addr, Host::Ipv6),
+        map(ip4addr, Host::Ipv4),
+        map(hostname, Host::Hostname),
+    ))(input)?;
+    let (input, _) = not(host_char)(input)?;
+    Ok((input, host))
+}
+
+/// Why a servername was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServernameError {
+    /// Index of the faulty label, starting at 0
+    pub label: usize,
+    /// Offset of the faulty label in the servername
+    pub offset: usize,
+    pub kind: ServernameErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServernameErrorKind {
+    /// The label is empty: `irc..net` or `irc.net.`
+    Empty,
+    /// The label contains something else than letters, digits and hyphens
+    InvalidChar(u8),
+    /// The label starts with a hyphen: `-irc.net`
+    LeadingHyphen,
+    /// The label ends with a hyphen: `irc-.net`
+    TrailingHyphen,
+    /// The label is longer than 63 chars
+    TooLong,
+}
+
+/// <servername> ::= <hostname>
+/// Extract a servername and check every label with `validate_servername`.
+/// Return an error if the input does not start with a valid servername:
+/// Err( (input, Verify) )
+pub fn servername(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    nom::combinator::verify(
+        take_while1(|c: u8| c.is_ascii_alphanumeric() || c == b'-' || c == b'.'),
+        |name: &[u8]| validate_servername(name).is_ok(),
+    )(input)
+}
+
+/// Check that `name` is a dotted servername where each label only contains
+/// letters, digits and hyphens and does not start or end with a hyphen.
+/// Return the first label that does not follow these rules.
+pub fn validate_servername(name: &[u8]) -> Result<(), ServernameError> {
+    let mut offset = 0;
+    for (index, label) in name.split(|&c| c == b'.').enumerate() {
+        let error = |kind| ServernameError {
+            label: index,
+            offset,
+            kind,
+        };
+        if label.is_empty() {
+            return Err(error(ServernameErrorKind::Empty));
+        }
+        if label.len() > 63 {
+            return Err(error(ServernameErrorKind::TooLong));
+        }
+        if let Some(&c) = label
+            .iter()
+            .find(|&&c| !(c.is_ascii_alphanumeric() || c == b'-'))
+        {
+            return Err(error(ServernameErrorKind::InvalidChar(c)));
+        }
+        if label[0] == b'-' {
+            return Err(error(ServernameErrorKind::LeadingHyphen));
+        }
+        if label[label.len() - 1] == b'-' {
+            return Err(error(ServernameErrorKind::TrailingHyphen));
+        }
+        offset += label.len() + 1;
+    }
+    Ok(())
+}
+
+/// <hostname>   ::= <shortname> { '.' <shortname> }
+/// <shortname>  ::= ( <letter> | <number> ) { <letter> | <number> | '-' }
+pub fn hostname(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(separated_nonempty_list(tag("."), shortname))(input)
+}
+
+fn shortname(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(preceded(
+        alt((letter, number)),
+        take_while(|c: u8| c.is_ascii_alphanumeric() || c == b'-'),
+    ))(input)
+}
+
+/// <ip4addr>    ::= 1*3digit "." 1*3digit "." 1*3digit "." 1*3digit
+pub fn ip4addr(input: &[u8]) -> IResult<&[u8], Ipv4Addr> {
+    map_res(
+        take_while1(|c: u8| c.is_ascii_digit() || c == b'.'),
+        parse_utf8,
+    )(input)
+}
+
+/// <ip6addr>    ::= 1*hexdigit 7( ":" 1*hexdigit ) | "0:0:0:0:0:" ( "0" | "FFFF" ) ":" <ip4addr>
+/// The compressed `::` form is accepted too.
+pub fn ip6addr(input: &[u8]) -> IResult<&[u8], Ipv6Addr> {
+    map_res(
+        take_while1(|c: u8| c.is_ascii_hexdigit() || c == b':' || c == b'.'),
+        parse_utf8,
+    )(input)
+}
+
+fn parse_utf8<T: FromStr>(input: &[u8]) -> Result<T, ()> {
+    str::from_utf8(input)
+        .map_err(|_| ())?
+        .parse()
+        .map_err(|_| ())
+}
+
+fn host_char(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((letter, number, tag("-"), tag("."), tag(":")))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+    use nom::Err;
+
+    #[test]
+    fn host_empty() {
+        let empty: &[u8] = b"";
+        assert!(host(empty).is_err());
+    }
+
+    #[test]
+    fn host_hostname() {
+        let h: &[u8] = b"irc.example.com";
+        assert_eq!(host(h), Ok((&b""[..], Host::Hostname(h))));
+        let h: &[u8] = b"my-host1 :rest";
+        assert_eq!(
+            host(h),
+            Ok((&b" :rest"[..], Host::Hostname(&b"my-host1"[..])))
+        );
+        let h: &[u8] = b"deadbeef.com";
+        assert_eq!(host(h), Ok((&b""[..], Host::Hostname(h))));
+    }
+
+    #[test]
+    fn host_ipv4() {
+        let h: &[u8] = b"127.0.0.1 rest";
+        assert_eq!(
+            host(h),
+            Ok((&b" rest"[..], Host::Ipv4(Ipv4Addr::new(127, 0, 0, 1))))
+        );
+        let h: &[u8] = b"1.2.3.4.example.com";
+        assert_eq!(host(h), Ok((&b""[..], Host::Hostname(h))));
+        let h: &[u8] = b"1.2.3";
+        assert_eq!(host(h), Ok((&b""[..], Host::Hostname(h))));
+    }
+
+    #[test]
+    fn host_ipv6() {
+        let h: &[u8] = b"2001:db8::1";
+        assert_eq!(
+            host(h),
+            Ok((&b""[..], Host::Ipv6("2001:db8::1".parse().unwrap())))
+        );
+        let h: &[u8] = b"0::ffff:1.2.3.4";
+        assert_eq!(
+            host(h),
+            Ok((&b""[..], Host::Ipv6("::ffff:1.2.3.4".parse().unwrap())))
+        );
+    }
+
+    #[test]
+    fn servername_valid() {
+        let s: &[u8] = b"irc.example-1.com SQUIT";
+        assert_eq!(
+            servername(s),
+            Ok((&b" SQUIT"[..], &b"irc.example-1.com"[..]))
+        );
+        let s: &[u8] = b"localhost";
+        assert_eq!(servername(s), Ok((&b""[..], s)));
+    }
+
+    #[test]
+    fn servername_invalid() {
+        let s: &[u8] = b"irc.-example.com";
+        assert_eq!(servername(s), Err(Err::Error((s, ErrorKind::Verify))));
+        assert_eq!(
+            validate_servername(s),
+            Err(ServernameError {
+                label: 1,
+                offset: 4,
+                kind: ServernameErrorKind::LeadingHyphen
+            })
+        );
+    }
+
+    #[test]
+    fn validate_servername_errors() {
+        let error = |name: &[u8]| validate_servername(name).unwrap_err().kind;
+        assert_eq!(error(b""), ServernameErrorKind::Empty);
+        assert_eq!(error(b"irc..net"), ServernameErrorKind::Empty);
+        assert_eq!(error(b"irc.net."), ServernameErrorKind::Empty);
+        assert_eq!(error(b"irc-.net"), ServernameErrorKind::TrailingHyphen);
+        assert_eq!(error(b"irc.n_t"), ServernameErrorKind::InvalidChar(b'_'));
+        assert_eq!(error(&[b'a'; 64]), ServernameErrorKind::TooLong);
+        assert_eq!(validate_servername(b"a.b.c"), Ok(()));
+    }
+
+    #[test]
+    fn host_invalid() {
+        let h: &[u8] = b"-host";
+        assert!(host(h).is_err());
+        let h: &[u8] = b"host..com";
+        assert_eq!(host(h), Err(Err::Error((&b"..com"[..], ErrorKind::Not))));
+        let h: &[u8] = b"2001:db8:::1";
+        assert!(host(h).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! The features advertised by the server in RPL_ISUPPORT (005)
+//! `:server 005 me NICKLEN=30 CHANTYPES=# -EXCEPTS :are supported by this server`
+//! The tokens are spread over several messages and a later `-TOKEN` removes
+//! a token advertised before, so an `ISupport` is updated with each of them.
+//! ```
+//! use irc_parser::isupport::ISupport;
+//! use irc_parser::message::parse_message;
+//!
+//! let mut isupport = ISupport::new();
+//! let message = parse_message(b":irc.example.com 005 me NICKLEN=30 NETWORK=Example :are supported\r\n").unwrap();
+//! assert!(isupport.update(&message));
+//! assert_eq!(isupport.nicklen, Some(30));
+//! assert_eq!(isupport.network.as_deref(), Some(&b"Example"[..]));
+//! ```
+
+use crate::casemap::CaseMapping;
+use crate::message::Message;
+use crate::mode::{ChanModes, PrefixModes};
+use crate::numeric::Numeric;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// The maximum number of targets of each command, from the TARGMAX token:
+/// `PRIVMSG:4,JOIN:`, a command without number has no limit
+pub type TargMax = Vec<(Vec<u8>, Option<usize>)>;
+
+/// The tokens of RPL_ISUPPORT, `None` when a token was not advertised
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ISupport {
+    pub casemapping: Option<CaseMapping>,
+    pub channellen: Option<usize>,
+    /// The channel modes by category: `beI,k,l,imnpst`
+    pub chanmodes: Option<Vec<u8>>,
+    pub chantypes: Option<Vec<u8>>,
+    /// The mode letter of the ban exceptions, `e` by default
+    pub excepts: Option<u8>,
+    /// The mode letter of the invite exceptions, `I` by default
+    pub invex: Option<u8>,
+    /// The maximum length of a line, crlf included and tags excluded
+    pub linelen: Option<usize>,
+    /// The maximum number of modes with an argument in a MODE,
+    /// `usize::MAX` when the server advertises no limit
+    pub modes: Option<usize>,
+    pub network: Option<Vec<u8>>,
+    pub nicklen: Option<usize>,
+    /// The membership modes and their prefixes: `(ov)@+`
+    pub prefix: Option<Vec<u8>>,
+    /// The prefixes allowed before a channel to only message some members
+    pub statusmsg: Option<Vec<u8>>,
+    pub targmax: Option<TargMax>,
+    /// Every other token with its value, unescaped
+    pub other: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl ISupport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the advertised channel modes, or the ones of RFC 2812
+    pub fn chanmodes(&self) -> ChanModes {
+        self.chanmodes
+            .as_deref()
+            .and_then(ChanModes::parse)
+            .unwrap_or_default()
+    }
+
+    /// Return the advertised membership modes, or the ones of RFC 2812
+    pub fn prefixes(&self) -> PrefixModes {
+        self.prefix
+            .as_deref()
+            .and_then(PrefixModes::parse)
+            .unwrap_or_default()
+    }
+
+    /// Return the maximum number of targets of a command, `None` if there is
+    /// no limit
+    /// TARGMAX is used when advertised, otherwise the older MAXTARGETS limits
+    /// PRIVMSG and NOTICE.
+    pub fn max_targets(&self, command: &[u8]) -> Option<usize> {
+        if let Some(targmax) = &self.targmax {
+            return targmax
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(command))
+                .and_then(|(_, max)| *max);
+        }
+        let is_text =
+            command.eq_ignore_ascii_case(b"PRIVMSG") || command.eq_ignore_ascii_case(b"NOTICE");
+        match self.other.get(&b"MAXTARGETS"[..]) {
+            Some(Some(max)) if is_text => number(max),
+            _ => None,
+        }
+    }
+
+    /// Apply the tokens of a RPL_ISUPPORT, between the nick and the trailing
+    /// text, and return false if the message is something else
+    pub fn update(&mut self, message: &Message) -> bool {
+        if message.command.numeric() != Some(Numeric::RplISupport) {
+            return false;
+        }
+        for token in message.params.middles().iter().skip(1) {
+            self.apply(token);
+        }
+        true
+    }
+
+    /// Apply a single token: `KEY`, `KEY=value` or `-KEY`
+    /// The values are unescaped and an invalid value is ignored.
+    pub fn apply(&mut self, token: &[u8]) {
+        if let Some(key) = token.strip_prefix(b"-") {
+            self.remove(key);
+            return;
+        }
+        let (key, value) = match token.iter().position(|&c| c == b'=') {
+            Some(eq) => (&token[..eq], Some(unescape(&token[eq + 1..]))),
+            None => (token, None),
+        };
+        let value = value.as_deref();
+        let bytes = || value.map(<[u8]>::to_vec);
+        match key {
+            b"CASEMAPPING" => self.casemapping = value.and_then(CaseMapping::parse),
+            b"CHANNELLEN" => self.channellen = value.and_then(number),
+            b"CHANMODES" => self.chanmodes = bytes(),
+            b"CHANTYPES" => self.chantypes = Some(bytes().unwrap_or_default()),
+            b"EXCEPTS" => self.excepts = letter(value, b'e'),
+            b"INVEX" => self.invex = letter(value, b'I'),
+            b"LINELEN" => self.linelen = value.and_then(number),
+            b"MODES" => {
+                self.modes = match value {
+                    None | Some(b"") => Some(usize::MAX),
+                    Some(value) => number(value),
+                }
+            }
+            b"NETWORK" => self.network = bytes(),
+            b"NICKLEN" => self.nicklen = value.and_then(number),
+            b"PREFIX" => self.prefix = Some(bytes().unwrap_or_default()),
+            b"STATUSMSG" => self.statusmsg = bytes(),
+            b"TARGMAX" => self.targmax = Some(targmax(value.unwrap_or(b""))),
+            key => {
+                self.other.insert(key.to_vec(), bytes());
+            }
+        }
+    }
+
+    /// Forget a token advertised before
+    fn remove(&mut self, key: &[u8]) {
+        match key {
+            b"CASEMAPPING" => self.casemapping = None,
+            b"CHANNELLEN" => self.channellen = None,
+            b"CHANMODES" => self.chanmodes = None,
+            b"CHANTYPES" => self.chantypes = None,
+            b"EXCEPTS" => self.excepts = None,
+            b"INVEX" => self.invex = None,
+            b"LINELEN" => self.linelen = None,
+            b"MODES" => self.modes = None,
+            b"NETWORK" => self.network = None,
+            b"NICKLEN" => self.nicklen = None,
+            b"PREFIX" => self.prefix = None,
+            b"STATUSMSG" => self.statusmsg = None,
+            b"TARGMAX" => self.targmax = None,
+            key => {
+                self.other.remove(key);
+            }
+        }
+    }
+}
+
+fn number(value: &[u8]) -> Option<usize> {
+    if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    value.iter().try_fold(0usize, |acc, d| {
+        acc.checked_mul(10)?.checked_add(usize::from(d - b'0'))
+    })
+}
+
+/// A mode letter, `default` if the token has no value
+fn letter(value: Option<&[u8]>, default: u8) -> Option<u8> {
+    match value {
+        None | Some(b"") => Some(default),
+        Some(&[letter]) => Some(letter),
+        Some(_) => None,
+    }
+}
+
+fn targmax(value: &[u8]) -> TargMax {
+    value
+        .split(|&c| c == b',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.iter().position(|&c| c == b':') {
+            Some(colon) => (entry[..colon].to_vec(), number(&entry[colon + 1..])),
+            None => (entry.to_vec(), None),
+        })
+        .collect()
+}
+
+/// Replace the `\xHH` escapes of a value by the byte they stand for
+pub fn unescape(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value.windows(2).any(|w| w == b"\\x") {
+        return Cow::Borrowed(value);
+    }
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value;
+    while let Some((&c, tail)) = rest.split_first() {
+        let byte = match tail {
+            [b'x', high, low, ..] if c == b'\\' => hex(*high).zip(hex(*low)),
+            _ => None,
+        };
+        match byte {
+            Some((high, low)) => {
+                bytes.push(high << 4 | low);
+                rest = &tail[3..];
+            }
+            None => {
+                bytes.push(c);
+                rest = tail;
+            }
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+fn hex(c: u8) -> Option<u8> {
+    char::from(c).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn isupport_tokens() {
+        let mut isupport = ISupport::new();
+        let m = b":irc.example.com 005 me CASEMAPPING=ascii CHANNELLEN=64 CHANMODES=beI,k,l,imnpst CHANTYPES=#& EXCEPTS INVEX=J MODES=4 PREFIX=(ov)@+ STATUSMSG=@+ TARGMAX=PRIVMSG:4,JOIN: :are supported\r\n";
+        assert!(isupport.update(&parse_message(m).unwrap()));
+        assert_eq!(isupport.casemapping, Some(CaseMapping::Ascii));
+        assert_eq!(isupport.channellen, Some(64));
+        assert_eq!(isupport.chanmodes.as_deref(), Some(&b"beI,k,l,imnpst"[..]));
+        assert_eq!(isupport.chantypes.as_deref(), Some(&b"#&"[..]));
+        assert_eq!(isupport.excepts, Some(b'e'));
+        assert_eq!(isupport.invex, Some(b'J'));
+        assert_eq!(isupport.modes, Some(4));
+        assert_eq!(isupport.prefix.as_deref(), Some(&b"(ov)@+"[..]));
+        assert_eq!(isupport.statusmsg.as_deref(), Some(&b"@+"[..]));
+        assert_eq!(
+            isupport.targmax,
+            Some(vec![
+                (b"PRIVMSG".to_vec(), Some(4)),
+                (b"JOIN".to_vec(), None)
+            ])
+        );
+        assert!(isupport.other.is_empty());
+        assert_eq!(isupport.max_targets(b"privmsg"), Some(4));
+        assert_eq!(isupport.max_targets(b"JOIN"), None);
+        assert_eq!(isupport.chanmodes().arg_when_set, ['l']);
+        assert_eq!(isupport.prefixes().symbol('v'), Some(b'+'));
+        let ping = parse_message(b"PING :a\r\n").unwrap();
+        assert!(!isupport.update(&ping));
+    }
+
+    #[test]
+    fn isupport_other_and_negation() {
+        let mut isupport = ISupport::new();
+        for token in [
+            &b"NICKLEN=30"[..],
+            b"SAFELIST",
+            b"NETWORK=A\\x20B",
+            b"MODES",
+            b"CHANTYPES=",
+        ] {
+            isupport.apply(token);
+        }
+        assert_eq!(isupport.network.as_deref(), Some(&b"A B"[..]));
+        assert_eq!(isupport.modes, Some(usize::MAX));
+        assert_eq!(isupport.chantypes.as_deref(), Some(&b""[..]));
+        assert_eq!(isupport.other.get(&b"SAFELIST"[..]), Some(&None));
+        isupport.apply(b"MAXTARGETS=3");
+        assert_eq!(isupport.max_targets(b"NOTICE"), Some(3));
+        assert_eq!(isupport.max_targets(b"KICK"), None);
+        isupport.apply(b"-MAXTARGETS");
+        isupport.apply(b"-SAFELIST");
+        isupport.apply(b"-NICKLEN");
+        assert!(isupport.other.is_empty());
+        assert_eq!(isupport.nicklen, None);
+        // invalid values
+        isupport.apply(b"NICKLEN=abc");
+        isupport.apply(b"EXCEPTS=ab");
+        assert_eq!(isupport.nicklen, None);
+        assert_eq!(isupport.excepts, None);
+    }
+
+    #[test]
+    fn isupport_unescape() {
+        assert_eq!(unescape(b"a\\x3Db\\x5c\\x"), &b"a=b\\\\x"[..]);
+        assert!(matches!(unescape(b"plain"), Cow::Borrowed(_)));
+    }
+}
@@ -0,0 +1,335 @@
+//! The JSON representation of the irc-parser-tests suite:
+//! `{"tags": {"id": "1", "k": null}, "source": "nick!u@h", "command": "PRIVMSG", "params": ["#a", "hi"]}`
+//! `tags` and `source` are omitted when the message has none, a tag without
+//! value is `null`.
+
+use crate::builder::MessageBuilder;
+use crate::command::command;
+use crate::error::{error_offset, ParseError};
+use crate::message::{needs_trailing, Message};
+use crate::prefix::prefix;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while};
+use nom::combinator::{all_consuming, map};
+use nom::error::ErrorKind;
+use nom::multi::separated_list;
+use nom::sequence::{delimited, preceded, separated_pair, terminated};
+use nom::{Err, IResult};
+
+impl<'a> Message<'a> {
+    /// Return the JSON representation of the message
+    /// The bytes that are not valid UTF-8 are replaced by U+FFFD.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        if !self.tags.is_empty() {
+            json.push_str("\"tags\":{");
+            for (i, tag) in self.tags.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                push_string(&mut json, tag.key);
+                json.push(':');
+                if tag.value.is_empty() {
+                    json.push_str("null");
+                } else {
+                    push_string(&mut json, &tag.value);
+                }
+            }
+            json.push_str("},");
+        }
+        if let Some(prefix) = &self.prefix {
+            json.push_str("\"source\":");
+            push_string(&mut json, &prefix.to_bytes());
+            json.push(',');
+        }
+        json.push_str("\"command\":");
+        push_string(&mut json, self.command.as_str().as_bytes());
+        json.push_str(",\"params\":[");
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_string(&mut json, param);
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+fn push_string(json: &mut String, bytes: &[u8]) {
+    json.push('"');
+    for c in String::from_utf8_lossy(bytes).chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Convert the JSON representation of a message in the line to send, crlf
+/// included
+/// The `command` and `params` keys are mandatory, other keys are refused.
+/// The message is checked like `MessageBuilder::build` does, a command, a
+/// source, a tag key or a parameter that can't be sent as is is refused with
+/// `InvalidJson` at offset 0: a CR, LF or NUL anywhere, or a parameter
+/// before the last one that is empty, starts with ':' or contains a space.
+pub fn from_json(json: &[u8]) -> Result<Vec<u8>, ParseError> {
+    let (_, fields) = all_consuming(delimited(ws, object(field), ws))(json).map_err(|err| {
+        ParseError::InvalidJson {
+            offset: error_offset(json, err),
+        }
+    })?;
+    let invalid = ParseError::InvalidJson { offset: 0 };
+    let mut tags = None;
+    let mut source = None;
+    let mut name = None;
+    let mut params = None;
+    for field in fields {
+        match field {
+            Field::Tags(value) => tags = Some(value),
+            Field::Source(value) => source = value,
+            Field::Command(value) => name = Some(value),
+            Field::Params(value) => params = Some(value),
+        }
+    }
+    let (name, params) = match (name, params) {
+        (Some(name), Some(params)) => (name, params),
+        _ => return Err(invalid),
+    };
+
+    let command = match all_consuming(command)(&name) {
+        Ok((_, command)) => command,
+        Err(_) => return Err(invalid),
+    };
+    let mut builder = MessageBuilder::new(command);
+    for (key, value) in tags.iter().flatten() {
+        builder = builder.tag(key, value.as_deref().unwrap_or_default());
+    }
+    if let Some(source) = &source {
+        match all_consuming(prefix)(source) {
+            Ok((_, source)) => builder = builder.prefix(source),
+            Err(_) => return Err(invalid),
+        }
+    }
+    let count = params.len();
+    for (i, param) in params.iter().enumerate() {
+        builder = if i + 1 == count && needs_trailing(param) {
+            builder.trailing(param)
+        } else {
+            builder.param(param)
+        };
+    }
+    match builder.build() {
+        Ok(message) => Ok(message.to_bytes()),
+        Err(_) => Err(invalid),
+    }
+}
+
+type TagEntry = (Vec<u8>, Option<Vec<u8>>);
+type TagList = Vec<TagEntry>;
+
+enum Field {
+    Tags(TagList),
+    Source(Option<Vec<u8>>),
+    Command(Vec<u8>),
+    Params(Vec<Vec<u8>>),
+}
+
+fn field(input: &[u8]) -> IResult<&[u8], Field> {
+    alt((
+        preceded(
+            terminated(tag("\"tags\""), colon),
+            map(object(tag_entry), Field::Tags),
+        ),
+        preceded(
+            terminated(tag("\"source\""), colon),
+            map(nullable, Field::Source),
+        ),
+        preceded(
+            terminated(tag("\"command\""), colon),
+            map(string, Field::Command),
+        ),
+        preceded(
+            terminated(tag("\"params\""), colon),
+            map(
+                delimited(
+                    terminated(tag("["), ws),
+                    separated_list(delimited(ws, tag(","), ws), string),
+                    preceded(ws, tag("]")),
+                ),
+                Field::Params,
+            ),
+        ),
+    ))(input)
+}
+
+fn tag_entry(input: &[u8]) -> IResult<&[u8], TagEntry> {
+    separated_pair(string, colon, nullable)(input)
+}
+
+/// `{` <entry> [ ',' <entry> ]* `}`
+fn object<'a, O>(
+    entry: impl Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], Vec<O>> {
+    delimited(
+        terminated(tag("{"), ws),
+        separated_list(delimited(ws, tag(","), ws), entry),
+        preceded(ws, tag("}")),
+    )
+}
+
+fn colon(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    delimited(ws, tag(":"), ws)(input)
+}
+
+fn ws(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while(|c| matches!(c, b' ' | b'\t' | b'\r' | b'\n'))(input)
+}
+
+fn nullable(input: &[u8]) -> IResult<&[u8], Option<Vec<u8>>> {
+    alt((map(tag("null"), |_| None), map(string, Some)))(input)
+}
+
+/// A JSON string, unescaped and encoded in UTF-8
+fn string(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+    let error = |rest| Err(Err::Error((rest, ErrorKind::Escaped)));
+    let mut rest = tag("\"")(input)?.0;
+    let mut value = Vec::new();
+    loop {
+        match rest {
+            [b'"', tail @ ..] => return Ok((tail, value)),
+            [b'\\', b'u', tail @ ..] => {
+                let (tail, mut code) = hex4(tail).ok_or(Err::Error((rest, ErrorKind::Escaped)))?;
+                rest = tail;
+                if (0xD800..0xDC00).contains(&code) {
+                    let low = match rest {
+                        [b'\\', b'u', tail @ ..] => hex4(tail),
+                        _ => None,
+                    };
+                    match low {
+                        Some((tail, low)) if (0xDC00..0xE000).contains(&low) => {
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            rest = tail;
+                        }
+                        _ => return error(rest),
+                    }
+                }
+                let c = match core::char::from_u32(code) {
+                    Some(c) => c,
+                    None => return error(rest),
+                };
+                value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            [b'\\', c, tail @ ..] => {
+                value.push(match c {
+                    b'"' => b'"',
+                    b'\\' => b'\\',
+                    b'/' => b'/',
+                    b'b' => 0x08,
+                    b'f' => 0x0C,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    _ => return error(rest),
+                });
+                rest = tail;
+            }
+            [c, tail @ ..] if *c >= 0x20 => {
+                value.push(*c);
+                rest = tail;
+            }
+            _ => return error(rest),
+        }
+    }
+}
+
+/// Four hex digits, without sign
+fn hex4(input: &[u8]) -> Option<(&[u8], u32)> {
+    let digits = input.get(..4)?;
+    let code = digits
+        .iter()
+        .try_fold(0, |code, &d| Some(code * 16 + char::from(d).to_digit(16)?))?;
+    Some((&input[4..], code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn message_to_json() {
+        let m = parse_message(b"@a=b\\sc;k :nick!u@h PRIVMSG #a :say \"hi\"\r\n").unwrap();
+        assert_eq!(
+            m.to_json(),
+            r##"{"tags":{"a":"b c","k":null},"source":"nick!u@h","command":"PRIVMSG","params":["#a","say \"hi\""]}"##
+        );
+        let m = parse_message(b"PING\r\n").unwrap();
+        assert_eq!(m.to_json(), r##"{"command":"PING","params":[]}"##);
+    }
+
+    #[test]
+    fn message_from_json() {
+        let json = br##"{
+            "tags": {"a": "b c", "k": null},
+            "source": "nick!u@h",
+            "command": "PRIVMSG",
+            "params": ["#a", "caf\u00e9 \ud83d\ude00"]
+        }"##;
+        let line = from_json(json).unwrap();
+        assert_eq!(
+            line,
+            "@a=b\\sc;k :nick!u@h PRIVMSG #a :café 😀\r\n"
+                .as_bytes()
+                .to_vec()
+        );
+        let m = parse_message(&line).unwrap();
+        assert_eq!(from_json(m.to_json().as_bytes()), Ok(line.clone()));
+        assert_eq!(
+            from_json(br##"{"command": "PING", "params": [""]}"##),
+            Ok(b"PING :\r\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn invalid_json() {
+        assert!(from_json(br##"{"params": []}"##).is_err());
+        assert!(from_json(br##"{"command": "PING", "params": [], "x": 1}"##).is_err());
+        assert!(from_json(br##"{"command": "P\q", "params": []}"##).is_err());
+        assert!(from_json(br##"{"command": "PING", "params": ["\ud83d"]}"##).is_err());
+        assert!(from_json(br##"{"command": "PING", "params": ["\u+04a"]}"##).is_err());
+    }
+
+    #[test]
+    fn unsendable_json() {
+        let refused: &[&[u8]] = &[
+            br##"{"command": "PRIVMSG", "params": ["#a", "x\r\nQUIT"]}"##,
+            br##"{"command": "PRIVMSG", "params": ["#a", "x\u000d\u000aQUIT"]}"##,
+            br##"{"command": "PRIVMSG", "params": ["#a b", "hi"]}"##,
+            br##"{"command": "PRIVMSG", "params": ["", "hi"]}"##,
+            br##"{"command": "PRIVMSG", "params": [":a", "hi"]}"##,
+            br##"{"command": "PRIVMSG", "params": ["#a", "a\u0000b"]}"##,
+            br##"{"command": "PRIVMSG #a", "params": ["hi"]}"##,
+            br##"{"command": "1234", "params": []}"##,
+            br##"{"tags": {"a b": null}, "command": "PING", "params": ["x"]}"##,
+            br##"{"source": "a b", "command": "PING", "params": ["x"]}"##,
+        ];
+        for json in refused {
+            assert_eq!(
+                from_json(json),
+                Err(ParseError::InvalidJson { offset: 0 }),
+                "{}",
+                String::from_utf8_lossy(json)
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Request and response over IRC with labeled-response: every request gets a
+//! `label` tag that the server copies on its response, a single message, a
+//! `labeled-response` batch or an `ACK` when there is nothing to answer.
+//! ```
+//! use irc_parser::batch::BatchCollector;
+//! use irc_parser::builder::MessageBuilder;
+//! use irc_parser::command::Command;
+//! use irc_parser::label::LabelTracker;
+//! use irc_parser::message::parse_message;
+//!
+//! let mut tracker = LabelTracker::new();
+//! let mut whois = MessageBuilder::new(Command::Whois).param("nick").build().unwrap();
+//! let label = tracker.label(&mut whois, "whois nick");
+//! assert_eq!(whois.tags.label(), Some("1"));
+//!
+//! let mut collector = BatchCollector::new();
+//! let ack = parse_message(b"@label=1 :irc ACK\r\n").unwrap();
+//! let event = collector.feed(ack).unwrap();
+//! assert_eq!(tracker.feed(&event), Some((label, "whois nick")));
+//! assert_eq!(tracker.pending(), 0);
+//! ```
+
+use crate::batch::Event;
+use crate::message::Message;
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Give a label to the outgoing requests and find the request answered by
+/// the incoming messages and batches
+/// `T` is anything identifying the request for the caller.
+#[derive(Debug, Clone)]
+pub struct LabelTracker<T> {
+    next: u64,
+    pending: BTreeMap<Vec<u8>, T>,
+}
+
+impl<T> LabelTracker<T> {
+    pub fn new() -> Self {
+        LabelTracker {
+            next: 1,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Tag the message with a new label, remember the request until its
+    /// response and return the label
+    /// A label already on the message is replaced.
+    pub fn label(&mut self, message: &mut Message<'_>, request: T) -> Vec<u8> {
+        let label = self.next.to_string().into_bytes();
+        self.next += 1;
+        message.tags.set(b"label", label.clone());
+        self.pending.insert(label.clone(), request);
+        label
+    }
+
+    /// Return the label and the request answered by an event of a
+    /// `BatchCollector`, the request is then forgotten
+    /// Return `None` for the events without label or with an unknown one,
+    /// they don't answer any request of the tracker.
+    pub fn feed(&mut self, event: &Event<'_>) -> Option<(Vec<u8>, T)> {
+        let tags = match event {
+            Event::Message(message) => &message.tags,
+            Event::Batch(batch) => &batch.tags,
+        };
+        let label = tags.get("label")?;
+        self.pending.remove_entry(label)
+    }
+
+    /// Return the number of requests still waiting for their response
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Forget a request, when the server will never answer it
+    pub fn cancel(&mut self, label: &[u8]) -> Option<T> {
+        self.pending.remove(label)
+    }
+}
+
+impl<T> Default for LabelTracker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::BatchCollector;
+    use crate::message::{parse_all, parse_message};
+
+    #[test]
+    fn label_responses() {
+        let mut tracker = LabelTracker::new();
+        let mut requests: Vec<_> = parse_all(b"WHO #a\r\nPRIVMSG #a :hi\r\nMODE #a\r\n")
+            .map(Result::unwrap)
+            .collect();
+        let labels: Vec<_> = requests
+            .iter_mut()
+            .enumerate()
+            .map(|(i, request)| tracker.label(request, i))
+            .collect();
+        assert_eq!(labels, vec![b"1".to_vec(), b"2".to_vec(), b"3".to_vec()]);
+        assert_eq!(requests[2].to_bytes(), b"@label=3 MODE #a\r\n");
+
+        let input = b"@label=1 :irc BATCH +x labeled-response\r\n\
+            @batch=x :irc 352 me #a u h s n H :0 Name\r\n\
+            @batch=x :irc 315 me #a :End of WHO list\r\n\
+            :irc BATCH -x\r\n\
+            :irc PING :unrelated\r\n\
+            @label=2 :me!u@h PRIVMSG #a :hi\r\n\
+            @label=9 :irc ACK\r\n";
+        let mut collector = BatchCollector::new();
+        let answered: Vec<_> = parse_all(input)
+            .filter_map(|message| collector.feed(message.unwrap()))
+            .filter_map(|event| tracker.feed(&event))
+            .map(|(_, request)| request)
+            .collect();
+        assert_eq!(answered, vec![0, 1]);
+        assert_eq!(tracker.pending(), 1);
+        assert_eq!(tracker.cancel(b"3"), Some(2));
+        // answered twice
+        let message = parse_message(b"@label=1 :irc ACK\r\n").unwrap();
+        assert_eq!(tracker.feed(&Event::Message(message)), None);
+    }
+
+    #[test]
+    fn label_replaces_label() {
+        let mut tracker = LabelTracker::new();
+        let mut request = parse_message(b"@label=x;a=b MODE #a\r\n").unwrap();
+        assert_eq!(tracker.label(&mut request, ()), b"1");
+        assert_eq!(request.to_bytes(), b"@a=b;label=1 MODE #a\r\n");
+        let message = parse_message(b"@label=x :irc ACK\r\n").unwrap();
+        assert_eq!(tracker.feed(&Event::Message(message)), None);
+        assert_eq!(tracker.pending(), 1);
+    }
+}
@@ -1 +1,67 @@
+//! Without the default `std` feature the crate is `no_std` and only needs
+//! `alloc`. nom 5 then requires a nightly compiler.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod batch;
+pub mod builder;
+pub mod bulk;
+pub mod cap;
+pub mod casemap;
+pub mod channel;
+pub mod command;
+pub mod config;
+pub mod ctcp;
+pub mod dcc;
+pub mod error;
+pub mod extban;
+pub mod format;
+pub mod host;
+pub mod isupport;
+pub mod json;
+pub mod label;
+pub mod lossy;
+pub mod mask;
+pub mod message;
+pub mod metadata;
+pub mod mode;
+pub mod modelist;
+pub mod monitor;
+pub mod names;
+pub mod numeric;
+mod output;
+pub mod owned;
+pub mod params;
 pub mod parser;
+pub mod prefix;
+#[cfg(feature = "std")]
+pub mod reader;
+pub mod recover;
+pub mod registration;
+#[cfg(feature = "s2s")]
+pub mod s2s;
+pub mod sasl;
+mod scan;
+pub mod split;
+#[cfg(feature = "state")]
+pub mod state;
+pub mod streaming;
+pub mod tags;
+pub mod target;
+pub mod typed;
+pub mod userhost;
+pub mod watch;
+pub mod who;
+
+pub use config::{ParserConfig, Rfc};
+#[cfg(feature = "std")]
+pub use error::ReadError;
+pub use error::{BuildError, EncodeError, ParseError};
+pub use json::from_json;
+pub use message::{
+    parse_all, parse_all_with, parse_message, parse_message_mut, parse_message_mut_with,
+    parse_message_with, Message,
+};
+pub use owned::OwnedMessage;
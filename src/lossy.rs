@@ -0,0 +1,307 @@
+//! Best-effort parsing of the invalid lines sent by some servers
+//! Instead of rejecting the line `parse_lossy` builds the most plausible
+//! message and describes every deviation from the grammar with a `Warning`.
+
+use crate::command::{command_with, Command};
+use crate::config::{ParserConfig, Rfc};
+use crate::error::ParseError;
+use crate::message::Message;
+use crate::params::Params;
+use crate::prefix::prefix_with;
+use crate::tags::{single_tag, Tags};
+use alloc::vec::Vec;
+
+/// A deviation from the grammar found by `parse_lossy`
+/// The offsets are relative to the start of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// The line is not terminated, it was parsed up to the end of the input
+    MissingLineEnding,
+    /// The line is terminated by a lone LF instead of a crlf
+    BareLf,
+    /// Something follows the line ending at `offset`, it was ignored
+    TrailingData { offset: usize },
+    /// The line is `len` bytes long, tags excluded, while only `max` bytes
+    /// are allowed
+    LineTooLong { len: usize, max: usize },
+    /// The tags are `len` bytes long while only `max` bytes are allowed
+    TagsTooLong { len: usize, max: usize },
+    /// The tag at `offset` is malformed and was dropped
+    InvalidTag { offset: usize },
+    /// The prefix at `offset` is malformed and was dropped
+    InvalidPrefix { offset: usize },
+    /// The command at `offset` is neither a word nor a 3-digit numeric, it
+    /// was kept as `Command::Unknown`
+    InvalidCommand { offset: usize },
+    /// A tab is in the parameters at `offset`
+    Tab { offset: usize },
+    /// A NUL or a CR is in the parameters at `offset`, it was kept
+    IllegalByte { offset: usize, byte: u8 },
+    /// There are `count` parameters while only 15 are allowed
+    TooManyParams { count: usize },
+    /// The text of a `PRIVMSG` or a `NOTICE` starting at `offset` was sent
+    /// without ':', its words were joined back into the trailing parameter
+    MissingTrailingColon { offset: usize },
+}
+
+/// A message and the deviations found while parsing it
+pub type Lossy<'a> = (Message<'a>, Vec<Warning>);
+
+/// Parse a line with the default configuration, see `parse_lossy_with`
+/// ```
+/// use irc_parser::lossy::{parse_lossy, Warning};
+///
+/// let (message, warnings) = parse_lossy(b"PRIVMSG #chan hello world\n").unwrap();
+/// assert_eq!(message.params.trailing(), Some(&b"hello world"[..]));
+/// assert_eq!(
+///     warnings,
+///     [Warning::BareLf, Warning::MissingTrailingColon { offset: 14 }]
+/// );
+/// ```
+pub fn parse_lossy(input: &[u8]) -> Result<Lossy<'_>, ParseError> {
+    parse_lossy_with(input, &ParserConfig::default())
+}
+
+/// Parse the first line of the input however malformed it is
+/// The malformed tags and prefix are dropped, an invalid command is kept as
+/// `Command::Unknown` and the parameters are split on spaces whatever bytes
+/// they contain.
+/// Only fail with `ParseError::MissingCommand` if there is no command at all.
+pub fn parse_lossy_with<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Result<Lossy<'a>, ParseError> {
+    let mut warnings = Vec::new();
+    let offset = |part: &[u8]| part.as_ptr() as usize - input.as_ptr() as usize;
+
+    let line = match input.iter().position(|&c| c == b'\n') {
+        Some(pos) => {
+            if pos + 1 < input.len() {
+                warnings.push(Warning::TrailingData { offset: pos + 1 });
+            }
+            match input[..pos].strip_suffix(b"\r") {
+                Some(line) => line,
+                None => {
+                    warnings.push(Warning::BareLf);
+                    &input[..pos]
+                }
+            }
+        }
+        None => {
+            warnings.push(Warning::MissingLineEnding);
+            input.strip_suffix(b"\r").unwrap_or(input)
+        }
+    };
+    // the limits count a crlf even if it is missing
+    let tags_len = config.tags_len(line);
+    if tags_len > config.max_tags_len {
+        warnings.push(Warning::TagsTooLong {
+            len: tags_len,
+            max: config.max_tags_len,
+        });
+    }
+    if line.len() + 2 - tags_len > config.max_line_len {
+        warnings.push(Warning::LineTooLong {
+            len: line.len() + 2 - tags_len,
+            max: config.max_line_len,
+        });
+    }
+
+    let mut rest = line;
+    let mut tags = Tags::new();
+    if let Some(after) = rest.strip_prefix(b"@") {
+        let (raw, after) = split_word(after);
+        for item in raw.split(|&c| c == b';') {
+            match single_tag(item) {
+                Ok((b"", tag)) => tags.push(tag.key, tag.value),
+                _ => warnings.push(Warning::InvalidTag {
+                    offset: offset(item),
+                }),
+            }
+        }
+        rest = after;
+    }
+
+    rest = skip_spaces(rest);
+    let mut prefix = None;
+    if let Some(after) = rest.strip_prefix(b":") {
+        let (raw, after) = split_word(after);
+        match prefix_with(config)(raw) {
+            Ok((b"", p)) => prefix = Some(p),
+            _ => warnings.push(Warning::InvalidPrefix {
+                offset: offset(raw),
+            }),
+        }
+        rest = skip_spaces(after);
+    }
+
+    let (raw, after) = split_word(rest);
+    let command = match command_with(config)(raw) {
+        _ if raw.is_empty() => {
+            return Err(ParseError::MissingCommand {
+                offset: offset(raw),
+            })
+        }
+        Ok((b"", command)) => command,
+        _ => {
+            warnings.push(Warning::InvalidCommand {
+                offset: offset(raw),
+            });
+            Command::Unknown(raw)
+        }
+    };
+    rest = after;
+
+    let max_middles = match config.rfc {
+        Rfc::Rfc1459 => None,
+        Rfc::Rfc2812 => Some(14),
+    };
+    let mut params = Params::new();
+    loop {
+        rest = skip_spaces(rest);
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(trailing) = rest.strip_prefix(b":") {
+            check_param(trailing, offset(trailing), &mut warnings);
+            params.push_trailing(trailing);
+            break;
+        }
+        if Some(params.len()) == max_middles {
+            check_param(rest, offset(rest), &mut warnings);
+            params.push_trailing(rest);
+            break;
+        }
+        let (middle, after) = split_word(rest);
+        check_param(middle, offset(middle), &mut warnings);
+        params.push(middle);
+        rest = after;
+    }
+    if params.len() > 15 {
+        warnings.push(Warning::TooManyParams {
+            count: params.len(),
+        });
+    }
+
+    // the text of a message was most likely split on its spaces
+    let is_text = matches!(command, Command::Privmsg | Command::Notice);
+    if is_text && !params.has_trailing() && params.len() > 2 {
+        let start = offset(params[1]);
+        let end = offset(params[params.len() - 1]) + params[params.len() - 1].len();
+        warnings.push(Warning::MissingTrailingColon { offset: start });
+        let target = params[0];
+        params = Params::new();
+        params.push(target);
+        params.push_trailing(&input[start..end]);
+    }
+
+    Ok((
+        Message {
+            tags,
+            prefix,
+            command,
+            params,
+        },
+        warnings,
+    ))
+}
+
+/// Split the input on its first space
+fn split_word(input: &[u8]) -> (&[u8], &[u8]) {
+    let end = input.iter().position(|&c| c == b' ').unwrap_or(input.len());
+    input.split_at(end)
+}
+
+fn skip_spaces(input: &[u8]) -> &[u8] {
+    let start = input.iter().position(|&c| c != b' ').unwrap_or(input.len());
+    &input[start..]
+}
+
+/// Warn about the tabs and the illegal bytes of a parameter found at `offset`
+fn check_param(param: &[u8], offset: usize, warnings: &mut Vec<Warning>) {
+    for (i, &c) in param.iter().enumerate() {
+        match c {
+            b'\t' => warnings.push(Warning::Tab { offset: offset + i }),
+            0x00 | b'\r' => warnings.push(Warning::IllegalByte {
+                offset: offset + i,
+                byte: c,
+            }),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+    use crate::prefix::Prefix;
+
+    #[test]
+    fn lossy_valid() {
+        let m: &[u8] = b"@a=b :nick!user@host PRIVMSG #chan :hello world\r\n";
+        let (message, warnings) = parse_lossy(m).unwrap();
+        assert_eq!(Ok(message), parse_message(m));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn lossy_invalid() {
+        let m: &[u8] = b"@a=b;=c :nick!@host 12 #chan\tx a\0b :c\r\rest";
+        let (message, warnings) = parse_lossy(m).unwrap();
+        assert_eq!(message.tags.get("a"), Some(&b"b"[..]));
+        assert_eq!(message.prefix, None);
+        assert_eq!(message.command, Command::Unknown(b"12"));
+        assert_eq!(
+            &message.params[..],
+            &[&b"#chan\tx"[..], &b"a\0b"[..], &b"c\r\rest"[..]]
+        );
+        assert_eq!(
+            warnings,
+            [
+                Warning::MissingLineEnding,
+                Warning::InvalidTag { offset: 5 },
+                Warning::InvalidPrefix { offset: 9 },
+                Warning::InvalidCommand { offset: 20 },
+                Warning::Tab { offset: 28 },
+                Warning::IllegalByte {
+                    offset: 32,
+                    byte: 0
+                },
+                Warning::IllegalByte {
+                    offset: 37,
+                    byte: b'\r'
+                },
+                Warning::IllegalByte {
+                    offset: 38,
+                    byte: b'\r'
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lossy_missing_colon() {
+        let m: &[u8] = b":irc.example.com NOTICE *  looking up  your host\r\n";
+        let (message, warnings) = parse_lossy(m).unwrap();
+        assert_eq!(message.prefix, Some(Prefix::Server(b"irc.example.com")));
+        assert_eq!(message.params.middles(), &[&b"*"[..]]);
+        assert_eq!(
+            message.params.trailing(),
+            Some(&b"looking up  your host"[..])
+        );
+        assert_eq!(warnings, [Warning::MissingTrailingColon { offset: 27 }]);
+        // other commands don't carry text
+        let (message, warnings) = parse_lossy(b"MODE #chan +o nick\r\n").unwrap();
+        assert_eq!(message.params.len(), 3);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn lossy_missing_command() {
+        assert_eq!(
+            parse_lossy(b":nick  \r\n"),
+            Err(ParseError::MissingCommand { offset: 7 })
+        );
+    }
+}
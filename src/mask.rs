@@ -0,0 +1,271 @@
+use crate::casemap::CaseMapping;
+use crate::channel::chanstring;
+use crate::prefix::Prefix;
+use crate::target::Target;
+use alloc::vec::Vec;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{map, verify};
+use nom::sequence::preceded;
+use nom::IResult;
+
+/// <mask>       ::= ( '#' | '$' ) <chanstring>
+/// A '#' mask must contain a wildcard, otherwise it's a channel.
+/// Return `Target::HostMask` or `Target::ServerMask` without the leading char
+/// Or an error if the input is not a mask: Err( (input, ErrorKind) )
+pub fn mask(input: &[u8]) -> IResult<&[u8], Target<'_>> {
+    alt((
+        map(preceded(tag("$"), chanstring), Target::ServerMask),
+        map(
+            preceded(tag("#"), verify(chanstring, has_wildcard)),
+            Target::HostMask,
+        ),
+    ))(input)
+}
+
+/// Return true if the mask contains an unescaped `*` or `?`
+pub fn has_wildcard(mask: &[u8]) -> bool {
+    tokens(mask)
+        .iter()
+        .any(|token| matches!(token, Token::Star | Token::Any))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    /// `*`: any sequence of chars, possibly empty
+    Star,
+    /// `?`: exactly one char
+    Any,
+    Literal(u8),
+}
+
+fn tokens(mask: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::with_capacity(mask.len());
+    let mut chars = mask.iter();
+    while let Some(&c) = chars.next() {
+        tokens.push(match c {
+            b'*' => Token::Star,
+            b'?' => Token::Any,
+            // a trailing backslash matches itself
+            b'\\' => Token::Literal(*chars.next().unwrap_or(&b'\\')),
+            c => Token::Literal(c),
+        });
+    }
+    tokens
+}
+
+/// Match `subject` against an IRC glob `mask`
+/// `*` matches any sequence of chars, `?` matches exactly one char and `\`
+/// escapes the next char so `\*` only matches a `*`. The comparison is case
+/// sensitive.
+pub fn mask_matches(mask: &[u8], subject: &[u8]) -> bool {
+    matches_by(mask, subject, |a, b| a == b)
+}
+
+/// Same as `mask_matches` but the case is ignored following `mapping`
+pub fn mask_matches_with(mask: &[u8], subject: &[u8], mapping: CaseMapping) -> bool {
+    matches_by(mask, subject, |a, b| mapping.lower(a) == mapping.lower(b))
+}
+
+fn matches_by(mask: &[u8], subject: &[u8], eq: impl Fn(u8, u8) -> bool) -> bool {
+    let tokens = tokens(mask);
+    let (mut t, mut s) = (0, 0);
+    // position of the last star and of the subject when we met it
+    let mut backtrack = None;
+    while s < subject.len() {
+        match tokens.get(t) {
+            Some(Token::Star) => {
+                t += 1;
+                backtrack = Some((t, s));
+                continue;
+            }
+            Some(Token::Any) => {
+                t += 1;
+                s += 1;
+                continue;
+            }
+            Some(&Token::Literal(c)) if eq(c, subject[s]) => {
+                t += 1;
+                s += 1;
+                continue;
+            }
+            _ => (),
+        }
+        // mismatch, let the last star eat one more char
+        match backtrack {
+            Some((star_t, star_s)) => {
+                t = star_t;
+                s = star_s + 1;
+                backtrack = Some((star_t, s));
+            }
+            None => return false,
+        }
+    }
+    tokens[t..].iter().all(|token| *token == Token::Star)
+}
+
+/// A `nick!user@host` mask as found in the ban, exception and invite lists
+/// ```
+/// use irc_parser::mask::HostMask;
+/// use irc_parser::prefix::Prefix;
+///
+/// let mask = HostMask::parse("*!*@*.Example.com");
+/// let prefix = Prefix::User {
+///     nick: b"nick",
+///     user: Some(b"user"),
+///     host: Some(b"irc.example.com"),
+/// };
+/// assert!(mask.matches(&prefix));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostMask<'a> {
+    pub nick: &'a [u8],
+    pub user: &'a [u8],
+    pub host: &'a [u8],
+}
+
+impl<'a> HostMask<'a> {
+    /// Split a mask into its parts, completed the way servers do
+    /// `nick` is `nick!*@*`, `user@host` is `*!user@host`, `nick!user` is
+    /// `nick!user@*` and a lone host, containing a '.' or a ':', is
+    /// `*!*@host`. The missing or empty parts are `*`.
+    pub fn parse<M: AsRef<[u8]> + ?Sized>(mask: &'a M) -> Self {
+        let mask = mask.as_ref();
+        let (nick, rest) = match mask.iter().position(|&c| c == b'!') {
+            Some(bang) => (Some(&mask[..bang]), Some(&mask[bang + 1..])),
+            None => (None, None),
+        };
+        let (nick, user, host) = match (nick, rest) {
+            (Some(nick), Some(rest)) => match rest.iter().rposition(|&c| c == b'@') {
+                Some(at) => (Some(nick), Some(&rest[..at]), Some(&rest[at + 1..])),
+                None => (Some(nick), Some(rest), None),
+            },
+            _ => match mask.iter().rposition(|&c| c == b'@') {
+                Some(at) => (None, Some(&mask[..at]), Some(&mask[at + 1..])),
+                None if mask.iter().any(|&c| c == b'.' || c == b':') => (None, None, Some(mask)),
+                None => (Some(mask), None, None),
+            },
+        };
+        let or_star = |part: Option<&'a [u8]>| match part {
+            Some(part) if !part.is_empty() => part,
+            _ => &b"*"[..],
+        };
+        HostMask {
+            nick: or_star(nick),
+            user: or_star(user),
+            host: or_star(host),
+        }
+    }
+
+    /// Match a prefix with the `rfc1459` case mapping, see `matches_with`
+    pub fn matches(&self, prefix: &Prefix) -> bool {
+        self.matches_with(prefix, CaseMapping::default())
+    }
+
+    /// Return true if the prefix is a user matching every part of the mask
+    /// The user and the host missing from the prefix are matched as empty.
+    pub fn matches_with(&self, prefix: &Prefix, mapping: CaseMapping) -> bool {
+        match prefix {
+            Prefix::Server(_) => false,
+            Prefix::User { nick, user, host } => {
+                mask_matches_with(self.nick, nick, mapping)
+                    && mask_matches_with(self.user, user.unwrap_or(b""), mapping)
+                    && mask_matches_with(self.host, host.unwrap_or(b""), mapping)
+            }
+        }
+    }
+
+    /// Serialize the mask as `nick!user@host`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.nick, b"!", self.user, b"@", self.host].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_empty() {
+        let empty: &[u8] = b"";
+        assert!(mask(empty).is_err());
+    }
+
+    #[test]
+    fn mask_kinds() {
+        let m: &[u8] = b"$*.fr rest";
+        assert_eq!(
+            mask(m),
+            Ok((&b" rest"[..], Target::ServerMask(&b"*.fr"[..])))
+        );
+        let m: &[u8] = b"#*.edu";
+        assert_eq!(mask(m), Ok((&b""[..], Target::HostMask(&b"*.edu"[..]))));
+        let m: &[u8] = b"#rust";
+        assert!(mask(m).is_err());
+        let m: &[u8] = b"#\\*.edu";
+        assert!(mask(m).is_err());
+    }
+
+    #[test]
+    fn matches_literal() {
+        assert!(mask_matches(b"", b""));
+        assert!(mask_matches(b"abc", b"abc"));
+        assert!(!mask_matches(b"abc", b"abcd"));
+        assert!(!mask_matches(b"abc", b"ABC"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(mask_matches(b"*", b""));
+        assert!(mask_matches(b"*", b"anything"));
+        assert!(mask_matches(b"*.example.com", b"irc.example.com"));
+        assert!(!mask_matches(b"*.example.com", b"example.com"));
+        assert!(mask_matches(b"n?ck!*@*", b"nick!user@host"));
+        assert!(!mask_matches(b"n?ck!*@*", b"nck!user@host"));
+        assert!(mask_matches(b"*a*b*c", b"xxaxxbxbxxc"));
+        assert!(!mask_matches(b"*a*b*c", b"xxaxxbxbxxcx"));
+    }
+
+    #[test]
+    fn matches_escapes() {
+        assert!(mask_matches(b"a\\*b", b"a*b"));
+        assert!(!mask_matches(b"a\\*b", b"axxb"));
+        assert!(mask_matches(b"a\\?", b"a?"));
+        assert!(!mask_matches(b"a\\?", b"ab"));
+        assert!(mask_matches(b"a\\\\", b"a\\"));
+        assert!(mask_matches(b"a\\", b"a\\"));
+    }
+
+    #[test]
+    fn host_mask_parse() {
+        let parts = |mask: &str| HostMask::parse(mask).to_bytes();
+        assert_eq!(parts("n!u@h"), b"n!u@h");
+        assert_eq!(parts("nick"), b"nick!*@*");
+        assert_eq!(parts("user@host"), b"*!user@host");
+        assert_eq!(parts("nick!user"), b"nick!user@*");
+        assert_eq!(parts("*.example.com"), b"*!*@*.example.com");
+        assert_eq!(parts("!@"), b"*!*@*");
+        assert_eq!(parts("n!u@x@h"), b"n!u@x@h");
+    }
+
+    #[test]
+    fn host_mask_matches() {
+        let user = Prefix::User {
+            nick: b"Nick[a]",
+            user: Some(b"~user"),
+            host: Some(b"irc.example.com"),
+        };
+        assert!(HostMask::parse("nick{A}").matches(&user));
+        assert!(!HostMask::parse("nick{A}").matches_with(&user, CaseMapping::Ascii));
+        assert!(HostMask::parse("*!~*@*.EXAMPLE.com").matches(&user));
+        assert!(!HostMask::parse("*!user@*").matches(&user));
+        assert!(!HostMask::parse("*").matches(&Prefix::Server(b"irc.example.com")));
+        let bare = Prefix::User {
+            nick: b"nick",
+            user: None,
+            host: None,
+        };
+        assert!(HostMask::parse("nick").matches(&bare));
+        assert!(mask_matches_with(b"A?C", b"abc", CaseMapping::Ascii));
+    }
+}
@@ -0,0 +1,824 @@
+use crate::command::{command_with, Command};
+use crate::config::ParserConfig;
+use crate::config::Rfc;
+use crate::error::{error_offset, EncodeError, ParseError};
+#[cfg(feature = "std")]
+use crate::output::Writer;
+use crate::output::{to_vec, Buffer, Output};
+use crate::params::{params_with, Params};
+use crate::parser::{crlf, crlf_or_lf, nick_max, nick_rfc2812_max, space};
+use crate::prefix::{prefix_with, Prefix};
+use crate::tags::{escaped_tags, tags, unescape_in_place, Tags};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use nom::bytes::complete::tag;
+use nom::combinator::opt;
+use nom::sequence::{preceded, terminated};
+use nom::IResult;
+
+/// A parsed IRC message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message<'a> {
+    pub tags: Tags<'a>,
+    pub prefix: Option<Prefix<'a>>,
+    pub command: Command<'a>,
+    pub params: Params<'a>,
+}
+
+impl<'a> Message<'a> {
+    /// Serialize the message into a line terminated by a crlf
+    /// The last parameter is sent as a trailing one, with a ':', if it was
+    /// pushed as a trailing or if it's empty, starts with a ':' or contains a
+    /// space. Parsing the line gives back the same message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_vec(|bytes| self.encode(bytes))
+    }
+
+    /// Same as `to_bytes` but write into `writer` without allocating
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.encode(&mut Writer(writer))
+    }
+
+    /// Serialize the message at the start of `buffer` and return the number
+    /// of bytes written
+    /// Return `EncodeError::BufferTooSmall` if it doesn't fit, the content of
+    /// the buffer is then unspecified.
+    pub fn encode_into(&self, buffer: &mut [u8]) -> Result<usize, EncodeError> {
+        let mut out = Buffer { buffer, len: 0 };
+        self.encode(&mut out)?;
+        Ok(out.len)
+    }
+
+    fn encode<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        if !self.tags.is_empty() {
+            out.put(b"@")?;
+            self.tags.encode(out)?;
+            out.put(b" ")?;
+        }
+        if let Some(prefix) = &self.prefix {
+            out.put(b":")?;
+            prefix.encode(out)?;
+            out.put(b" ")?;
+        }
+        self.command.encode(out)?;
+        let count = self.params.len();
+        for (i, param) in self.params.iter().enumerate() {
+            out.put(b" ")?;
+            if i + 1 == count && (self.params.has_trailing() || needs_trailing(param)) {
+                out.put(b":")?;
+            }
+            out.put(param)?;
+        }
+        out.put(b"\r\n")
+    }
+}
+
+/// Display the line without its crlf, invalid UTF-8 is replaced by U+FFFD
+impl<'a> fmt::Display for Message<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.to_bytes();
+        f.write_str(&String::from_utf8_lossy(&bytes[..bytes.len() - 2]))
+    }
+}
+
+/// Return true if the parameter can only be sent as a trailing parameter
+pub(crate) fn needs_trailing(param: &[u8]) -> bool {
+    param.is_empty() || param[0] == b':' || param.contains(&b' ')
+}
+
+/// <message>  ::= ['@' <tags> <SPACE>] [':' <prefix> <SPACE> ] <command> <params> <crlf>
+/// Parse a whole line and return the remaining bytes with the message
+/// Return an error if any part of the message is invalid or if the line is
+/// not terminated by a crlf.
+pub fn message(input: &[u8]) -> IResult<&[u8], Message<'_>> {
+    message_with(&ParserConfig::default())(input)
+}
+
+/// Same as `message` but follows the configuration
+/// The maximum line length is not checked here, see `parse_message_with`.
+pub fn message_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Message<'_>> {
+    message_tags_with(config, tags)
+}
+
+/// Same as `message_with` but parse the tags with `tags`
+fn message_tags_with(
+    config: &ParserConfig,
+    tags: fn(&[u8]) -> IResult<&[u8], Tags<'_>>,
+) -> impl Fn(&[u8]) -> IResult<&[u8], Message<'_>> {
+    let allow_bare_lf = config.allow_bare_lf;
+    let prefix = prefix_with(config);
+    let params = params_with(config);
+    let command = command_with(config);
+    move |input: &[u8]| {
+        let (input, tags) = opt(terminated(preceded(tag("@"), tags), space))(input)?;
+        let (input, prefix) = opt(terminated(preceded(tag(":"), &prefix), space))(input)?;
+        let (input, command) = command(input)?;
+        let (input, params) = params(input)?;
+        let (input, _) = if allow_bare_lf {
+            crlf_or_lf(input)?
+        } else {
+            crlf(input)?
+        };
+        Ok((
+            input,
+            Message {
+                tags: tags.unwrap_or_default(),
+                prefix,
+                command,
+                params,
+            },
+        ))
+    }
+}
+
+/// Parse exactly one message terminated by a crlf with the default
+/// configuration, see `parse_message_with`
+pub fn parse_message(input: &[u8]) -> Result<Message<'_>, ParseError> {
+    parse_message_with(input, &ParserConfig::default())
+}
+
+/// Parse exactly one message terminated by a crlf, or a lone LF if
+/// `allow_bare_lf` is set
+/// Return `ParseError::UnterminatedLine` if there is no line ending in the
+/// input, `ParseError::TagsTooLong` or `ParseError::LineTooLong` if the tags
+/// or the rest of the line are longer than allowed by the configuration and
+/// `ParseError::TrailingData` if something follows the crlf.
+pub fn parse_message_with<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Result<Message<'a>, ParseError> {
+    parse_line(input, config, tags)
+}
+
+/// Parse exactly one message with the default configuration, see
+/// `parse_message_mut_with`
+pub fn parse_message_mut(input: &mut [u8]) -> Result<Message<'_>, ParseError> {
+    parse_message_mut_with(input, &ParserConfig::default())
+}
+
+/// Same as `parse_message_with` but the escaped tag values are unescaped in
+/// `input` itself so the tags never allocate
+/// The tags section of `input` is garbage once the message is dropped, the
+/// input is only modified if the message is valid.
+pub fn parse_message_mut_with<'a>(
+    input: &'a mut [u8],
+    config: &ParserConfig,
+) -> Result<Message<'a>, ParseError> {
+    // check everything, the tags included, before modifying anything
+    parse_line(input, config, escaped_tags)?;
+    let tags_len = config.tags_len(input);
+    let (tags_part, rest) = input.split_at_mut(tags_len);
+    // the tags can be followed by more than one space
+    let spaces = rest.iter().take_while(|&&c| c == b' ').count();
+    let mut message = parse_line(&rest[spaces..], config, tags)?;
+    if tags_len > 0 {
+        // without the '@' and the space
+        let end = tags_part.len() - 1;
+        let mut remaining = &mut tags_part[1..end];
+        while !remaining.is_empty() {
+            let len = remaining
+                .iter()
+                .position(|&c| c == b';')
+                .unwrap_or(remaining.len());
+            let (tag, rest) = core::mem::take(&mut remaining).split_at_mut(len);
+            remaining = rest.get_mut(1..).unwrap_or_default();
+            let eq = tag.iter().position(|&c| c == b'=').unwrap_or(tag.len());
+            let (key, value) = tag.split_at_mut(eq);
+            let value = value.get_mut(1..).unwrap_or_default();
+            let len = unescape_in_place(value);
+            let value: &'a [u8] = value;
+            message.tags.push(key, &value[..len]);
+        }
+    }
+    Ok(message)
+}
+
+/// Same as `parse_message_with` but parse the tags with `tags`
+pub(crate) fn parse_line<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+    tags: fn(&[u8]) -> IResult<&[u8], Tags<'_>>,
+) -> Result<Message<'a>, ParseError> {
+    let len = match config.line_len(input) {
+        Some(len) => len,
+        None => {
+            return Err(ParseError::UnterminatedLine {
+                offset: input.len(),
+            })
+        }
+    };
+    let tags_len = config.tags_len(&input[..len]);
+    if tags_len > config.max_tags_len {
+        return Err(ParseError::TagsTooLong {
+            len: tags_len,
+            max: config.max_tags_len,
+        });
+    }
+    if len - tags_len > config.max_line_len {
+        return Err(ParseError::LineTooLong {
+            offset: tags_len,
+            len: len - tags_len,
+            max: config.max_line_len,
+        });
+    }
+    match message_tags_with(config, tags)(input) {
+        Ok((rest, _)) if !rest.is_empty() => Err(ParseError::TrailingData {
+            offset: input.len() - rest.len(),
+        }),
+        Ok((_, message)) if config.require_utf8 => check_utf8(input, message),
+        Ok((_, message)) => Ok(message),
+        Err(_) => Err(diagnose(input, config, tags)),
+    }
+}
+
+/// Return `ParseError::InvalidUtf8` if a parameter is not valid UTF-8
+fn check_utf8<'a>(input: &[u8], message: Message<'a>) -> Result<Message<'a>, ParseError> {
+    for param in message.params.iter() {
+        if let Err(e) = core::str::from_utf8(param) {
+            return Err(ParseError::InvalidUtf8 {
+                offset: param.as_ptr() as usize - input.as_ptr() as usize + e.valid_up_to(),
+            });
+        }
+    }
+    Ok(message)
+}
+
+/// Find out why `input` is not a valid message by parsing it part by part
+fn diagnose(
+    input: &[u8],
+    config: &ParserConfig,
+    tags: fn(&[u8]) -> IResult<&[u8], Tags<'_>>,
+) -> ParseError {
+    let offset = |rest: &[u8]| input.len() - rest.len();
+    let mut rest = input;
+    if let Some(after) = rest.strip_prefix(b"@") {
+        match terminated(tags, space)(after) {
+            Ok((after, _)) => rest = after,
+            Err(e) => {
+                return ParseError::InvalidTags {
+                    offset: offset(after) + error_offset(after, e),
+                }
+            }
+        }
+    }
+    if let Some(after) = rest.strip_prefix(b":") {
+        match terminated(prefix_with(config), space)(after) {
+            Ok((after, _)) => rest = after,
+            Err(e) => {
+                let token = &after[..after.iter().position(|&c| c == b' ').unwrap_or(after.len())];
+                // a '!' or a '@' means it was meant as a user prefix
+                if let Some(end) = token.iter().position(|&c| c == b'!' || c == b'@') {
+                    let nick: IResult<&[u8], &[u8]> = match config.rfc {
+                        Rfc::Rfc1459 => nick_max(usize::MAX)(&token[..end]),
+                        Rfc::Rfc2812 => nick_rfc2812_max(usize::MAX)(&token[..end]),
+                    };
+                    let len = nick.map(|(_, nick)| nick.len()).unwrap_or(0);
+                    if len < end {
+                        return ParseError::InvalidNickChar {
+                            offset: offset(after) + len,
+                        };
+                    }
+                    if len > config.max_nick_len {
+                        return ParseError::NickTooLong {
+                            offset: offset(after),
+                            len,
+                            max: config.max_nick_len,
+                        };
+                    }
+                }
+                return ParseError::InvalidPrefix {
+                    offset: offset(after) + error_offset(after, e),
+                };
+            }
+        }
+    }
+    let (after, params) = match command_with(config)(rest) {
+        Ok((after, _)) => match params_with(config)(after) {
+            Ok(params) => params,
+            Err(e) => {
+                return ParseError::InvalidCommand {
+                    offset: offset(after) + error_offset(after, e),
+                }
+            }
+        },
+        Err(_) if matches!(rest.first(), None | Some(b' ') | Some(b'\r') | Some(b'\n')) => {
+            return ParseError::MissingCommand {
+                offset: offset(rest),
+            }
+        }
+        Err(e) => {
+            return ParseError::InvalidCommand {
+                offset: offset(rest) + error_offset(rest, e),
+            }
+        }
+    };
+    // the params stop on a forbidden byte or on something stuck to the command
+    let pos = after.iter().position(|&c| c != b' ').unwrap_or(after.len());
+    match after.get(pos) {
+        Some(&byte) if pos > 0 || !params.is_empty() || matches!(byte, 0x00 | b'\r' | b'\n') => {
+            ParseError::IllegalByteInParam {
+                offset: offset(&after[pos..]),
+                byte,
+            }
+        }
+        _ => ParseError::InvalidCommand {
+            offset: offset(&after[pos..]),
+        },
+    }
+}
+
+/// Parse every crlf terminated line of the input with the default
+/// configuration, see `parse_all_with`
+pub fn parse_all(input: &[u8]) -> Messages<'_> {
+    parse_all_with(input, &ParserConfig::default())
+}
+
+/// Parse every line of the input
+/// Malformed lines are yielded as errors, with offsets relative to the start
+/// of the line, and the parsing continue with the next line. If the input
+/// does not end with a line ending the last item is `ParseError::UnterminatedLine`.
+pub fn parse_all_with<'a>(input: &'a [u8], config: &ParserConfig) -> Messages<'a> {
+    Messages {
+        input,
+        config: config.clone(),
+    }
+}
+
+/// Iterator over the messages of a buffer, created by `parse_all`
+#[derive(Debug, Clone)]
+pub struct Messages<'a> {
+    input: &'a [u8],
+    config: ParserConfig,
+}
+
+impl<'a> Iterator for Messages<'a> {
+    type Item = Result<Message<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+        let end = self.config.line_len(self.input).unwrap_or(self.input.len());
+        let (line, rest) = self.input.split_at(end);
+        self.input = rest;
+        Some(parse_message_with(line, &self.config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::borrow::Cow;
+    use nom::error::ErrorKind;
+    use nom::Err;
+
+    #[test]
+    fn message_empty() {
+        let empty: &[u8] = b"";
+        assert!(message(empty).is_err());
+    }
+
+    #[test]
+    fn message_command_only() {
+        let m: &[u8] = b"QUIT\r\n";
+        assert_eq!(
+            message(m),
+            Ok((
+                &b""[..],
+                Message {
+                    tags: Tags::new(),
+                    prefix: None,
+                    command: Command::Quit,
+                    params: Params::new(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn message_full() {
+        let m: &[u8] = b":irc.example.com 001 nick :Welcome to IRC\r\nPING";
+        assert_eq!(
+            message(m),
+            Ok((
+                &b"PING"[..],
+                Message {
+                    tags: Tags::new(),
+                    prefix: Some(Prefix::Server(&b"irc.example.com"[..])),
+                    command: Command::Numeric(1),
+                    params: {
+                        let mut params = Params::from(vec![&b"nick"[..]]);
+                        params.push_trailing(&b"Welcome to IRC"[..]);
+                        params
+                    },
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn message_middles() {
+        let m: &[u8] = b"MODE #chan +o  nick \r\n";
+        let (_, m) = message(m).unwrap();
+        assert_eq!(&m.params[..], &[&b"#chan"[..], &b"+o"[..], &b"nick"[..]]);
+    }
+
+    #[test]
+    fn message_empty_trailing() {
+        let m: &[u8] = b"PRIVMSG #chan :\r\n";
+        let (_, m) = message(m).unwrap();
+        assert_eq!(&m.params[..], &[&b"#chan"[..], &b""[..]]);
+        assert_eq!(m.params.trailing(), Some(&b""[..]));
+    }
+
+    #[test]
+    fn message_without_crlf() {
+        let m: &[u8] = b"PING server";
+        assert_eq!(message(m), Err(Err::Error((&b""[..], ErrorKind::CrLf))));
+    }
+
+    #[test]
+    fn message_user_prefix() {
+        let m: &[u8] = b":nick!user@host JOIN #chan\r\n";
+        let (_, m) = message(m).unwrap();
+        assert_eq!(
+            m.prefix,
+            Some(Prefix::User {
+                nick: &b"nick"[..],
+                user: Some(&b"user"[..]),
+                host: Some(&b"host"[..]),
+            })
+        );
+    }
+
+    #[test]
+    fn message_tags() {
+        let m: &[u8] = b"@time=2019-02-28T19:30:01.727Z;msgid=a\\sb :nick PRIVMSG #chan :hi\r\n";
+        let (_, m) = message(m).unwrap();
+        assert_eq!(m.tags.len(), 2);
+        assert_eq!(m.tags.get("time"), Some(&b"2019-02-28T19:30:01.727Z"[..]));
+        assert_eq!(m.tags.get("msgid"), Some(&b"a b"[..]));
+        assert!(matches!(m.prefix, Some(Prefix::User { .. })));
+        let m: &[u8] = b"@ PING\r\n";
+        assert!(message(m).is_err());
+    }
+
+    #[test]
+    fn message_bad_command() {
+        let m: &[u8] = b":nick !PING\r\n";
+        assert!(message(m).is_err());
+    }
+
+    #[test]
+    fn to_bytes_roundtrip() {
+        let lines: &[&[u8]] = &[
+            b"PING\r\n",
+            b"PRIVMSG #chan :hello world\r\n",
+            b"PRIVMSG #chan hello\r\n",
+            b"PRIVMSG #chan :hello\r\n",
+            b"PRIVMSG #chan :\r\n",
+            b":irc.example.com 001 nick :Welcome\r\n",
+            b":nick!user@host JOIN #chan\r\n",
+            b"@time=2019-02-28T19:30:01.727Z;+draft/x=a\\sb;flag :nick@host NOTICE #a ::)\r\n",
+        ];
+        for line in lines {
+            let message = parse_message(line).unwrap();
+            assert_eq!(message.to_bytes(), line.to_vec());
+            assert_eq!(parse_message(&message.to_bytes()), Ok(message));
+        }
+    }
+
+    #[test]
+    fn to_bytes_forces_trailing() {
+        for (param, line) in &[
+            (&b"hello world"[..], &b"PRIVMSG #a :hello world\r\n"[..]),
+            (b":)", b"PRIVMSG #a ::)\r\n"),
+            (b"", b"PRIVMSG #a :\r\n"),
+        ] {
+            let message = Message {
+                tags: Tags::new(),
+                prefix: None,
+                command: Command::Privmsg,
+                params: Params::from(vec![&b"#a"[..], param]),
+            };
+            assert_eq!(message.to_bytes(), line.to_vec());
+        }
+    }
+
+    #[test]
+    fn encode_into_buffer() {
+        let line: &[u8] = b"@a=b\\sc :nick PRIVMSG #chan :hello world\r\n";
+        let message = parse_message(line).unwrap();
+        let mut buffer = [0; 512];
+        let len = message.encode_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[..len], line);
+        let mut small = [0; 16];
+        assert_eq!(
+            message.encode_into(&mut small),
+            Err(EncodeError::BufferTooSmall)
+        );
+        let mut exact = vec![0; line.len()];
+        assert_eq!(message.encode_into(&mut exact), Ok(line.len()));
+    }
+
+    #[test]
+    fn message_display() {
+        let message = parse_message(b"@a=b :nick!u@h PRIVMSG #chan :hello world\r\n").unwrap();
+        assert_eq!(
+            message.to_string(),
+            "@a=b :nick!u@h PRIVMSG #chan :hello world"
+        );
+        let message = parse_message(b"PRIVMSG #chan :\xff\r\n").unwrap();
+        assert_eq!(message.to_string(), "PRIVMSG #chan :\u{fffd}");
+    }
+
+    #[test]
+    fn parse_message_ok() {
+        let m: &[u8] = b"PING :irc.example.com\r\n";
+        let (_, expected) = message(m).unwrap();
+        assert_eq!(parse_message(m), Ok(expected));
+    }
+
+    #[test]
+    fn parse_message_incomplete() {
+        let m: &[u8] = b"PRIVMSG #chan :hel";
+        assert_eq!(
+            parse_message(m),
+            Err(ParseError::UnterminatedLine { offset: 18 })
+        );
+        let m: &[u8] = b"PRIVMSG #chan :hello\r";
+        assert_eq!(
+            parse_message(m),
+            Err(ParseError::UnterminatedLine { offset: 21 })
+        );
+    }
+
+    #[test]
+    fn parse_message_in_place() {
+        let line = b"@a=b\\sc;k;e=x\\:y\\\\  :nick PRIVMSG #chan :hi\r\n";
+        let mut input = line.to_vec();
+        let message = parse_message_mut(&mut input).unwrap();
+        assert_eq!(message, parse_message(line).unwrap());
+        assert!(message
+            .tags
+            .iter()
+            .all(|tag| matches!(tag.value, Cow::Borrowed(_))));
+        assert_eq!(message.tags.get("e"), Some(&b"x;y\\"[..]));
+
+        let mut input = b"PING :a\r\n".to_vec();
+        assert_eq!(parse_message_mut(&mut input).unwrap().params[0], b"a");
+        let mut input = b"@a=b\\s :nick 12 :x\r\n".to_vec();
+        assert_eq!(
+            parse_message_mut(&mut input),
+            Err(ParseError::InvalidCommand { offset: 15 })
+        );
+        assert_eq!(input, b"@a=b\\s :nick 12 :x\r\n".to_vec());
+    }
+
+    #[test]
+    fn parse_message_invalid() {
+        let m: &[u8] = b":nick 12 :hello\r\n";
+        assert_eq!(
+            parse_message(m),
+            Err(ParseError::InvalidCommand { offset: 8 })
+        );
+        let errors: &[(&[u8], ParseError)] = &[
+            (b"@a=b;;c PING\r\n", ParseError::InvalidTags { offset: 4 }),
+            (b": PING\r\n", ParseError::InvalidPrefix { offset: 1 }),
+            (
+                b":ni*ck!u@h PING\r\n",
+                ParseError::InvalidNickChar { offset: 3 },
+            ),
+            (b":nick  \r\n", ParseError::MissingCommand { offset: 7 }),
+            (b"\r\n", ParseError::MissingCommand { offset: 0 }),
+            (b"PING#chan\r\n", ParseError::InvalidCommand { offset: 4 }),
+            (
+                b"PRIVMSG #a b\0c\r\n",
+                ParseError::IllegalByteInParam {
+                    offset: 12,
+                    byte: 0,
+                },
+            ),
+            (
+                b"PING a \rb\r\n",
+                ParseError::IllegalByteInParam {
+                    offset: 7,
+                    byte: b'\r',
+                },
+            ),
+        ];
+        for (line, error) in errors {
+            assert_eq!(parse_message(line), Err(*error));
+        }
+    }
+
+    #[test]
+    fn parse_error_display() {
+        assert_eq!(
+            ParseError::IllegalByteInParam {
+                offset: 12,
+                byte: 0
+            }
+            .to_string(),
+            "illegal byte 0x00 in the parameters at byte 12"
+        );
+        assert_eq!(
+            ParseError::LineTooLong {
+                offset: 0,
+                len: 513,
+                max: 512
+            }
+            .to_string(),
+            "the line is 513 bytes long while at most 512 are allowed"
+        );
+    }
+
+    #[test]
+    fn parse_error_span() {
+        let m: &[u8] = b":nick 12 :hello\r\n";
+        let error = parse_message(m).unwrap_err();
+        assert_eq!(error, ParseError::InvalidCommand { offset: 8 });
+        assert_eq!(error.span(), 8..9);
+        assert_eq!(
+            error.caret(m),
+            ":nick 12 :hello\n        ^ expected a command or a 3-digit numeric"
+        );
+        let m: &[u8] = b"PING";
+        let error = parse_message(m).unwrap_err();
+        assert_eq!(error.span(), 4..4);
+        assert_eq!(error.caret(m), "PING\n    ^ expected a crlf");
+        let mut m = b"PRIVMSG #chan :".to_vec();
+        m.resize(515, b'a');
+        m.extend_from_slice(b"\r\n");
+        assert_eq!(parse_message(&m).unwrap_err().span(), 512..517);
+    }
+
+    #[test]
+    fn parse_message_trailing_data() {
+        let m: &[u8] = b"PING a\r\nPING b\r\n";
+        assert_eq!(
+            parse_message(m),
+            Err(ParseError::TrailingData { offset: 8 })
+        );
+    }
+
+    #[test]
+    fn parse_message_too_long() {
+        let mut m = b"PRIVMSG #chan :".to_vec();
+        m.resize(510, b'a');
+        m.extend_from_slice(b"\r\n");
+        assert!(parse_message(&m).is_ok());
+        m.insert(20, b'a');
+        assert_eq!(
+            parse_message(&m),
+            Err(ParseError::LineTooLong {
+                offset: 0,
+                len: 513,
+                max: 512
+            })
+        );
+        let config = ParserConfig {
+            max_line_len: 1024,
+            ..ParserConfig::default()
+        };
+        assert!(parse_message_with(&m, &config).is_ok());
+    }
+
+    #[test]
+    fn parse_message_tags_too_long() {
+        let mut m = b"@a=".to_vec();
+        m.resize(8190, b'a');
+        m.extend_from_slice(b" PRIVMSG #chan :");
+        m.resize(8191 + 510, b'a');
+        m.extend_from_slice(b"\r\n");
+        assert!(parse_message(&m).is_ok());
+        m.insert(5, b'a');
+        assert_eq!(
+            parse_message(&m),
+            Err(ParseError::TagsTooLong {
+                len: 8192,
+                max: 8191
+            })
+        );
+        m.remove(5);
+        m.insert(8195, b'a');
+        assert_eq!(
+            parse_message(&m),
+            Err(ParseError::LineTooLong {
+                offset: 8191,
+                len: 513,
+                max: 512
+            })
+        );
+    }
+
+    #[test]
+    fn parse_message_bare_lf() {
+        let m: &[u8] = b"PING :irc.example.com\n";
+        assert_eq!(
+            parse_message(m),
+            Err(ParseError::UnterminatedLine { offset: 22 })
+        );
+        let config = ParserConfig {
+            allow_bare_lf: true,
+            ..ParserConfig::default()
+        };
+        let message = parse_message_with(m, &config).unwrap();
+        assert_eq!(message.params.trailing(), Some(&b"irc.example.com"[..]));
+        let m: &[u8] = b"PING :irc.example.com\r\n";
+        assert!(parse_message_with(m, &config).is_ok());
+    }
+
+    #[test]
+    fn parse_message_utf8() {
+        let m: &[u8] = b"PRIVMSG #chan :caf\xc3\xa9 \xff\r\n";
+        assert!(parse_message(m).is_ok());
+        let config = ParserConfig::default().require_utf8(true);
+        assert_eq!(
+            parse_message_with(m, &config),
+            Err(ParseError::InvalidUtf8 { offset: 21 })
+        );
+        let m: &[u8] = b"PRIVMSG #chan :caf\xc3\xa9\r\n";
+        assert!(parse_message_with(m, &config).is_ok());
+    }
+
+    #[test]
+    fn parse_message_presets() {
+        let m: &[u8] = b":averylongnick!user@host PRIVMSG #chan :hi\r\n";
+        assert!(parse_message_with(m, &ParserConfig::modern()).is_ok());
+        assert_eq!(
+            parse_message_with(m, &ParserConfig::strict_rfc1459()),
+            Err(ParseError::NickTooLong {
+                offset: 1,
+                len: 13,
+                max: 9
+            })
+        );
+        let m: &[u8] = b"@a=b PING :a\r\n";
+        assert_eq!(
+            parse_message_with(m, &ParserConfig::rfc2812()),
+            Err(ParseError::TagsTooLong { len: 5, max: 0 })
+        );
+        let m: &[u8] = b":nick_|away!user@host PING :a\n";
+        assert!(parse_message_with(m, &ParserConfig::modern()).is_ok());
+        let config = ParserConfig::rfc2812().allow_bare_lf(true);
+        assert!(parse_message_with(m, &config).is_err());
+        let config = config.max_nick_len(10);
+        assert!(parse_message_with(m, &config).is_ok());
+    }
+
+    #[test]
+    fn parse_all_bare_lf() {
+        let buf: &[u8] = b"PING a\nPING b\r\nPING c\n";
+        let config = ParserConfig {
+            allow_bare_lf: true,
+            ..ParserConfig::default()
+        };
+        let messages: Vec<_> = parse_all_with(buf, &config).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(Result::is_ok));
+        let messages: Vec<_> = parse_all(buf).collect();
+        assert_eq!(
+            messages[0],
+            Err(ParseError::IllegalByteInParam {
+                offset: 6,
+                byte: b'\n'
+            })
+        );
+        assert_eq!(messages[1], Err(ParseError::UnterminatedLine { offset: 7 }));
+    }
+
+    #[test]
+    fn parse_all_empty() {
+        assert_eq!(parse_all(b"").next(), None);
+    }
+
+    #[test]
+    fn parse_all_lines() {
+        let buf: &[u8] = b"PING a\r\n:nick JOIN #chan\r\nPONG b\r\n";
+        let commands: Vec<_> = parse_all(buf).map(|m| m.map(|m| m.command)).collect();
+        assert_eq!(
+            commands,
+            vec![Ok(Command::Ping), Ok(Command::Join), Ok(Command::Pong),]
+        );
+    }
+
+    #[test]
+    fn parse_all_continue_after_error() {
+        let buf: &[u8] = b"PING a\r\n12 bad\r\nPONG b\r\nPRIV";
+        let mut messages = parse_all(buf);
+        assert!(messages.next().unwrap().is_ok());
+        assert!(matches!(
+            messages.next(),
+            Some(Err(ParseError::InvalidCommand { offset: 2 }))
+        ));
+        assert!(messages.next().unwrap().is_ok());
+        assert_eq!(
+            messages.next(),
+            Some(Err(ParseError::UnterminatedLine { offset: 4 }))
+        );
+        assert_eq!(messages.next(), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! The key-value metadata of users and channels from the IRCv3 metadata
+//! draft: `METADATA * SET avatar :https://example.com/me.png`
+//! The target `*` is the client itself.
+
+use crate::batch::{Batch, BatchItem};
+use crate::command::Command;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::vec::Vec;
+
+/// A METADATA command sent by a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataCommand<'a> {
+    /// `METADATA <target> GET <key> [<key>…]`
+    Get {
+        target: &'a [u8],
+        keys: Vec<&'a [u8]>,
+    },
+    /// `METADATA <target> LIST`
+    List { target: &'a [u8] },
+    /// `METADATA <target> SET <key> [:<value>]`, without value the key is
+    /// removed
+    Set {
+        target: &'a [u8],
+        key: &'a [u8],
+        value: Option<&'a [u8]>,
+    },
+    /// `METADATA <target> CLEAR`
+    Clear { target: &'a [u8] },
+    /// `METADATA * SUB <key> [<key>…]`
+    Sub { keys: Vec<&'a [u8]> },
+    /// `METADATA * UNSUB <key> [<key>…]`
+    Unsub { keys: Vec<&'a [u8]> },
+    /// `METADATA * SUBS`
+    Subs,
+    /// `METADATA <target> SYNC`
+    Sync { target: &'a [u8] },
+}
+
+impl<'a> MetadataCommand<'a> {
+    /// Return `None` if it's not a METADATA or if it's malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        if message.command != Command::Metadata {
+            return None;
+        }
+        let (target, subcommand, params) = match &message.params[..] {
+            [target, subcommand, params @ ..] => (*target, subcommand.to_ascii_uppercase(), params),
+            _ => return None,
+        };
+        let keys = || Some(params.to_vec()).filter(|keys| !keys.is_empty());
+        Some(match (&subcommand[..], params) {
+            (b"GET", _) => MetadataCommand::Get {
+                target,
+                keys: keys()?,
+            },
+            (b"LIST", []) => MetadataCommand::List { target },
+            (b"SET", [key]) => MetadataCommand::Set {
+                target,
+                key,
+                value: None,
+            },
+            (b"SET", [key, value]) => MetadataCommand::Set {
+                target,
+                key,
+                value: Some(value),
+            },
+            (b"CLEAR", []) => MetadataCommand::Clear { target },
+            (b"SUB", _) => MetadataCommand::Sub { keys: keys()? },
+            (b"UNSUB", _) => MetadataCommand::Unsub { keys: keys()? },
+            (b"SUBS", []) => MetadataCommand::Subs,
+            (b"SYNC", []) => MetadataCommand::Sync { target },
+            _ => return None,
+        })
+    }
+
+    /// Return the line to send, crlf included
+    /// ```
+    /// use irc_parser::metadata::MetadataCommand;
+    ///
+    /// let set = MetadataCommand::Set { target: b"*", key: b"avatar", value: Some(b"a b") };
+    /// assert_eq!(set.to_bytes(), b"METADATA * SET avatar :a b\r\n");
+    /// let sub = MetadataCommand::Sub { keys: vec![b"avatar", b"url"] };
+    /// assert_eq!(sub.to_bytes(), b"METADATA * SUB avatar url\r\n");
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (target, subcommand, params, value): (&[u8], &[u8], &[&[u8]], _) = match self {
+            MetadataCommand::Get { target, keys } => (target, b"GET", keys, None),
+            MetadataCommand::List { target } => (target, b"LIST", &[], None),
+            MetadataCommand::Set { target, key, value } => {
+                (target, b"SET", core::slice::from_ref(key), *value)
+            }
+            MetadataCommand::Clear { target } => (target, b"CLEAR", &[], None),
+            MetadataCommand::Sub { keys } => (b"*", b"SUB", keys, None),
+            MetadataCommand::Unsub { keys } => (b"*", b"UNSUB", keys, None),
+            MetadataCommand::Subs => (b"*", b"SUBS", &[], None),
+            MetadataCommand::Sync { target } => (target, b"SYNC", &[], None),
+        };
+        let mut line = b"METADATA ".to_vec();
+        line.extend_from_slice(target);
+        line.push(b' ');
+        line.extend_from_slice(subcommand);
+        for param in params {
+            line.push(b' ');
+            line.extend_from_slice(param);
+        }
+        if let Some(value) = value {
+            line.extend_from_slice(b" :");
+            line.extend_from_slice(value);
+        }
+        line.extend_from_slice(b"\r\n");
+        line
+    }
+}
+
+/// The value of a key of a target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetadataUpdate<'a> {
+    /// The nick or channel, `*` for the client itself
+    pub target: &'a [u8],
+    pub key: &'a [u8],
+    /// `*` when everyone can see the key
+    pub visibility: &'a [u8],
+    /// `None` when the key was removed
+    pub value: Option<&'a [u8]>,
+}
+
+impl<'a> MetadataUpdate<'a> {
+    /// Whether the key belongs to the client itself
+    pub fn is_self(&self) -> bool {
+        self.target == b"*"
+    }
+
+    fn from_params(params: &[&'a [u8]]) -> Option<Self> {
+        let (target, key, visibility, value) = match *params {
+            [target, key, visibility] => (target, key, visibility, None),
+            [target, key, visibility, value] => (target, key, visibility, Some(value)),
+            _ => return None,
+        };
+        Some(MetadataUpdate {
+            target,
+            key,
+            visibility,
+            value,
+        })
+    }
+}
+
+/// A METADATA message or reply sent by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataEvent<'a> {
+    /// `METADATA <target> <key> <visibility> [:<value>]`: a subscribed key
+    /// changed, RPL_KEYVALUE (761) and RPL_WHOISKEYVALUE (760): the value of
+    /// a key
+    Update(MetadataUpdate<'a>),
+    /// RPL_KEYNOTSET (766): the key has no value
+    NotSet { target: &'a [u8], key: &'a [u8] },
+    /// RPL_METADATASUBOK (770): the keys are now subscribed
+    Subscribed(Vec<&'a [u8]>),
+    /// RPL_METADATAUNSUBOK (771): the keys are not subscribed anymore
+    Unsubscribed(Vec<&'a [u8]>),
+    /// RPL_METADATASUBS (772): the subscribed keys
+    Subscriptions(Vec<&'a [u8]>),
+    /// RPL_METADATASYNCLATER (774): send a SYNC for the target later, after
+    /// the number of seconds if the server gave one
+    SyncLater {
+        target: &'a [u8],
+        retry_after: Option<u64>,
+    },
+}
+
+impl<'a> MetadataEvent<'a> {
+    /// Return `None` if the message is not a METADATA from a server or one of
+    /// the metadata numerics, or if it's malformed
+    /// ```
+    /// use irc_parser::message::parse_message;
+    /// use irc_parser::metadata::MetadataEvent;
+    ///
+    /// let m = parse_message(b":nick!u@h METADATA nick avatar * :https://x.y/a.png\r\n").unwrap();
+    /// match MetadataEvent::from_message(&m) {
+    ///     Some(MetadataEvent::Update(update)) => {
+    ///         assert_eq!(update.key, b"avatar");
+    ///         assert_eq!(update.value, Some(&b"https://x.y/a.png"[..]));
+    ///     }
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        let params = &message.params[..];
+        if message.command == Command::Metadata {
+            return MetadataUpdate::from_params(params).map(MetadataEvent::Update);
+        }
+        // the nick of the client comes first
+        let params = params.get(1..)?;
+        Some(match (message.command.numeric()?, params) {
+            (Numeric::RplWhoisKeyValue, _) | (Numeric::RplKeyValue, _) => {
+                MetadataEvent::Update(MetadataUpdate::from_params(params)?)
+            }
+            (Numeric::RplKeyNotSet, [target, key, ..]) => MetadataEvent::NotSet { target, key },
+            (Numeric::RplMetadataSubOk, keys) => MetadataEvent::Subscribed(keys_list(keys)),
+            (Numeric::RplMetadataUnsubOk, keys) => MetadataEvent::Unsubscribed(keys_list(keys)),
+            (Numeric::RplMetadataSubs, keys) => MetadataEvent::Subscriptions(keys_list(keys)),
+            (Numeric::RplMetadataSyncLater, [target]) => MetadataEvent::SyncLater {
+                target,
+                retry_after: None,
+            },
+            (Numeric::RplMetadataSyncLater, [target, retry_after]) => MetadataEvent::SyncLater {
+                target,
+                retry_after: Some(core::str::from_utf8(retry_after).ok()?.parse().ok()?),
+            },
+            _ => return None,
+        })
+    }
+
+    /// Return the events of a `metadata` batch, sent after a JOIN or a SYNC,
+    /// nothing if the batch has another type
+    pub fn from_batch(batch: &Batch<'a>) -> Vec<Self> {
+        if batch.kind != b"metadata" {
+            return Vec::new();
+        }
+        batch
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                BatchItem::Message(message) => Self::from_message(message),
+                BatchItem::Batch(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// The keys can be sent as separate parameters or in the trailing one
+fn keys_list<'a>(params: &[&'a [u8]]) -> Vec<&'a [u8]> {
+    params
+        .iter()
+        .flat_map(|param| param.split(|&c| c == b' '))
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::{BatchCollector, Event};
+    use crate::message::{parse_all, parse_message};
+
+    #[test]
+    fn metadata_command() {
+        let m = parse_message(b"METADATA * set url :https://example.com\r\n").unwrap();
+        let command = MetadataCommand::from_message(&m).unwrap();
+        assert_eq!(
+            command,
+            MetadataCommand::Set {
+                target: b"*",
+                key: b"url",
+                value: Some(b"https://example.com")
+            }
+        );
+        assert_eq!(
+            command.to_bytes(),
+            b"METADATA * SET url :https://example.com\r\n"
+        );
+        let m = parse_message(b"METADATA #chan GET a b\r\n").unwrap();
+        assert_eq!(
+            MetadataCommand::from_message(&m),
+            Some(MetadataCommand::Get {
+                target: b"#chan",
+                keys: vec![b"a", b"b"]
+            })
+        );
+        let m = parse_message(b"METADATA * SUB\r\n").unwrap();
+        assert_eq!(MetadataCommand::from_message(&m), None);
+        assert_eq!(
+            MetadataCommand::Clear { target: b"*" }.to_bytes(),
+            b"METADATA * CLEAR\r\n"
+        );
+    }
+
+    #[test]
+    fn metadata_events() {
+        let m = parse_message(b":irc 761 me * url * :https://example.com\r\n").unwrap();
+        let update = match MetadataEvent::from_message(&m) {
+            Some(MetadataEvent::Update(update)) => update,
+            event => panic!("{:?}", event),
+        };
+        assert!(update.is_self());
+        let m = parse_message(b":irc 766 me nick url :key not set\r\n").unwrap();
+        assert_eq!(
+            MetadataEvent::from_message(&m),
+            Some(MetadataEvent::NotSet {
+                target: b"nick",
+                key: b"url"
+            })
+        );
+        let m = parse_message(b":irc 770 me :avatar url\r\n").unwrap();
+        assert_eq!(
+            MetadataEvent::from_message(&m),
+            Some(MetadataEvent::Subscribed(vec![b"avatar", b"url"]))
+        );
+        let m = parse_message(b":irc 774 me #chan 4\r\n").unwrap();
+        assert_eq!(
+            MetadataEvent::from_message(&m),
+            Some(MetadataEvent::SyncLater {
+                target: b"#chan",
+                retry_after: Some(4)
+            })
+        );
+        // a removed key
+        let m = parse_message(b":nick!u@h METADATA nick url *\r\n").unwrap();
+        match MetadataEvent::from_message(&m) {
+            Some(MetadataEvent::Update(update)) => assert_eq!(update.value, None),
+            event => panic!("{:?}", event),
+        }
+    }
+
+    #[test]
+    fn metadata_batch() {
+        let input = b":irc BATCH +1 metadata\r\n\
+            @batch=1 :irc METADATA #chan topic-url * :https://a\r\n\
+            @batch=1 :irc METADATA nick avatar * :https://b\r\n\
+            :irc BATCH -1\r\n";
+        let mut collector = BatchCollector::new();
+        let batch = parse_all(input)
+            .filter_map(|m| collector.feed(m.unwrap()))
+            .find_map(|event| match event {
+                Event::Batch(batch) => Some(batch),
+                Event::Message(_) => None,
+            })
+            .unwrap();
+        let events = MetadataEvent::from_batch(&batch);
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1],
+            MetadataEvent::Update(MetadataUpdate {
+                target: b"nick",
+                key: b"avatar",
+                visibility: b"*",
+                value: Some(b"https://b")
+            })
+        );
+    }
+}
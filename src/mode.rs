@@ -0,0 +1,757 @@
+use crate::command::Command;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use crate::tags::Tags;
+use alloc::borrow::Cow;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Whether a mode is set or unset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModeChange {
+    /// `+`
+    Add,
+    /// `-`
+    Remove,
+}
+
+/// One mode of a mode string with its argument
+pub type ModeEntry<'a> = (ModeChange, char, Option<&'a [u8]>);
+
+/// The channel modes by category, advertised in the CHANMODES token of
+/// RPL_ISUPPORT: `CHANMODES=beI,k,l,imnpst`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChanModes {
+    /// The modes of a list, they always take an argument but it may be
+    /// omitted to ask for the list
+    pub list: Vec<char>,
+    /// The modes always taking an argument
+    pub always_arg: Vec<char>,
+    /// The modes taking an argument only when they are set
+    pub arg_when_set: Vec<char>,
+    /// The modes never taking an argument
+    pub no_arg: Vec<char>,
+}
+
+impl ChanModes {
+    /// Parse the value of the CHANMODES token
+    /// Servers may add more categories in the future, they are ignored.
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let mut categories = value
+            .split(|&c| c == b',')
+            .map(|modes| modes.iter().map(|&c| char::from(c)).collect());
+        Some(ChanModes {
+            list: categories.next()?,
+            always_arg: categories.next()?,
+            arg_when_set: categories.next()?,
+            no_arg: categories.next()?,
+        })
+    }
+
+    /// Return true if the mode takes an argument when it is changed this way
+    pub fn takes_arg(&self, change: ModeChange, mode: char) -> bool {
+        self.list.contains(&mode)
+            || self.always_arg.contains(&mode)
+            || (change == ModeChange::Add && self.arg_when_set.contains(&mode))
+    }
+}
+
+/// The modes described in RFC 2812: `beI,k,l,imnpst`
+impl Default for ChanModes {
+    fn default() -> Self {
+        ChanModes {
+            list: vec!['b', 'e', 'I'],
+            always_arg: vec!['k'],
+            arg_when_set: vec!['l'],
+            no_arg: vec!['i', 'm', 'n', 'p', 's', 't'],
+        }
+    }
+}
+
+/// The channel membership modes and their prefixes, highest first,
+/// advertised in the PREFIX token of RPL_ISUPPORT: `PREFIX=(ov)@+`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixModes(pub Vec<(char, u8)>);
+
+impl PrefixModes {
+    /// Parse the value of the PREFIX token, an empty value means there is no
+    /// membership mode
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        if value.is_empty() {
+            return Some(PrefixModes(Vec::new()));
+        }
+        let rest = value.strip_prefix(b"(")?;
+        let close = rest.iter().position(|&c| c == b')')?;
+        let (modes, symbols) = (&rest[..close], &rest[close + 1..]);
+        if modes.len() != symbols.len() {
+            return None;
+        }
+        let entries = modes
+            .iter()
+            .map(|&c| char::from(c))
+            .zip(symbols.iter().copied());
+        Some(PrefixModes(entries.collect()))
+    }
+
+    /// Return the prefix of a membership mode: `@` for `o`
+    pub fn symbol(&self, mode: char) -> Option<u8> {
+        self.0
+            .iter()
+            .find(|entry| entry.0 == mode)
+            .map(|entry| entry.1)
+    }
+
+    /// Return the membership mode of a prefix: `o` for `@`
+    pub fn mode(&self, symbol: u8) -> Option<char> {
+        self.0
+            .iter()
+            .find(|entry| entry.1 == symbol)
+            .map(|entry| entry.0)
+    }
+
+    /// Return the prefixes, highest first, as expected by
+    /// `Names::from_message_with`
+    pub fn symbols(&self) -> Vec<u8> {
+        self.0.iter().map(|entry| entry.1).collect()
+    }
+
+    /// Return the membership of a prefix: `@`
+    pub fn prefix(&self, symbol: u8) -> Option<MembershipPrefix> {
+        let rank = self.0.iter().position(|entry| entry.1 == symbol)?;
+        Some(MembershipPrefix {
+            mode: self.0[rank].0,
+            symbol,
+            rank,
+        })
+    }
+
+    /// Return the highest membership among the prefixes of a member, they
+    /// can be in any order: `+@` gives `@`
+    pub fn highest(&self, prefixes: &[u8]) -> Option<MembershipPrefix> {
+        prefixes.iter().filter_map(|&c| self.prefix(c)).max()
+    }
+
+    /// Whether a member with these prefixes has at least the membership of
+    /// `symbol`: `@` is at least `%`
+    /// An unknown `symbol` is never reached.
+    pub fn is_at_least(&self, prefixes: &[u8], symbol: u8) -> bool {
+        match (self.highest(prefixes), self.prefix(symbol)) {
+            (Some(highest), Some(wanted)) => highest >= wanted,
+            _ => false,
+        }
+    }
+
+    /// Order two members by their highest membership, a member without
+    /// prefix is the lowest
+    /// ```
+    /// use irc_parser::mode::PrefixModes;
+    ///
+    /// let prefixes = PrefixModes::parse(b"(qaohv)~&@%+").unwrap();
+    /// let mut members = vec![&b"+"[..], b"", b"%+", b"@"];
+    /// members.sort_by(|a, b| prefixes.compare(b, a));
+    /// assert_eq!(members, vec![&b"@"[..], b"%+", b"+", b""]);
+    /// assert!(prefixes.is_at_least(b"+@", b'%'));
+    /// ```
+    pub fn compare(&self, a: &[u8], b: &[u8]) -> Ordering {
+        self.highest(a).cmp(&self.highest(b))
+    }
+}
+
+/// A channel membership, the ones advertised first are higher: `@` > `%` >
+/// `+`
+/// The memberships of different `PrefixModes` are not comparable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MembershipPrefix {
+    /// The membership mode: `o`
+    pub mode: char,
+    /// The prefix: `@`
+    pub symbol: u8,
+    /// The position in the PREFIX token, 0 for the highest
+    pub rank: usize,
+}
+
+impl Ord for MembershipPrefix {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.rank.cmp(&self.rank)
+    }
+}
+
+impl PartialOrd for MembershipPrefix {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `(ov)@+` as described in RFC 2812
+impl Default for PrefixModes {
+    fn default() -> Self {
+        PrefixModes(vec![('o', b'@'), ('v', b'+')])
+    }
+}
+
+/// Interpret the parameters following the target of a channel MODE:
+/// `+ov-b nick nick mask`
+/// The modes `b`, `e`, `I`, `k`, `o` and `v` always take an argument and `l`
+/// takes one when it is set, as described in RFC 2812. A list mode without
+/// argument (`+b`) asks for the list and is returned with no argument.
+pub fn channel_modes<'a>(params: &[&'a [u8]]) -> Vec<ModeEntry<'a>> {
+    channel_modes_with(params, &ChanModes::default(), &PrefixModes::default())
+}
+
+/// Same as `channel_modes` with the modes advertised by the server, the
+/// membership modes always take an argument and the unknown modes never do
+pub fn channel_modes_with<'a>(
+    params: &[&'a [u8]],
+    chanmodes: &ChanModes,
+    prefixes: &PrefixModes,
+) -> Vec<ModeEntry<'a>> {
+    modes_with(params, |change, mode| {
+        prefixes.symbol(mode).is_some() || chanmodes.takes_arg(change, mode)
+    })
+}
+
+/// Interpret the parameters following the target of a user MODE: `+iw-o`
+/// User modes never take an argument.
+pub fn user_modes<'a>(params: &[&'a [u8]]) -> Vec<ModeEntry<'a>> {
+    modes_with(params, |_, _| false)
+}
+
+/// The modes set on a user, one bit per letter
+/// ```
+/// use irc_parser::message::parse_message;
+/// use irc_parser::mode::{user_modes, UserModes};
+///
+/// let reply = parse_message(b":irc.example.com 221 nick +iw\r\n").unwrap();
+/// let mut modes = UserModes::from_message(&reply).unwrap();
+/// modes.apply(&user_modes(&[b"-w+o"]));
+/// assert!(modes.is_invisible() && modes.is_oper());
+/// assert_eq!(modes.to_bytes(), b"+io");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct UserModes(u64);
+
+impl UserModes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a compact mode string: `+iw`, the chars other than ASCII
+    /// letters are ignored
+    pub fn parse(modes: &[u8]) -> Self {
+        let mut user_modes = Self::new();
+        for &mode in modes {
+            user_modes.set(char::from(mode), ModeChange::Add);
+        }
+        user_modes
+    }
+
+    /// Interpret a RPL_UMODEIS (221): `:server 221 nick +iw`
+    pub fn from_message(message: &Message) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplUModeIs) {
+            return None;
+        }
+        message.params.get(1).map(|modes| Self::parse(modes))
+    }
+
+    /// Apply the changes of a user MODE, see `user_modes`
+    pub fn apply(&mut self, entries: &[ModeEntry]) {
+        for &(change, mode, _) in entries {
+            self.set(mode, change);
+        }
+    }
+
+    /// Set or unset a mode, the chars other than ASCII letters are ignored
+    pub fn set(&mut self, mode: char, change: ModeChange) {
+        if let Some(bit) = bit(mode) {
+            match change {
+                ModeChange::Add => self.0 |= bit,
+                ModeChange::Remove => self.0 &= !bit,
+            }
+        }
+    }
+
+    pub fn contains(&self, mode: char) -> bool {
+        bit(mode).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    /// `+i`
+    pub fn is_invisible(&self) -> bool {
+        self.contains('i')
+    }
+
+    /// `+o`, or `+O` for the operators of the local server
+    pub fn is_oper(&self) -> bool {
+        self.contains('o') || self.contains('O')
+    }
+
+    /// `+w`
+    pub fn is_wallops(&self) -> bool {
+        self.contains('w')
+    }
+
+    /// Return the modes in alphabetical order, lower case first
+    pub fn modes(&self) -> impl Iterator<Item = char> + '_ {
+        ('a'..='z')
+            .chain('A'..='Z')
+            .filter(move |&mode| self.contains(mode))
+    }
+
+    /// Serialize the modes as a compact mode string: `+iw`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![b'+'];
+        bytes.extend(self.modes().map(|mode| mode as u8));
+        bytes
+    }
+}
+
+/// The state of a channel: its flags, key, limit and lists
+/// ```
+/// use irc_parser::mode::{channel_modes, ChannelModes};
+///
+/// let mut current = ChannelModes::new();
+/// current.apply(&channel_modes(&[b"+ntk", b"key"]));
+/// let mut wanted = current.clone();
+/// wanted.apply(&channel_modes(&[b"-t+lb", b"10", b"*!*@spam"]));
+/// assert_eq!(current.diff(&wanted), [&b"-t+lb"[..], b"10", b"*!*@spam"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelModes {
+    flags: u64,
+    /// `+k`
+    pub key: Option<Vec<u8>>,
+    /// `+l`
+    pub limit: Option<usize>,
+    /// `+b`
+    pub bans: Vec<Vec<u8>>,
+    /// `+e`
+    pub excepts: Vec<Vec<u8>>,
+    /// `+I`
+    pub invites: Vec<Vec<u8>>,
+}
+
+impl ChannelModes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the changes of a channel MODE, see `channel_modes`
+    /// A mode without argument is a flag, except the list modes whose list
+    /// was only asked for. The modes with an argument other than the key, the
+    /// limit and the lists, like the membership modes, are ignored.
+    pub fn apply(&mut self, entries: &[ModeEntry]) {
+        for &(change, mode, arg) in entries {
+            let list = match mode {
+                'b' => &mut self.bans,
+                'e' => &mut self.excepts,
+                'I' => &mut self.invites,
+                'k' => {
+                    self.key = match change {
+                        ModeChange::Add => arg.map(<[u8]>::to_vec),
+                        ModeChange::Remove => None,
+                    };
+                    continue;
+                }
+                'l' => {
+                    self.limit = match (change, arg) {
+                        (ModeChange::Add, Some(limit)) => core::str::from_utf8(limit)
+                            .ok()
+                            .and_then(|limit| limit.parse().ok()),
+                        _ => None,
+                    };
+                    continue;
+                }
+                _ => {
+                    if arg.is_none() {
+                        self.set_flag(mode, change);
+                    }
+                    continue;
+                }
+            };
+            let mask = match arg {
+                Some(mask) => mask,
+                None => continue,
+            };
+            let position = list.iter().position(|entry| entry == mask);
+            match (change, position) {
+                (ModeChange::Add, None) => list.push(mask.to_vec()),
+                (ModeChange::Remove, Some(position)) => {
+                    list.remove(position);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Set or unset a flag, the chars other than ASCII letters are ignored
+    pub fn set_flag(&mut self, mode: char, change: ModeChange) {
+        if let Some(bit) = bit(mode) {
+            match change {
+                ModeChange::Add => self.flags |= bit,
+                ModeChange::Remove => self.flags &= !bit,
+            }
+        }
+    }
+
+    pub fn has_flag(&self, mode: char) -> bool {
+        bit(mode).is_some_and(|bit| self.flags & bit != 0)
+    }
+
+    /// Return the flags in alphabetical order, lower case first
+    pub fn flags(&self) -> impl Iterator<Item = char> + '_ {
+        ('a'..='z')
+            .chain('A'..='Z')
+            .filter(move |&mode| self.has_flag(mode))
+    }
+
+    /// Return the parameters of the MODE changing this state into `other`:
+    /// the mode string followed by the arguments, or nothing if both are
+    /// the same
+    /// The removals come first, a changed key is removed before being set.
+    pub fn diff(&self, other: &Self) -> Vec<Vec<u8>> {
+        let mut entries: Vec<(ModeChange, char, Option<&[u8]>)> = Vec::new();
+        let (remove, add) = (ModeChange::Remove, ModeChange::Add);
+        entries.extend(
+            self.flags()
+                .filter(|&m| !other.has_flag(m))
+                .map(|m| (remove, m, None)),
+        );
+        if self.key.is_some() && self.key != other.key {
+            entries.push((remove, 'k', self.key.as_deref()));
+        }
+        if self.limit.is_some() && other.limit.is_none() {
+            entries.push((remove, 'l', None));
+        }
+        let lists = [
+            ('b', &self.bans, &other.bans),
+            ('e', &self.excepts, &other.excepts),
+            ('I', &self.invites, &other.invites),
+        ];
+        for (mode, from, to) in lists.iter() {
+            let removed = from.iter().filter(|mask| !to.contains(mask));
+            entries.extend(removed.map(|mask| (remove, *mode, Some(&mask[..]))));
+        }
+        entries.extend(
+            other
+                .flags()
+                .filter(|&m| !self.has_flag(m))
+                .map(|m| (add, m, None)),
+        );
+        if other.key.is_some() && self.key != other.key {
+            entries.push((add, 'k', other.key.as_deref()));
+        }
+        let limit = other.limit.filter(|_| self.limit != other.limit);
+        let limit = limit.map(|limit| limit.to_string().into_bytes());
+        if let Some(limit) = &limit {
+            entries.push((add, 'l', Some(limit)));
+        }
+        for (mode, from, to) in lists.iter() {
+            let added = to.iter().filter(|mask| !from.contains(mask));
+            entries.extend(added.map(|mask| (add, *mode, Some(&mask[..]))));
+        }
+
+        mode_params(&entries)
+            .iter()
+            .map(|param| param.to_vec())
+            .collect()
+    }
+}
+
+/// Merge the changes into as few MODE lines as possible, serialized with
+/// their crlf: `+o a`, `+o b` and `-b x` give `MODE #chan +oo-b a b x`
+/// A line holds at most `max_modes` modes with an argument, the MODES token
+/// of RPL_ISUPPORT or 3 if the server didn't advertise it.
+/// ```
+/// use irc_parser::mode::{mode_lines, ModeChange::*};
+///
+/// let entries = [
+///     (Add, 'o', Some(&b"a"[..])),
+///     (Add, 'o', Some(&b"b"[..])),
+///     (Add, 'n', None),
+///     (Remove, 'b', Some(&b"x"[..])),
+/// ];
+/// assert_eq!(
+///     mode_lines(b"#chan", &entries, 2),
+///     vec![b"MODE #chan +oon a b\r\n".to_vec(), b"MODE #chan -b x\r\n".to_vec()]
+/// );
+/// ```
+pub fn mode_lines(target: &[u8], entries: &[ModeEntry], max_modes: usize) -> Vec<Vec<u8>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut with_arg = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.2.is_some() {
+            if with_arg == max_modes.max(1) {
+                lines.push(mode_line(target, &entries[start..i]));
+                start = i;
+                with_arg = 0;
+            }
+            with_arg += 1;
+        }
+    }
+    if start < entries.len() {
+        lines.push(mode_line(target, &entries[start..]));
+    }
+    lines
+}
+
+fn mode_line(target: &[u8], entries: &[ModeEntry]) -> Vec<u8> {
+    let modes = mode_params(entries);
+    let mut params: Vec<&[u8]> = vec![target];
+    params.extend(modes.iter().map(|param| &param[..]));
+    Message {
+        tags: Tags::new(),
+        prefix: None,
+        command: Command::Mode,
+        params: params.into(),
+    }
+    .to_bytes()
+}
+
+/// Return the compact mode string of the changes followed by their
+/// arguments, or nothing if there is no change
+fn mode_params<'a>(entries: &[ModeEntry<'a>]) -> Vec<Cow<'a, [u8]>> {
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    let mut modes = Vec::new();
+    let mut args = Vec::new();
+    let mut current = None;
+    for &(change, mode, arg) in entries {
+        if current != Some(change) {
+            modes.push(match change {
+                ModeChange::Add => b'+',
+                ModeChange::Remove => b'-',
+            });
+            current = Some(change);
+        }
+        modes.push(mode as u8);
+        args.extend(arg.map(Cow::Borrowed));
+    }
+    let mut params = vec![Cow::Owned(modes)];
+    params.extend(args);
+    params
+}
+
+fn bit(mode: char) -> Option<u64> {
+    match mode {
+        'a'..='z' => Some(1 << (mode as u8 - b'a')),
+        'A'..='Z' => Some(1 << (26 + mode as u8 - b'A')),
+        _ => None,
+    }
+}
+
+/// Interpret a mode string followed by its arguments, `takes_arg` tells if
+/// a mode consumes the next argument
+/// A mode string may contain multiple `+` and `-` runs, the modes before the
+/// first one are added. Mode strings may be split over multiple parameters,
+/// `+o nick -v nick`, the following ones must start with `+` or `-` and the
+/// unused arguments are ignored.
+pub fn modes_with<'a, F>(params: &[&'a [u8]], takes_arg: F) -> Vec<ModeEntry<'a>>
+where
+    F: Fn(ModeChange, char) -> bool,
+{
+    let mut entries = Vec::new();
+    let mut params = params.iter();
+    let mut change = ModeChange::Add;
+    let mut first = true;
+    while let Some(modes) = params.next() {
+        if !first && !modes.starts_with(b"+") && !modes.starts_with(b"-") {
+            continue;
+        }
+        first = false;
+        for &c in modes.iter() {
+            match c {
+                b'+' => change = ModeChange::Add,
+                b'-' => change = ModeChange::Remove,
+                c => {
+                    let mode = char::from(c);
+                    let arg = if takes_arg(change, mode) {
+                        params.next().copied()
+                    } else {
+                        None
+                    };
+                    entries.push((change, mode, arg));
+                }
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ModeChange::*;
+
+    #[test]
+    fn modes_channel() {
+        let params: &[&[u8]] = &[b"+ov-b", b"nick1", b"nick2", b"*!*@host"];
+        assert_eq!(
+            channel_modes(params),
+            vec![
+                (Add, 'o', Some(&b"nick1"[..])),
+                (Add, 'v', Some(&b"nick2"[..])),
+                (Remove, 'b', Some(&b"*!*@host"[..])),
+            ]
+        );
+        let params: &[&[u8]] = &[b"+lk-l", b"10", b"key"];
+        assert_eq!(
+            channel_modes(params),
+            vec![
+                (Add, 'l', Some(&b"10"[..])),
+                (Add, 'k', Some(&b"key"[..])),
+                (Remove, 'l', None),
+            ]
+        );
+    }
+
+    #[test]
+    fn modes_channel_list_query() {
+        let params: &[&[u8]] = &[b"+b"];
+        assert_eq!(channel_modes(params), vec![(Add, 'b', None)]);
+    }
+
+    #[test]
+    fn modes_split_params() {
+        let params: &[&[u8]] = &[b"+o", b"nick", b"extra", b"-v", b"other"];
+        assert_eq!(
+            channel_modes(params),
+            vec![
+                (Add, 'o', Some(&b"nick"[..])),
+                (Remove, 'v', Some(&b"other"[..])),
+            ]
+        );
+    }
+
+    #[test]
+    fn modes_user() {
+        let params: &[&[u8]] = &[b"+iw-o"];
+        assert_eq!(
+            user_modes(params),
+            vec![(Add, 'i', None), (Add, 'w', None), (Remove, 'o', None)]
+        );
+    }
+
+    #[test]
+    fn modes_isupport() {
+        let chanmodes = ChanModes::parse(b"beIq,k,flj,CFLMPQcgimnprstz,X").unwrap();
+        assert_eq!(chanmodes.list, ['b', 'e', 'I', 'q']);
+        assert_eq!(chanmodes.no_arg.len(), 16);
+        let prefixes = PrefixModes::parse(b"(qaohv)~&@%+").unwrap();
+        assert_eq!(prefixes.symbol('h'), Some(b'%'));
+        assert_eq!(prefixes.mode(b'~'), Some('q'));
+        assert_eq!(prefixes.symbols(), b"~&@%+");
+        let params: &[&[u8]] = &[b"+hqj-f", b"nick", b"mask", b"3:5", b"extra"];
+        assert_eq!(
+            channel_modes_with(params, &chanmodes, &prefixes),
+            vec![
+                (Add, 'h', Some(&b"nick"[..])),
+                (Add, 'q', Some(&b"mask"[..])),
+                (Add, 'j', Some(&b"3:5"[..])),
+                (Remove, 'f', None),
+            ]
+        );
+        assert_eq!(ChanModes::parse(b"b,k,l"), None);
+        assert_eq!(PrefixModes::parse(b"(ov)@"), None);
+        assert_eq!(PrefixModes::parse(b""), Some(PrefixModes(vec![])));
+    }
+
+    #[test]
+    fn user_modes_state() {
+        let mut modes = UserModes::parse(b"+Ziw");
+        assert!(modes.contains('Z') && !modes.contains('z'));
+        modes.apply(&user_modes(&[b"+O-iZ+x"]));
+        assert!(modes.is_oper() && !modes.is_invisible() && modes.is_wallops());
+        assert_eq!(modes.modes().collect::<Vec<_>>(), ['w', 'x', 'O']);
+        assert_eq!(modes.to_bytes(), b"+wxO");
+        assert_eq!(UserModes::new().to_bytes(), b"+");
+        modes.set('1', Add);
+        assert_eq!(UserModes::parse(&modes.to_bytes()), modes);
+        let ping = crate::message::parse_message(b"PING :a\r\n").unwrap();
+        assert_eq!(UserModes::from_message(&ping), None);
+    }
+
+    #[test]
+    fn channel_modes_state() {
+        let mut modes = ChannelModes::new();
+        let params: &[&[u8]] = &[b"+nsklbbo", b"key", b"20", b"a!*@*", b"b!*@*", b"nick"];
+        modes.apply(&channel_modes(params));
+        assert!(modes.has_flag('n') && modes.has_flag('s') && !modes.has_flag('o'));
+        assert_eq!(modes.key.as_deref(), Some(&b"key"[..]));
+        assert_eq!(modes.limit, Some(20));
+        assert_eq!(modes.bans, [b"a!*@*".to_vec(), b"b!*@*".to_vec()]);
+        let params: &[&[u8]] = &[b"-sklb+b+e", b"key", b"a!*@*", b"b!*@*", b"c!*@*"];
+        modes.apply(&channel_modes(params));
+        assert_eq!(modes.flags().collect::<Vec<_>>(), ['n']);
+        assert_eq!((modes.key.as_ref(), modes.limit), (None, None));
+        assert_eq!(modes.bans, [b"b!*@*".to_vec()]);
+        assert_eq!(modes.excepts, [b"c!*@*".to_vec()]);
+        // asking for a list changes nothing
+        let before = modes.clone();
+        modes.apply(&channel_modes(&[b"+b"]));
+        assert_eq!(modes, before);
+    }
+
+    #[test]
+    fn channel_modes_diff() {
+        let mut from = ChannelModes::new();
+        from.apply(&channel_modes(&[b"+mkl", b"old", b"5"]));
+        assert!(from.diff(&from).is_empty());
+        let mut to = ChannelModes::new();
+        to.apply(&channel_modes(&[b"+nklI", b"new", b"5", b"*!*@friend"]));
+        let diff = from.diff(&to);
+        assert_eq!(diff, [&b"-mk+nkI"[..], b"old", b"new", b"*!*@friend"]);
+        // applying the diff gives the wanted state
+        let params: Vec<&[u8]> = diff.iter().map(Vec::as_slice).collect();
+        from.apply(&channel_modes(&params));
+        assert_eq!(from, to);
+        assert_eq!(
+            to.diff(&ChannelModes::new()),
+            [&b"-nklI"[..], b"new", b"*!*@friend"]
+        );
+    }
+
+    #[test]
+    fn modes_compaction() {
+        let entries = channel_modes(&[b"+o", b"a", b"+o", b"b", b"-b", b"x"]);
+        assert_eq!(
+            mode_lines(b"#chan", &entries, 3),
+            vec![b"MODE #chan +oo-b a b x\r\n".to_vec()]
+        );
+        assert_eq!(
+            mode_lines(b"#chan", &entries, 1),
+            vec![
+                b"MODE #chan +o a\r\n".to_vec(),
+                b"MODE #chan +o b\r\n".to_vec(),
+                b"MODE #chan -b x\r\n".to_vec()
+            ]
+        );
+        assert!(mode_lines(b"#chan", &[], 3).is_empty());
+        // the flags don't count
+        let entries = channel_modes(&[b"+nto-s", b"a"]);
+        assert_eq!(
+            mode_lines(b"#chan", &entries, 1),
+            vec![b"MODE #chan +nto-s a\r\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn membership_rank() {
+        let prefixes = PrefixModes::parse(b"(qaohv)~&@%+").unwrap();
+        let op = prefixes.prefix(b'@').unwrap();
+        assert_eq!((op.mode, op.rank), ('o', 2));
+        assert!(op > prefixes.prefix(b'%').unwrap());
+        assert!(op < prefixes.prefix(b'~').unwrap());
+        assert_eq!(prefixes.prefix(b'!'), None);
+        assert_eq!(prefixes.highest(b"+%").unwrap().symbol, b'%');
+        assert_eq!(prefixes.highest(b""), None);
+        assert!(prefixes.is_at_least(b"%", b'%'));
+        assert!(!prefixes.is_at_least(b"+", b'%'));
+        assert!(!prefixes.is_at_least(b"", b'+'));
+        assert!(!prefixes.is_at_least(b"@", b'!'));
+        assert_eq!(prefixes.compare(b"+", b""), Ordering::Greater);
+        assert_eq!(prefixes.compare(b"@+", b"@"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The list modes of a channel answered by a numeric per entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListKind {
+    /// `b`: RPL_BANLIST (367) and RPL_ENDOFBANLIST (368)
+    Ban,
+    /// `e`: RPL_EXCEPTLIST (348) and RPL_ENDOFEXCEPTLIST (349)
+    Except,
+    /// `I`: RPL_INVITELIST (346) and RPL_ENDOFINVITELIST (347)
+    Invite,
+}
+
+impl ListKind {
+    /// Return the channel mode of the list
+    pub fn mode(self) -> char {
+        match self {
+            ListKind::Ban => 'b',
+            ListKind::Except => 'e',
+            ListKind::Invite => 'I',
+        }
+    }
+
+    /// Return the kind of list of an entry numeric
+    pub fn from_entry(numeric: Numeric) -> Option<Self> {
+        match numeric {
+            Numeric::RplBanList => Some(ListKind::Ban),
+            Numeric::RplExceptList => Some(ListKind::Except),
+            Numeric::RplInviteList => Some(ListKind::Invite),
+            _ => None,
+        }
+    }
+
+    /// Return the kind of list of an end of list numeric
+    pub fn from_end(numeric: Numeric) -> Option<Self> {
+        match numeric {
+            Numeric::RplEndOfBanList => Some(ListKind::Ban),
+            Numeric::RplEndOfExceptList => Some(ListKind::Except),
+            Numeric::RplEndOfInviteList => Some(ListKind::Invite),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of a ban, exception or invite list
+/// `:server 367 me #chan *!*@host setter 1600000000`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListEntry<'a> {
+    pub kind: ListKind,
+    pub channel: &'a [u8],
+    pub mask: &'a [u8],
+    /// The nick or full prefix of who set the entry, not sent by every server
+    pub set_by: Option<&'a [u8]>,
+    /// When the entry was set, in seconds since the epoch
+    pub set_at: Option<u64>,
+}
+
+impl<'a> ListEntry<'a> {
+    /// Return `None` if the message is not a valid RPL_BANLIST,
+    /// RPL_EXCEPTLIST or RPL_INVITELIST
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        let kind = ListKind::from_entry(message.command.numeric()?)?;
+        let (channel, mask, set_by, set_at) = match message.params[..] {
+            [_, channel, mask] => (channel, mask, None, None),
+            [_, channel, mask, set_by] => (channel, mask, Some(set_by), None),
+            [_, channel, mask, set_by, set_at] => {
+                let set_at = core::str::from_utf8(set_at).ok()?.parse().ok()?;
+                (channel, mask, Some(set_by), Some(set_at))
+            }
+            _ => return None,
+        };
+        Some(ListEntry {
+            kind,
+            channel,
+            mask,
+            set_by,
+            set_at,
+        })
+    }
+}
+
+/// All the entries of a list of a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeList<'a> {
+    pub kind: ListKind,
+    pub channel: &'a [u8],
+    pub entries: Vec<ListEntry<'a>>,
+}
+
+/// Gather the entries of the ban, exception and invite lists until their
+/// end of list numeric
+#[derive(Debug, Clone, Default)]
+pub struct ModeListAccumulator<'a> {
+    pending: Vec<ModeList<'a>>,
+}
+
+impl<'a> ModeListAccumulator<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a message, anything else than the list numerics is ignored
+    /// Return all the entries of a list once its end arrived, an empty list
+    /// has no entry numeric at all.
+    /// ```
+    /// use irc_parser::message::parse_all;
+    /// use irc_parser::modelist::{ListKind, ModeListAccumulator};
+    ///
+    /// let input = b":irc 367 me #chan *!*@a op 1600000000\r\n\
+    ///     :irc 367 me #chan *!*@b op 1600000001\r\n\
+    ///     :irc 368 me #chan :End of channel ban list\r\n";
+    /// let messages: Vec<_> = parse_all(input).map(Result::unwrap).collect();
+    /// let mut acc = ModeListAccumulator::new();
+    /// let lists: Vec<_> = messages.iter().filter_map(|m| acc.feed(m)).collect();
+    /// assert_eq!(lists.len(), 1);
+    /// assert_eq!(lists[0].kind, ListKind::Ban);
+    /// assert_eq!(lists[0].entries[1].mask, b"*!*@b");
+    /// assert_eq!(lists[0].entries[1].set_at, Some(1600000001));
+    /// ```
+    pub fn feed(&mut self, message: &Message<'a>) -> Option<ModeList<'a>> {
+        let numeric = message.command.numeric()?;
+        if let Some(entry) = ListEntry::from_message(message) {
+            match self.position(entry.kind, entry.channel) {
+                Some(pos) => self.pending[pos].entries.push(entry),
+                None => self.pending.push(ModeList {
+                    kind: entry.kind,
+                    channel: entry.channel,
+                    entries: vec![entry],
+                }),
+            }
+            return None;
+        }
+        let kind = ListKind::from_end(numeric)?;
+        let channel = *message.params.get(1)?;
+        Some(match self.position(kind, channel) {
+            Some(pos) => self.pending.remove(pos),
+            None => ModeList {
+                kind,
+                channel,
+                entries: Vec::new(),
+            },
+        })
+    }
+
+    fn position(&self, kind: ListKind, channel: &[u8]) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|list| list.kind == kind && list.channel == channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{parse_all, parse_message};
+
+    #[test]
+    fn list_entry() {
+        let m = parse_message(b":irc 367 me #chan *!*@host op!u@h 1600000000\r\n").unwrap();
+        assert_eq!(
+            ListEntry::from_message(&m),
+            Some(ListEntry {
+                kind: ListKind::Ban,
+                channel: b"#chan",
+                mask: b"*!*@host",
+                set_by: Some(b"op!u@h"),
+                set_at: Some(1600000000),
+            })
+        );
+        let m = parse_message(b":irc 346 me #chan *!*@host\r\n").unwrap();
+        let entry = ListEntry::from_message(&m).unwrap();
+        assert_eq!(entry.kind, ListKind::Invite);
+        assert_eq!(entry.kind.mode(), 'I');
+        assert_eq!(entry.set_by, None);
+        let m = parse_message(b":irc 348 me #chan *!*@host op soon\r\n").unwrap();
+        assert_eq!(ListEntry::from_message(&m), None);
+        let m = parse_message(b":irc 368 me #chan :End of channel ban list\r\n").unwrap();
+        assert_eq!(ListEntry::from_message(&m), None);
+    }
+
+    #[test]
+    fn list_accumulate() {
+        let input = b":irc 367 me #a a op 1\r\n\
+            :irc 348 me #a e op 2\r\n\
+            :irc 367 me #b b op 3\r\n\
+            :irc 367 me #a c op 4\r\n\
+            :irc 368 me #a :End of channel ban list\r\n\
+            :irc 349 me #a :End of channel exception list\r\n\
+            :irc 347 me #a :End of channel invite list\r\n";
+        let messages: Vec<_> = parse_all(input).map(Result::unwrap).collect();
+        let mut acc = ModeListAccumulator::new();
+        let lists: Vec<_> = messages.iter().filter_map(|m| acc.feed(m)).collect();
+        assert_eq!(lists.len(), 3);
+        let masks: Vec<_> = lists[0].entries.iter().map(|e| e.mask).collect();
+        assert_eq!(masks, vec![&b"a"[..], b"c"]);
+        assert_eq!(lists[1].kind, ListKind::Except);
+        assert_eq!(lists[1].entries.len(), 1);
+        assert_eq!(lists[2].kind, ListKind::Invite);
+        assert!(lists[2].entries.is_empty());
+        // the list of #b never ended
+        assert_eq!(acc.pending.len(), 1);
+    }
+}
@@ -0,0 +1,341 @@
+//! The notify lists of MONITOR: the client gives the nicks it wants to
+//! follow and the server tells when they connect or leave
+//! ```
+//! use irc_parser::message::parse_message;
+//! use irc_parser::monitor::{Monitor, PresenceEvent, PresenceTracker};
+//!
+//! assert_eq!(Monitor::Add(vec![b"alice", b"bob"]).to_bytes(), b"MONITOR + alice,bob\r\n");
+//! let mut tracker = PresenceTracker::new();
+//! let reply = parse_message(b":irc 730 me :alice!a@host,bob\r\n").unwrap();
+//! let events = tracker.feed(&reply);
+//! assert_eq!(events[0], PresenceEvent::Online { nick: b"alice", user: Some(b"a"), host: Some(b"host") });
+//! assert_eq!(tracker.is_online("Bob"), Some(true));
+//! ```
+
+use crate::casemap::{CaseMapping, IrcKey};
+use crate::command::Command;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use crate::watch::WatchReply;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A MONITOR command sent by a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Monitor<'a> {
+    /// `MONITOR + nick,nick`: follow the nicks
+    Add(Vec<&'a [u8]>),
+    /// `MONITOR - nick,nick`: stop following the nicks
+    Remove(Vec<&'a [u8]>),
+    /// `MONITOR C`: stop following every nick
+    Clear,
+    /// `MONITOR L`: list the followed nicks
+    List,
+    /// `MONITOR S`: send the status of every followed nick
+    Status,
+}
+
+impl<'a> Monitor<'a> {
+    /// Return `None` if it's not a MONITOR or if it's malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        if message.command != Command::Monitor {
+            return None;
+        }
+        let subcommand = message.params.first()?.to_ascii_uppercase();
+        Some(match (&subcommand[..], &message.params[1..]) {
+            (b"+", [targets]) => Monitor::Add(list(targets)),
+            (b"-", [targets]) => Monitor::Remove(list(targets)),
+            (b"C", []) => Monitor::Clear,
+            (b"L", []) => Monitor::List,
+            (b"S", []) => Monitor::Status,
+            _ => return None,
+        })
+    }
+
+    /// Return the line to send, crlf included
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = b"MONITOR ".to_vec();
+        match self {
+            Monitor::Add(targets) | Monitor::Remove(targets) => {
+                line.push(match self {
+                    Monitor::Add(_) => b'+',
+                    _ => b'-',
+                });
+                line.push(b' ');
+                line.extend_from_slice(&targets.join(&b','));
+            }
+            Monitor::Clear => line.push(b'C'),
+            Monitor::List => line.push(b'L'),
+            Monitor::Status => line.push(b'S'),
+        }
+        line.extend_from_slice(b"\r\n");
+        line
+    }
+}
+
+/// A reply of the server to MONITOR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorReply<'a> {
+    /// RPL_MONONLINE (730): `:server 730 me :nick!user@host,nick`
+    Online(Vec<&'a [u8]>),
+    /// RPL_MONOFFLINE (731): `:server 731 me :nick,nick`
+    Offline(Vec<&'a [u8]>),
+    /// RPL_MONLIST (732): `:server 732 me :nick,nick`
+    List(Vec<&'a [u8]>),
+    /// RPL_ENDOFMONLIST (733)
+    EndOfList,
+    /// ERR_MONLISTFULL (734): `:server 734 me 100 nick,nick :Monitor list
+    /// is full.`, none of the targets were added
+    ListFull {
+        limit: usize,
+        targets: Vec<&'a [u8]>,
+    },
+}
+
+impl<'a> MonitorReply<'a> {
+    /// Return `None` if the message is not one of the MONITOR numerics or if
+    /// it's malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        let params = &message.params[..];
+        Some(match (message.command.numeric()?, params) {
+            (Numeric::RplMonOnline, [_, targets]) => MonitorReply::Online(list(targets)),
+            (Numeric::RplMonOffline, [_, targets]) => MonitorReply::Offline(list(targets)),
+            (Numeric::RplMonList, [_, targets]) => MonitorReply::List(list(targets)),
+            (Numeric::RplEndOfMonList, _) => MonitorReply::EndOfList,
+            (Numeric::ErrMonListIsFull, [_, limit, targets, ..]) => MonitorReply::ListFull {
+                limit: core::str::from_utf8(limit).ok()?.parse().ok()?,
+                targets: list(targets),
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A followed user connected or left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceEvent<'a> {
+    /// The user and host are only known if the server sent them
+    Online {
+        nick: &'a [u8],
+        user: Option<&'a [u8]>,
+        host: Option<&'a [u8]>,
+    },
+    Offline {
+        nick: &'a [u8],
+    },
+}
+
+/// Whether the followed nicks are online, from the replies of the server to
+/// MONITOR or to WATCH
+#[derive(Debug, Clone, Default)]
+pub struct PresenceTracker {
+    mapping: CaseMapping,
+    online: BTreeMap<IrcKey<'static>, bool>,
+}
+
+impl PresenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare the nicks with the case mapping advertised by the server
+    pub fn with_casemapping(mapping: CaseMapping) -> Self {
+        PresenceTracker {
+            mapping,
+            online: BTreeMap::new(),
+        }
+    }
+
+    /// Feed a message, anything else than RPL_MONONLINE, RPL_MONOFFLINE and
+    /// the WATCH replies is ignored
+    /// Return the nicks whose status changed or was unknown.
+    pub fn feed<'a>(&mut self, message: &Message<'a>) -> Vec<PresenceEvent<'a>> {
+        let events = match MonitorReply::from_message(message) {
+            Some(MonitorReply::Online(targets)) => targets
+                .into_iter()
+                .map(|target| presence(target, true))
+                .collect(),
+            Some(MonitorReply::Offline(targets)) => targets
+                .into_iter()
+                .map(|target| presence(target, false))
+                .collect(),
+            _ => self.watch_events(message),
+        };
+        events
+            .into_iter()
+            .filter(|event| {
+                let (nick, online) = match *event {
+                    PresenceEvent::Online { nick, .. } => (nick, true),
+                    PresenceEvent::Offline { nick } => (nick, false),
+                };
+                let key = IrcKey::new(nick.to_vec(), self.mapping);
+                self.online.insert(key, online) != Some(online)
+            })
+            .collect()
+    }
+
+    fn watch_events<'a>(&mut self, message: &Message<'a>) -> Vec<PresenceEvent<'a>> {
+        let event = match WatchReply::from_message(message) {
+            Some(WatchReply::LogOn(user))
+            | Some(WatchReply::NowOn(user))
+            | Some(WatchReply::NowAway(user)) => PresenceEvent::Online {
+                nick: user.nick,
+                user: user.user,
+                host: user.host,
+            },
+            Some(WatchReply::LogOff(user)) | Some(WatchReply::NowOff(user)) => {
+                PresenceEvent::Offline { nick: user.nick }
+            }
+            Some(WatchReply::WatchOff(user)) => {
+                self.forget(user.nick);
+                return Vec::new();
+            }
+            Some(WatchReply::Cleared) => {
+                self.clear();
+                return Vec::new();
+            }
+            _ => return Vec::new(),
+        };
+        vec![event]
+    }
+
+    /// Return whether a nick is online, `None` if the server didn't tell
+    pub fn is_online<N: AsRef<[u8]> + ?Sized>(&self, nick: &N) -> Option<bool> {
+        let key = IrcKey::new(nick.as_ref(), self.mapping);
+        self.online.get(&key.into_owned()).copied()
+    }
+
+    /// Return the nicks known to be online
+    pub fn online(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.online
+            .iter()
+            .filter(|(_, &online)| online)
+            .map(|(nick, _)| nick.as_bytes())
+    }
+
+    /// Forget the status of a nick, once it's removed from the list
+    pub fn forget<N: AsRef<[u8]> + ?Sized>(&mut self, nick: &N) {
+        let key = IrcKey::new(nick.as_ref(), self.mapping);
+        self.online.remove(&key.into_owned());
+    }
+
+    /// Forget every status, once the list is cleared
+    pub fn clear(&mut self) {
+        self.online.clear();
+    }
+}
+
+/// Interpret a `nick!user@host` or `nick` target
+fn presence(target: &[u8], online: bool) -> PresenceEvent<'_> {
+    let (nick, userhost) = match target.iter().position(|&c| c == b'!') {
+        Some(pos) => (&target[..pos], Some(&target[pos + 1..])),
+        None => (target, None),
+    };
+    if !online {
+        return PresenceEvent::Offline { nick };
+    }
+    let (user, host) = match userhost {
+        Some(userhost) => match userhost.iter().position(|&c| c == b'@') {
+            Some(pos) => (Some(&userhost[..pos]), Some(&userhost[pos + 1..])),
+            None => (Some(userhost), None),
+        },
+        None => (None, None),
+    };
+    PresenceEvent::Online { nick, user, host }
+}
+
+fn list(targets: &[u8]) -> Vec<&[u8]> {
+    targets
+        .split(|&c| c == b',')
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn monitor_command() {
+        let m = parse_message(b"MONITOR + a,b\r\n").unwrap();
+        assert_eq!(
+            Monitor::from_message(&m),
+            Some(Monitor::Add(vec![b"a", b"b"]))
+        );
+        let m = parse_message(b"MONITOR c\r\n").unwrap();
+        assert_eq!(Monitor::from_message(&m), Some(Monitor::Clear));
+        let m = parse_message(b"MONITOR -\r\n").unwrap();
+        assert_eq!(Monitor::from_message(&m), None);
+        assert_eq!(Monitor::Remove(vec![b"a"]).to_bytes(), b"MONITOR - a\r\n");
+        assert_eq!(Monitor::Status.to_bytes(), b"MONITOR S\r\n");
+    }
+
+    #[test]
+    fn monitor_replies() {
+        let m = parse_message(b":irc 734 me 2 a,b :Monitor list is full.\r\n").unwrap();
+        assert_eq!(
+            MonitorReply::from_message(&m),
+            Some(MonitorReply::ListFull {
+                limit: 2,
+                targets: vec![b"a", b"b"]
+            })
+        );
+        let m = parse_message(b":irc 732 me :a,b\r\n").unwrap();
+        assert_eq!(
+            MonitorReply::from_message(&m),
+            Some(MonitorReply::List(vec![b"a", b"b"]))
+        );
+        let m = parse_message(b":irc 733 me :End of MONITOR list\r\n").unwrap();
+        assert_eq!(
+            MonitorReply::from_message(&m),
+            Some(MonitorReply::EndOfList)
+        );
+    }
+
+    #[test]
+    fn presence_tracking() {
+        let mut tracker = PresenceTracker::new();
+        let m = parse_message(b":irc 731 me :a,B[1]\r\n").unwrap();
+        assert_eq!(tracker.feed(&m).len(), 2);
+        // no change
+        assert!(tracker.feed(&m).is_empty());
+        let m = parse_message(b":irc 730 me :b{1}!u@h\r\n").unwrap();
+        assert_eq!(
+            tracker.feed(&m),
+            vec![PresenceEvent::Online {
+                nick: b"b{1}",
+                user: Some(b"u"),
+                host: Some(b"h")
+            }]
+        );
+        assert_eq!(tracker.is_online("a"), Some(false));
+        assert_eq!(tracker.is_online("c"), None);
+        assert_eq!(tracker.online().collect::<Vec<_>>(), vec![&b"B[1]"[..]]);
+        tracker.forget("a");
+        assert_eq!(tracker.is_online("a"), None);
+    }
+
+    #[test]
+    fn presence_with_watch() {
+        let mut tracker = PresenceTracker::new();
+        let m = parse_message(b":irc 609 me a u h 1600000000 :is away\r\n").unwrap();
+        assert_eq!(
+            tracker.feed(&m),
+            vec![PresenceEvent::Online {
+                nick: b"a",
+                user: Some(b"u"),
+                host: Some(b"h")
+            }]
+        );
+        let m = parse_message(b":irc 601 me a u h 1600000001 :logged offline\r\n").unwrap();
+        assert_eq!(
+            tracker.feed(&m),
+            vec![PresenceEvent::Offline { nick: b"a" }]
+        );
+        let m = parse_message(b":irc 602 me a u h 0 :stopped watching\r\n").unwrap();
+        assert!(tracker.feed(&m).is_empty());
+        assert_eq!(tracker.is_online("a"), None);
+    }
+}
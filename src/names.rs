@@ -0,0 +1,219 @@
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::vec::Vec;
+
+/// The membership prefixes used when the server didn't advertise any
+pub const DEFAULT_PREFIXES: &[u8] = b"~&@%+";
+
+/// The visibility of a channel in a RPL_NAMREPLY
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    /// `=`
+    Public,
+    /// `*`
+    Private,
+    /// `@`
+    Secret,
+}
+
+/// A member of a channel: `@+nick` or `@nick!user@host`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Member<'a> {
+    /// All the membership prefixes, highest first with multi-prefix: `@+`
+    pub prefixes: &'a [u8],
+    pub nick: &'a [u8],
+    /// Only sent with userhost-in-names
+    pub user: Option<&'a [u8]>,
+    /// Only sent with userhost-in-names
+    pub host: Option<&'a [u8]>,
+}
+
+/// The content of one or more RPL_NAMREPLY
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Names<'a> {
+    pub visibility: Visibility,
+    pub channel: &'a [u8],
+    pub members: Vec<Member<'a>>,
+}
+
+impl<'a> Names<'a> {
+    /// Interpret a RPL_NAMREPLY (353) with the default membership prefixes
+    /// `:server 353 me = #chan :@op +voice nick`
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        Self::from_message_with(message, DEFAULT_PREFIXES)
+    }
+
+    /// Same as `from_message` with the membership prefixes advertised by the
+    /// server in the PREFIX token of RPL_ISUPPORT
+    pub fn from_message_with(message: &Message<'a>, prefixes: &[u8]) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplNamReply) {
+            return None;
+        }
+        let (visibility, channel, names) = match &message.params[..] {
+            [_, visibility, channel, names] => (*visibility, *channel, *names),
+            // some servers omit the visibility
+            [_, channel, names] => (&b"="[..], *channel, *names),
+            _ => return None,
+        };
+        let visibility = match visibility {
+            b"=" => Visibility::Public,
+            b"*" => Visibility::Private,
+            b"@" => Visibility::Secret,
+            _ => return None,
+        };
+        let members = names
+            .split(|&c| c == b' ')
+            .filter(|name| !name.is_empty())
+            .map(|name| member(name, prefixes))
+            .collect();
+        Some(Names {
+            visibility,
+            channel,
+            members,
+        })
+    }
+}
+
+/// Split a name of RPL_NAMREPLY
+pub fn member<'a>(name: &'a [u8], prefixes: &[u8]) -> Member<'a> {
+    let len = name.iter().take_while(|c| prefixes.contains(c)).count();
+    let (prefix, name) = name.split_at(len);
+    let (nick, userhost) = match name.iter().position(|&c| c == b'!') {
+        Some(pos) => (&name[..pos], Some(&name[pos + 1..])),
+        None => (name, None),
+    };
+    let (user, host) = match userhost {
+        Some(userhost) => match userhost.iter().position(|&c| c == b'@') {
+            Some(pos) => (Some(&userhost[..pos]), Some(&userhost[pos + 1..])),
+            None => (Some(userhost), None),
+        },
+        None => (None, None),
+    };
+    Member {
+        prefixes: prefix,
+        nick,
+        user,
+        host,
+    }
+}
+
+/// Gather the RPL_NAMREPLY of a channel until its RPL_ENDOFNAMES
+#[derive(Debug, Clone, Default)]
+pub struct NamesAccumulator<'a> {
+    pending: Vec<Names<'a>>,
+    prefixes: Option<Vec<u8>>,
+}
+
+impl<'a> NamesAccumulator<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the membership prefixes advertised by the server
+    pub fn with_prefixes(prefixes: &[u8]) -> Self {
+        NamesAccumulator {
+            pending: Vec::new(),
+            prefixes: Some(prefixes.to_vec()),
+        }
+    }
+
+    /// Feed a message, anything else than 353 and 366 is ignored
+    /// Return all the members of a channel once its RPL_ENDOFNAMES (366)
+    /// arrived. A channel without any 353 has no member and is public.
+    pub fn feed(&mut self, message: &Message<'a>) -> Option<Names<'a>> {
+        match message.command.numeric()? {
+            Numeric::RplNamReply => {
+                let prefixes = self.prefixes.as_deref().unwrap_or(DEFAULT_PREFIXES);
+                let names = Names::from_message_with(message, prefixes)?;
+                match self.position(names.channel) {
+                    Some(pos) => self.pending[pos].members.extend(names.members),
+                    None => self.pending.push(names),
+                }
+                None
+            }
+            Numeric::RplEndOfNames => {
+                let channel = *message.params.get(1)?;
+                Some(match self.position(channel) {
+                    Some(pos) => self.pending.remove(pos),
+                    None => Names {
+                        visibility: Visibility::Public,
+                        channel,
+                        members: Vec::new(),
+                    },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn position(&self, channel: &[u8]) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|names| names.channel == channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{parse_all, parse_message};
+
+    #[test]
+    fn names_reply() {
+        let m = parse_message(b":irc 353 me @ #chan :@+op voice!u@h  nick\r\n").unwrap();
+        let names = Names::from_message(&m).unwrap();
+        assert_eq!(names.visibility, Visibility::Secret);
+        assert_eq!(names.channel, b"#chan");
+        assert_eq!(
+            names.members,
+            vec![
+                Member {
+                    prefixes: b"@+",
+                    nick: b"op",
+                    user: None,
+                    host: None
+                },
+                Member {
+                    prefixes: b"",
+                    nick: b"voice",
+                    user: Some(b"u"),
+                    host: Some(b"h")
+                },
+                Member {
+                    prefixes: b"",
+                    nick: b"nick",
+                    user: None,
+                    host: None
+                },
+            ]
+        );
+        let m = parse_message(b":irc 366 me #chan :End of /NAMES list.\r\n").unwrap();
+        assert_eq!(Names::from_message(&m), None);
+    }
+
+    #[test]
+    fn names_custom_prefixes() {
+        assert_eq!(member(b"!nick", b"!@").prefixes, b"!");
+        assert_eq!(member(b"~nick", b"@+").nick, b"~nick");
+    }
+
+    #[test]
+    fn names_accumulate() {
+        let input = b":irc 353 me = #a :@op\r\n\
+            :irc 353 me = #b :nick\r\n\
+            :irc 353 me = #a :+voice\r\n\
+            :irc 366 me #a :End of /NAMES list.\r\n\
+            :irc 366 me #b :End of /NAMES list.\r\n\
+            :irc 366 me #c :End of /NAMES list.\r\n";
+        let messages: Vec<_> = parse_all(input).map(Result::unwrap).collect();
+        let mut acc = NamesAccumulator::new();
+        let names: Vec<_> = messages.iter().filter_map(|m| acc.feed(m)).collect();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0].channel, b"#a");
+        let nicks: Vec<_> = names[0].members.iter().map(|m| m.nick).collect();
+        assert_eq!(nicks, vec![&b"op"[..], b"voice"]);
+        assert_eq!(names[1].members.len(), 1);
+        assert_eq!(names[2].channel, b"#c");
+        assert!(names[2].members.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::command::Command;
+
+macro_rules! numerics {
+    ($($variant:ident => $code:literal,)*) => {
+        /// A numeric reply with the name it has in the RFCs
+        /// Codes without a known name are kept in `Unknown`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Numeric {
+            $(
+                #[doc = concat!("`", stringify!($code), "`")]
+                $variant,
+            )*
+            Unknown(u16),
+        }
+
+        impl Numeric {
+            /// Return the reply with the code `code`
+            pub fn from_u16(code: u16) -> Self {
+                match code {
+                    $($code => Numeric::$variant,)*
+                    code => Numeric::Unknown(code),
+                }
+            }
+
+            /// Return the code of the reply
+            pub fn to_u16(self) -> u16 {
+                match self {
+                    $(Numeric::$variant => $code,)*
+                    Numeric::Unknown(code) => code,
+                }
+            }
+        }
+    };
+}
+
+numerics! {
+    RplWelcome => 1,
+    RplYourHost => 2,
+    RplCreated => 3,
+    RplMyInfo => 4,
+    RplISupport => 5,
+    RplBounce => 10,
+    RplTraceLink => 200,
+    RplTraceConnecting => 201,
+    RplTraceHandshake => 202,
+    RplTraceUnknown => 203,
+    RplTraceOperator => 204,
+    RplTraceUser => 205,
+    RplTraceServer => 206,
+    RplTraceService => 207,
+    RplTraceNewType => 208,
+    RplTraceClass => 209,
+    RplStatsLinkInfo => 211,
+    RplStatsCommands => 212,
+    RplStatsCLine => 213,
+    RplStatsILine => 215,
+    RplStatsKLine => 216,
+    RplStatsYLine => 218,
+    RplEndOfStats => 219,
+    RplUModeIs => 221,
+    RplServList => 234,
+    RplServListEnd => 235,
+    RplStatsUptime => 242,
+    RplStatsOLine => 243,
+    RplStatsHLine => 244,
+    RplLUserClient => 251,
+    RplLUserOp => 252,
+    RplLUserUnknown => 253,
+    RplLUserChannels => 254,
+    RplLUserMe => 255,
+    RplAdminMe => 256,
+    RplAdminLoc1 => 257,
+    RplAdminLoc2 => 258,
+    RplAdminEmail => 259,
+    RplTraceLog => 261,
+    RplTraceEnd => 262,
+    RplTryAgain => 263,
+    RplLocalUsers => 265,
+    RplGlobalUsers => 266,
+    RplWhoisCertFp => 276,
+    RplAway => 301,
+    RplUserHost => 302,
+    RplIsOn => 303,
+    RplUnAway => 305,
+    RplNowAway => 306,
+    RplWhoisUser => 311,
+    RplWhoisServer => 312,
+    RplWhoisOperator => 313,
+    RplWhowasUser => 314,
+    RplEndOfWho => 315,
+    RplWhoisIdle => 317,
+    RplEndOfWhois => 318,
+    RplWhoisChannels => 319,
+    RplListStart => 321,
+    RplList => 322,
+    RplListEnd => 323,
+    RplChannelModeIs => 324,
+    RplUniqOpIs => 325,
+    RplCreationTime => 329,
+    RplWhoisAccount => 330,
+    RplNoTopic => 331,
+    RplTopic => 332,
+    RplTopicWhoTime => 333,
+    RplInviting => 341,
+    RplSummoning => 342,
+    RplInviteList => 346,
+    RplEndOfInviteList => 347,
+    RplExceptList => 348,
+    RplEndOfExceptList => 349,
+    RplVersion => 351,
+    RplWhoReply => 352,
+    RplNamReply => 353,
+    RplWhoSpcRpl => 354,
+    RplLinks => 364,
+    RplEndOfLinks => 365,
+    RplEndOfNames => 366,
+    RplBanList => 367,
+    RplEndOfBanList => 368,
+    RplEndOfWhowas => 369,
+    RplInfo => 371,
+    RplMotd => 372,
+    RplEndOfInfo => 374,
+    RplMotdStart => 375,
+    RplEndOfMotd => 376,
+    RplYoureOper => 381,
+    RplRehashing => 382,
+    RplYoureService => 383,
+    RplTime => 391,
+    RplUsersStart => 392,
+    RplUsers => 393,
+    RplEndOfUsers => 394,
+    RplNoUsers => 395,
+    RplHostHidden => 396,
+    ErrNoSuchNick => 401,
+    ErrNoSuchServer => 402,
+    ErrNoSuchChannel => 403,
+    ErrCannotSendToChan => 404,
+    ErrTooManyChannels => 405,
+    ErrWasNoSuchNick => 406,
+    ErrTooManyTargets => 407,
+    ErrNoSuchService => 408,
+    ErrNoOrigin => 409,
+    ErrNoRecipient => 411,
+    ErrNoTextToSend => 412,
+    ErrNoTopLevel => 413,
+    ErrWildTopLevel => 414,
+    ErrBadMask => 415,
+    ErrInputTooLong => 417,
+    ErrUnknownCommand => 421,
+    ErrNoMotd => 422,
+    ErrNoAdminInfo => 423,
+    ErrFileError => 424,
+    ErrNoNicknameGiven => 431,
+    ErrErroneousNickname => 432,
+    ErrNicknameInUse => 433,
+    ErrNickCollision => 436,
+    ErrUnavailResource => 437,
+    ErrUserNotInChannel => 441,
+    ErrNotOnChannel => 442,
+    ErrUserOnChannel => 443,
+    ErrNoLogin => 444,
+    ErrSummonDisabled => 445,
+    ErrUsersDisabled => 446,
+    ErrNotRegistered => 451,
+    ErrNeedMoreParams => 461,
+    ErrAlreadyRegistred => 462,
+    ErrNoPermForHost => 463,
+    ErrPasswdMismatch => 464,
+    ErrYoureBannedCreep => 465,
+    ErrYouWillBeBanned => 466,
+    ErrKeySet => 467,
+    ErrChannelIsFull => 471,
+    ErrUnknownMode => 472,
+    ErrInviteOnlyChan => 473,
+    ErrBannedFromChan => 474,
+    ErrBadChannelKey => 475,
+    ErrBadChanMask => 476,
+    ErrNoChanModes => 477,
+    ErrBanListFull => 478,
+    ErrNoPrivileges => 481,
+    ErrChanOPrivsNeeded => 482,
+    ErrCantKillServer => 483,
+    ErrRestricted => 484,
+    ErrUniqOpPrivsNeeded => 485,
+    ErrNoOperHost => 491,
+    ErrUModeUnknownFlag => 501,
+    ErrUsersDontMatch => 502,
+    RplLogOn => 600,
+    RplLogOff => 601,
+    RplWatchOff => 602,
+    RplWatchStat => 603,
+    RplNowOn => 604,
+    RplNowOff => 605,
+    RplWatchList => 606,
+    RplEndOfWatchList => 607,
+    RplClearWatch => 608,
+    RplNowIsAway => 609,
+    RplMonOnline => 730,
+    RplMonOffline => 731,
+    RplMonList => 732,
+    RplEndOfMonList => 733,
+    ErrMonListIsFull => 734,
+    RplWhoisKeyValue => 760,
+    RplKeyValue => 761,
+    RplKeyNotSet => 766,
+    RplMetadataSubOk => 770,
+    RplMetadataUnsubOk => 771,
+    RplMetadataSubs => 772,
+    RplMetadataSyncLater => 774,
+    RplLoggedIn => 900,
+    RplLoggedOut => 901,
+    ErrNickLocked => 902,
+    RplSaslSuccess => 903,
+    ErrSaslFail => 904,
+    ErrSaslTooLong => 905,
+    ErrSaslAborted => 906,
+    ErrSaslAlready => 907,
+    RplSaslMechs => 908,
+}
+
+impl Numeric {
+    /// Return true for the error replies, 400 to 599
+    pub fn is_error(self) -> bool {
+        (400..600).contains(&self.to_u16())
+    }
+}
+
+impl From<u16> for Numeric {
+    fn from(code: u16) -> Self {
+        Numeric::from_u16(code)
+    }
+}
+
+impl<'a> Command<'a> {
+    /// Return the numeric reply if the command is a numeric
+    pub fn numeric(&self) -> Option<Numeric> {
+        match self {
+            Command::Numeric(code) => Some(Numeric::from_u16(*code)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numeric_roundtrip() {
+        for code in 0..1000 {
+            assert_eq!(Numeric::from_u16(code).to_u16(), code);
+        }
+        assert_eq!(Numeric::from_u16(1), Numeric::RplWelcome);
+        assert_eq!(Numeric::from_u16(433), Numeric::ErrNicknameInUse);
+        assert_eq!(Numeric::from_u16(999), Numeric::Unknown(999));
+    }
+
+    #[test]
+    fn numeric_is_error() {
+        assert!(!Numeric::RplWelcome.is_error());
+        assert!(Numeric::ErrNicknameInUse.is_error());
+        assert!(Numeric::Unknown(599).is_error());
+        assert!(!Numeric::Unknown(600).is_error());
+    }
+
+    #[test]
+    fn command_numeric() {
+        assert_eq!(Command::Numeric(1).numeric(), Some(Numeric::RplWelcome));
+        assert_eq!(Command::Privmsg.numeric(), None);
+    }
+}
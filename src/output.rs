@@ -0,0 +1,64 @@
+//! The destinations of the serialization, so the messages are serialized
+//! the same way in a `Vec`, a fixed buffer or a `std::io::Write`
+
+use crate::error::EncodeError;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+pub(crate) trait Output {
+    type Error;
+
+    /// Append all the bytes or fail
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl Output for Vec<u8> {
+    type Error = Infallible;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// A fixed buffer filled from its start
+pub(crate) struct Buffer<'b> {
+    pub buffer: &'b mut [u8],
+    pub len: usize,
+}
+
+impl<'b> Output for Buffer<'b> {
+    type Error = EncodeError;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let end = self.len + bytes.len();
+        let dest = self
+            .buffer
+            .get_mut(self.len..end)
+            .ok_or(EncodeError::BufferTooSmall)?;
+        dest.copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) struct Writer<'w, W>(pub &'w mut W);
+
+#[cfg(feature = "std")]
+impl<'w, W: std::io::Write> Output for Writer<'w, W> {
+    type Error = std::io::Error;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.write_all(bytes)
+    }
+}
+
+/// Serialize in a new `Vec`
+pub(crate) fn to_vec(encode: impl FnOnce(&mut Vec<u8>) -> Result<(), Infallible>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    match encode(&mut bytes) {
+        Ok(()) => bytes,
+        Err(never) => match never {},
+    }
+}
@@ -0,0 +1,242 @@
+use crate::command::Command;
+use crate::config::ParserConfig;
+use crate::error::ParseError;
+use crate::message::{parse_message_with, Message};
+use crate::params::Params;
+use crate::prefix::Prefix;
+use crate::tags::Tags;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+/// A message owning its data, created by `Message::into_owned`
+/// All the parts are stored in a single buffer, `as_message` borrows them
+/// back without allocating anything but the lists of tags and params.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedMessage {
+    buffer: Vec<u8>,
+    /// The keys and the unescaped values
+    tags: Vec<(Range<usize>, Range<usize>)>,
+    prefix: Option<OwnedPrefix>,
+    command: OwnedCommand,
+    params: Vec<Range<usize>>,
+    trailing: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OwnedPrefix {
+    Server(Range<usize>),
+    User {
+        nick: Range<usize>,
+        user: Option<Range<usize>>,
+        host: Option<Range<usize>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OwnedCommand {
+    Numeric(u16),
+    /// Given back to `Command::from_word`
+    Word(Range<usize>),
+}
+
+impl<'a> Message<'a> {
+    /// Copy the message out of the input so the input can be reused
+    pub fn into_owned(self) -> OwnedMessage {
+        let mut buffer = Vec::new();
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| (push(&mut buffer, tag.key), push(&mut buffer, &tag.value)))
+            .collect();
+        let prefix = self.prefix.map(|prefix| match prefix {
+            Prefix::Server(server) => OwnedPrefix::Server(push(&mut buffer, server)),
+            Prefix::User { nick, user, host } => OwnedPrefix::User {
+                nick: push(&mut buffer, nick),
+                user: user.map(|user| push(&mut buffer, user)),
+                host: host.map(|host| push(&mut buffer, host)),
+            },
+        });
+        let command = match self.command {
+            Command::Numeric(code) => OwnedCommand::Numeric(code),
+            Command::Unknown(word) => OwnedCommand::Word(push(&mut buffer, word)),
+            known => OwnedCommand::Word(push(&mut buffer, known.as_str().as_bytes())),
+        };
+        let params = self
+            .params
+            .iter()
+            .map(|param| push(&mut buffer, param))
+            .collect();
+        OwnedMessage {
+            buffer,
+            tags,
+            prefix,
+            command,
+            params,
+            trailing: self.params.has_trailing(),
+        }
+    }
+}
+
+impl OwnedMessage {
+    /// Parse exactly one message with the default configuration and copy it,
+    /// see `parse_message_with`
+    pub fn parse(input: &[u8]) -> Result<Self, ParseError> {
+        Self::parse_with(input, &ParserConfig::default())
+    }
+
+    /// Parse exactly one message and copy it, see `parse_message_with`
+    pub fn parse_with(input: &[u8], config: &ParserConfig) -> Result<Self, ParseError> {
+        parse_message_with(input, config).map(Message::into_owned)
+    }
+
+    /// Return the unescaped value of the tag `key`
+    /// If the key was sent multiple times the last value is returned.
+    pub fn tag(&self, key: &str) -> Option<&[u8]> {
+        self.tags
+            .iter()
+            .rev()
+            .find(|(k, _)| &self.buffer[k.clone()] == key.as_bytes())
+            .map(|(_, value)| &self.buffer[value.clone()])
+    }
+
+    pub fn prefix(&self) -> Option<Prefix<'_>> {
+        let slice = |range: &Range<usize>| &self.buffer[range.clone()];
+        self.prefix.as_ref().map(|prefix| match prefix {
+            OwnedPrefix::Server(server) => Prefix::Server(slice(server)),
+            OwnedPrefix::User { nick, user, host } => Prefix::User {
+                nick: slice(nick),
+                user: user.as_ref().map(slice),
+                host: host.as_ref().map(slice),
+            },
+        })
+    }
+
+    pub fn command(&self) -> Command<'_> {
+        match &self.command {
+            OwnedCommand::Numeric(code) => Command::Numeric(*code),
+            OwnedCommand::Word(word) => Command::from_word(&self.buffer[word.clone()]),
+        }
+    }
+
+    /// Return the parameter at `index`, the trailing one included
+    pub fn param(&self, index: usize) -> Option<&[u8]> {
+        let range = self.params.get(index)?;
+        Some(&self.buffer[range.clone()])
+    }
+
+    /// Return the number of parameters, the trailing one included
+    pub fn params_len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Same as `Message::to_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_message().to_bytes()
+    }
+
+    /// Borrow the message back
+    pub fn as_message(&self) -> Message<'_> {
+        let slice = |range: &Range<usize>| &self.buffer[range.clone()];
+        let mut tags = Tags::new();
+        for (key, value) in &self.tags {
+            tags.push(slice(key), slice(value));
+        }
+        let mut params = Params::new();
+        for (i, param) in self.params.iter().enumerate() {
+            if self.trailing && i + 1 == self.params.len() {
+                params.push_trailing(slice(param));
+            } else {
+                params.push(slice(param));
+            }
+        }
+        Message {
+            tags,
+            prefix: self.prefix(),
+            command: self.command(),
+            params,
+        }
+    }
+}
+
+impl<'a> From<Message<'a>> for OwnedMessage {
+    fn from(message: Message<'a>) -> Self {
+        message.into_owned()
+    }
+}
+
+impl<'a> From<&'a OwnedMessage> for Message<'a> {
+    fn from(message: &'a OwnedMessage) -> Self {
+        message.as_message()
+    }
+}
+
+/// Same as the `Display` of `Message`
+impl fmt::Display for OwnedMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_message().fmt(f)
+    }
+}
+
+/// Append `bytes` to the buffer and return where they are
+fn push(buffer: &mut Vec<u8>, bytes: &[u8]) -> Range<usize> {
+    let start = buffer.len();
+    buffer.extend_from_slice(bytes);
+    start..buffer.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn owned_roundtrip() {
+        let lines: &[&[u8]] = &[
+            b"@a=b\\sc;k :nick!u@h PRIVMSG #chan :hello world\r\n",
+            b":irc.example.com 001 nick :Welcome\r\n",
+            b"FOO bar baz\r\n",
+            b"PING\r\n",
+        ];
+        for line in lines {
+            let input = line.to_vec();
+            let message = parse_message(&input).unwrap();
+            let owned = message.clone().into_owned();
+            assert_eq!(owned.as_message(), message);
+        }
+    }
+
+    #[test]
+    fn owned_outlives_input() {
+        let owned = {
+            let input = b":nick PRIVMSG #chan :hi\r\n".to_vec();
+            parse_message(&input).unwrap().into_owned()
+        };
+        let message = owned.as_message();
+        assert_eq!(message.command, Command::Privmsg);
+        assert_eq!(message.params.trailing(), Some(&b"hi"[..]));
+    }
+
+    #[test]
+    fn owned_accessors() {
+        let owned =
+            OwnedMessage::parse(b"@id=1;id=2 :irc.example.com FOO #chan :hi there\r\n").unwrap();
+        assert_eq!(owned.tag("id"), Some(&b"2"[..]));
+        assert_eq!(owned.tag("x"), None);
+        assert_eq!(owned.command(), Command::Unknown(b"FOO"));
+        assert_eq!(owned.prefix(), Some(Prefix::Server(b"irc.example.com")));
+        assert_eq!(owned.params_len(), 2);
+        assert_eq!(owned.param(1), Some(&b"hi there"[..]));
+        assert_eq!(owned.param(2), None);
+        assert_eq!(
+            owned.to_bytes(),
+            b"@id=1;id=2 :irc.example.com FOO #chan :hi there\r\n".to_vec()
+        );
+        assert_eq!(
+            owned.to_string(),
+            "@id=1;id=2 :irc.example.com FOO #chan :hi there"
+        );
+        let message = Message::from(&owned);
+        assert_eq!(OwnedMessage::from(message), owned);
+    }
+}
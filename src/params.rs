@@ -0,0 +1,305 @@
+use crate::config::{ParserConfig, Rfc};
+use crate::parser::{is_nonwhite, nonwhite, space};
+use crate::scan::find_any;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+use nom::bytes::complete::{tag, take_while};
+use nom::combinator::{opt, recognize, verify};
+use nom::sequence::preceded;
+use nom::{Err, IResult};
+
+/// The number of parameters stored without allocating, enough for most
+/// messages: `PRIVMSG #chan :text`, `PING :token`, `MODE #chan +o nick`
+const INLINE_PARAMS: usize = 4;
+
+/// The parameters of a message
+/// Derefs to the list of all the parameters, the trailing one included.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Params<'a> {
+    list: List<'a>,
+    trailing: bool,
+}
+
+impl<'a> fmt::Debug for Params<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Params")
+            .field("list", &self.list.as_slice())
+            .field("trailing", &self.trailing)
+            .finish()
+    }
+}
+
+/// The first parameters are stored inline, the list only moves to the heap
+/// when it grows past `INLINE_PARAMS`
+#[derive(Clone)]
+enum List<'a> {
+    Inline {
+        items: [&'a [u8]; INLINE_PARAMS],
+        len: usize,
+    },
+    Heap(Vec<&'a [u8]>),
+}
+
+impl<'a> List<'a> {
+    fn push(&mut self, param: &'a [u8]) {
+        match self {
+            List::Inline { items, len } if *len < INLINE_PARAMS => {
+                items[*len] = param;
+                *len += 1;
+            }
+            List::Inline { items, len } => {
+                let mut list = Vec::with_capacity(INLINE_PARAMS * 2);
+                list.extend_from_slice(&items[..*len]);
+                list.push(param);
+                *self = List::Heap(list);
+            }
+            List::Heap(list) => list.push(param),
+        }
+    }
+
+    fn as_slice(&self) -> &[&'a [u8]] {
+        match self {
+            List::Inline { items, len } => &items[..*len],
+            List::Heap(list) => list,
+        }
+    }
+}
+
+impl<'a> Default for List<'a> {
+    fn default() -> Self {
+        List::Inline {
+            items: [&[]; INLINE_PARAMS],
+            len: 0,
+        }
+    }
+}
+
+/// Two lists are equal if they hold the same parameters, wherever they are
+impl<'a> PartialEq for List<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<'a> Eq for List<'a> {}
+
+impl<'a> Params<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a middle parameter
+    pub fn push(&mut self, param: &'a [u8]) {
+        self.list.push(param);
+    }
+
+    /// Add the trailing parameter, it must be the last one
+    pub fn push_trailing(&mut self, param: &'a [u8]) {
+        self.list.push(param);
+        self.trailing = true;
+    }
+
+    /// Return true if the last parameter was introduced by a ':'
+    pub fn has_trailing(&self) -> bool {
+        self.trailing
+    }
+
+    /// Return the trailing parameter if there is one
+    /// An empty trailing (`PRIVMSG #chan :`) is returned as `Some(b"")`.
+    pub fn trailing(&self) -> Option<&'a [u8]> {
+        if self.trailing {
+            self.list.as_slice().last().copied()
+        } else {
+            None
+        }
+    }
+
+    /// Return the middle parameters, without the trailing one
+    pub fn middles(&self) -> &[&'a [u8]] {
+        let list = self.list.as_slice();
+        if self.trailing {
+            &list[..list.len() - 1]
+        } else {
+            list
+        }
+    }
+}
+
+impl<'a> Deref for Params<'a> {
+    type Target = [&'a [u8]];
+
+    fn deref(&self) -> &Self::Target {
+        self.list.as_slice()
+    }
+}
+
+impl<'a> From<Vec<&'a [u8]>> for Params<'a> {
+    fn from(list: Vec<&'a [u8]>) -> Self {
+        Params {
+            list: List::Heap(list),
+            trailing: false,
+        }
+    }
+}
+
+/// <params>   ::= <SPACE> [ ':' <trailing> | <middle> <params> ]
+/// Parse as many parameters as possible, it never fails on a
+/// complete input. Zero parameter is valid.
+pub fn params(input: &[u8]) -> IResult<&[u8], Params<'_>> {
+    params_max(input, None)
+}
+
+/// Same as `params` but follows the grammar of the configured RFC
+/// <params>   ::= *14( <SPACE> <middle> ) [ <SPACE> ':' <trailing> ]
+///              | 14( <SPACE> <middle> ) [ <SPACE> [ ':' ] <trailing> ]
+/// In RFC 2812 mode everything after the 14th middle is the trailing
+/// parameter, even without ':'.
+pub fn params_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Params<'_>> {
+    let max_middles = match config.rfc {
+        Rfc::Rfc1459 => None,
+        Rfc::Rfc2812 => Some(14),
+    };
+    move |input: &[u8]| params_max(input, max_middles)
+}
+
+fn params_max(mut input: &[u8], max_middles: Option<usize>) -> IResult<&[u8], Params<'_>> {
+    let mut params = Params::new();
+    loop {
+        input = match space(input) {
+            Ok((input, _)) => input,
+            Err(Err::Error(_)) => return Ok((input, params)),
+            Err(e) => return Err(e),
+        };
+        if Some(params.len()) == max_middles {
+            let (input, _) = opt(tag(":"))(input)?;
+            let (input, trailing) = trailing(input)?;
+            params.push_trailing(trailing);
+            return Ok((input, params));
+        }
+        if let Ok((input, trailing)) = preceded(tag(":"), trailing)(input) {
+            params.push_trailing(trailing);
+            return Ok((input, params));
+        }
+        match middle(input) {
+            Ok((rest, middle)) => {
+                params.push(middle);
+                input = rest;
+            }
+            Err(Err::Error(_)) => return Ok((input, params)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// <middle>   ::= <Any *non-empty* sequence of octets not including SPACE
+///                or NUL or CR or LF, the first of which may not be ':'>
+pub fn middle(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(preceded(
+        verify(nonwhite, |c: &[u8]| c[0] != b':'),
+        take_while(is_nonwhite),
+    ))(input)
+}
+
+/// <trailing> ::= <Any, possibly *empty*, sequence of octets not including
+///                NUL or CR or LF>
+pub fn trailing(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let len = find_any(0x00, b'\r', b'\n', input).unwrap_or(input.len());
+    Ok((&input[len..], &input[..len]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+
+    #[test]
+    fn params_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(params(empty), Ok((empty, Params::new())));
+        let crlf: &[u8] = b"\r\n";
+        assert_eq!(params(crlf), Ok((crlf, Params::new())));
+    }
+
+    #[test]
+    fn params_middles() {
+        let p: &[u8] = b" #chan +o  nick \r\n";
+        let (rest, p) = params(p).unwrap();
+        assert_eq!(rest, &b"\r\n"[..]);
+        assert_eq!(&p[..], &[&b"#chan"[..], &b"+o"[..], &b"nick"[..]]);
+        assert!(!p.has_trailing());
+        assert_eq!(p.trailing(), None);
+    }
+
+    #[test]
+    fn params_spill() {
+        let p: &[u8] = b" a b c d e :f g\r\n";
+        let (_, p) = params(p).unwrap();
+        assert_eq!(p.len(), 6);
+        assert_eq!(p.middles().len(), 5);
+        assert_eq!(p.trailing(), Some(&b"f g"[..]));
+        let list: Vec<&[u8]> = vec![b"a", b"b", b"c", b"d", b"e", b"f g"];
+        let mut expected = Params::from(list[..5].to_vec());
+        expected.push_trailing(list[5]);
+        assert_eq!(p, expected);
+        // the storage doesn't matter for the comparison
+        assert_eq!(Params::from(vec![&b"a"[..]]), {
+            let mut inline = Params::new();
+            inline.push(b"a");
+            inline
+        });
+    }
+
+    #[test]
+    fn params_trailing() {
+        let p: &[u8] = b" #chan :hello :world\r\n";
+        let (rest, p) = params(p).unwrap();
+        assert_eq!(rest, &b"\r\n"[..]);
+        assert_eq!(&p[..], &[&b"#chan"[..], &b"hello :world"[..]]);
+        assert!(p.has_trailing());
+        assert_eq!(p.trailing(), Some(&b"hello :world"[..]));
+        assert_eq!(p.middles(), &[&b"#chan"[..]]);
+    }
+
+    #[test]
+    fn params_empty_trailing() {
+        let p: &[u8] = b" #chan :\r\n";
+        let (_, p) = params(p).unwrap();
+        assert_eq!(&p[..], &[&b"#chan"[..], &b""[..]]);
+        assert_eq!(p.trailing(), Some(&b""[..]));
+    }
+
+    #[test]
+    fn params_rfc2812_limit() {
+        let p: &[u8] = b" 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16\r\n";
+        let (_, all) = params(p).unwrap();
+        assert_eq!(all.len(), 16);
+        let config = ParserConfig {
+            rfc: Rfc::Rfc2812,
+            ..ParserConfig::default()
+        };
+        let (rest, limited) = params_with(&config)(p).unwrap();
+        assert_eq!(rest, &b"\r\n"[..]);
+        assert_eq!(limited.len(), 15);
+        assert_eq!(limited.trailing(), Some(&b"15 16"[..]));
+        let p: &[u8] = b" 1 2 3 4 5 6 7 8 9 10 11 12 13 14 :15 16\r\n";
+        let (_, limited) = params_with(&config)(p).unwrap();
+        assert_eq!(limited.trailing(), Some(&b"15 16"[..]));
+    }
+
+    #[test]
+    fn middle_colon() {
+        let m: &[u8] = b":abc";
+        assert_eq!(middle(m), Err(Err::Error((m, ErrorKind::Verify))));
+        let m: &[u8] = b"a:bc d";
+        assert_eq!(middle(m), Ok((&b" d"[..], &b"a:bc"[..])));
+    }
+
+    #[test]
+    fn trailing_stops_at_crlf() {
+        let t: &[u8] = b"a b\0c";
+        assert_eq!(trailing(t), Ok((&b"\0c"[..], &b"a b"[..])));
+        let t: &[u8] = b"\r\n";
+        assert_eq!(trailing(t), Ok((t, &b""[..])));
+    }
+}
@@ -1,25 +1,37 @@
+//! The building blocks of the grammar
+//! They are generic over the nom error so they can be combined with
+//! `VerboseError` or any other `nom::error::ParseError`, the errors described
+//! below are the ones of the default `(input, ErrorKind)` tuple.
+
+use nom::error::{ErrorKind, ParseError};
 use nom::AsChar;
-use nom::InputTakeAtPosition;
-use nom::{error::ErrorKind, Err, IResult, Needed};
+use nom::{Err, IResult};
 
 /// <SPACE>    ::= ' ' { ' ' }
 /// One space and then as much space as you want
 /// return Ok( (nextBytes, eatenSpaces) )
 /// Or if there is no space at the beginning Err( (input, TakeWhile1) )
-pub fn space(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn space<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     nom::bytes::complete::take_while1(|item| item == b' ')(input)
 }
 
 /// <crlf>     ::= CR LF
 /// Recognizes the string "\r\n".
 /// Return an error if there is not enough data: Err( (input, Crlf) )
-pub fn crlf(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn crlf<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     nom::character::complete::crlf(input)
 }
 
-pub fn one_char(input: &[u8]) -> IResult<&[u8], &[u8]> {
+/// <crlf>     ::= CR LF | LF
+/// Recognizes the string "\r\n" or a lone "\n" as sent by some servers.
+/// Return an error if there is neither: Err( (input, Tag) )
+pub fn crlf_or_lf<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    nom::branch::alt((crlf, nom::bytes::complete::tag("\n")))(input)
+}
+
+pub fn one_char<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     if input.is_empty() {
-        return Err(Err::Error((input, ErrorKind::Char)));
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
     }
     Ok((&input[1..], &input[0..1]))
 }
@@ -28,9 +40,9 @@ pub fn one_char(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// Extract the first char of the input
 /// Return an error if there is not enough data or if it’s not a letter:
 /// Err( (input, Char) )
-pub fn letter(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn letter<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     if input.is_empty() || !input[0].is_alpha() {
-        return Err(Err::Error((input, ErrorKind::Char)));
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
     }
     Ok((&input[1..], &input[0..1]))
 }
@@ -39,9 +51,9 @@ pub fn letter(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// Extract the first char of the input
 /// Return an error if there is not enough data or if it’s not a number:
 /// Err( (input, Char) )
-pub fn number(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn number<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     if input.is_empty() || !input[0].is_dec_digit() {
-        return Err(Err::Error((input, ErrorKind::Char)));
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
     }
     Ok((&input[1..], &input[0..1]))
 }
@@ -50,7 +62,7 @@ pub fn number(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// Extract the first char of the input
 /// Return an error if there is not enough data or if it’s not a special char:
 /// Err( (input, Char) )
-pub fn special(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn special<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     if input.is_empty()
         || !(input[0] == b'-'
             || input[0] == b'['
@@ -61,7 +73,21 @@ pub fn special(input: &[u8]) -> IResult<&[u8], &[u8]> {
             || input[0] == b'{'
             || input[0] == b'}')
     {
-        return Err(Err::Error((input, ErrorKind::Char)));
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
+    }
+    Ok((&input[1..], &input[0..1]))
+}
+
+/// <special>    ::= '[' | ']' | '\' | '`' | '_' | '^' | '{' | '|' | '}'
+/// The RFC 2812 version of `special`: '-' is gone, '_' and '|' are added.
+/// Return an error if there is not enough data or if it’s not a special char:
+/// Err( (input, Char) )
+pub fn special_rfc2812<'a, E: ParseError<&'a [u8]>>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], &'a [u8], E> {
+    if input.is_empty() || !(0x5B..=0x60).contains(&input[0]) && !(0x7B..=0x7D).contains(&input[0])
+    {
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
     }
     Ok((&input[1..], &input[0..1]))
 }
@@ -71,21 +97,134 @@ pub fn special(input: &[u8]) -> IResult<&[u8], &[u8]> {
 /// Extract the first char of the input
 /// Return an error if there is not enough data or if it’s not a nonwhite char:
 /// Err( (input, Char) )
-pub fn nonwhite(input: &[u8]) -> IResult<&[u8], &[u8]> {
+pub fn nonwhite<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
     if input.is_empty()
         || input[0] == b' '
         || input[0] == 0x00 // NUL
         || input[0] == b'\r'
         || input[0] == b'\n'
     {
-        return Err(Err::Error((input, ErrorKind::Char)));
+        return Err(Err::Error(E::from_error_kind(input, ErrorKind::Char)));
     }
     Ok((&input[1..], &input[0..1]))
 }
 
+/// <letter> { <letter> }
+/// Bulk version of `letter`, extract all the letters at once
+/// Return an error if the input does not start with a letter:
+/// Err( (input, TakeWhile1) )
+pub fn letters<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    nom::bytes::complete::take_while1(|c: u8| c.is_ascii_alphabetic())(input)
+}
+
+/// <number> { <number> }
+/// Bulk version of `number`, extract all the digits at once
+/// Return an error if the input does not start with a digit:
+/// Err( (input, TakeWhile1) )
+pub fn numbers<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    nom::bytes::complete::take_while1(|c: u8| c.is_ascii_digit())(input)
+}
+
+/// <nonwhite> { <nonwhite> }
+/// Bulk version of `nonwhite`, extract everything up to the next space, NUL,
+/// CR or LF
+/// Return an error if the input does not start with a nonwhite char:
+/// Err( (input, TakeWhile1) )
+pub fn nonwhites<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    nom::bytes::complete::take_while1(is_nonwhite)(input)
+}
+
+/// Return true if `c` matches `nonwhite`
+pub fn is_nonwhite(c: u8) -> bool {
+    !matches!(c, b' ' | 0x00 | b'\r' | b'\n')
+}
+
+/// <nick>       ::= <letter> { <letter> | <number> | <special> }
+/// Extract a nick of any length in a single scan
+/// Return an error if the input does not start with a letter:
+/// Err( (input, Char) )
+pub fn take_nick<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    let (_, _) = letter(input)?;
+    let len = input
+        .iter()
+        .take_while(|&&c| c.is_ascii_alphanumeric() || b"-[]\\`^{}".contains(&c))
+        .count();
+    Ok((&input[len..], &input[..len]))
+}
+
+/// <nick>       ::= <letter> { <letter> | <number> | <special> }
+/// Extract a nick of at most 9 chars as required by the RFC
+/// Return an error if the input does not start with a letter:
+/// Err( (input, Char) )
+/// Or if the nick is longer than 9 chars: Err( (input, TooLarge) )
+pub fn nick<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    nick_max(9)(input)
+}
+
+/// Same as `nick` but allow nicks of up to `max_len` chars since a lot of
+/// networks accept longer nicks than the RFC
+pub fn nick_max<'a, E: ParseError<&'a [u8]>>(
+    max_len: usize,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    move |input: &'a [u8]| {
+        let (rest, nick) = take_nick(input)?;
+        if nick.len() > max_len {
+            return Err(Err::Error(E::from_error_kind(input, ErrorKind::TooLarge)));
+        }
+        Ok((rest, nick))
+    }
+}
+
+/// <user>       ::= <nonwhite> { <nonwhite> }
+/// Extract a username, it stops on the first '@' since it separates the user
+/// from the host in a prefix
+/// Return an error if the input does not start with a nonwhite char other
+/// than '@': Err( (input, Char) )
+pub fn user<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    let len = input
+        .iter()
+        .take_while(|&&c| is_nonwhite(c) && c != b'@')
+        .count();
+    match input.first() {
+        _ if len > 0 => Ok((&input[len..], &input[..len])),
+        Some(b'@') => Err(Err::Error(E::from_error_kind(input, ErrorKind::Verify))),
+        _ => Err(Err::Error(E::from_error_kind(input, ErrorKind::Char))),
+    }
+}
+
+/// <nick>       ::= ( <letter> | <special> ) { <letter> | <number> | <special> | '-' }
+/// The RFC 2812 version of `nick_max`, using `special_rfc2812`
+/// Return an error if the input does not start with a letter or a special
+/// char: Err( (input, Char) )
+/// Or if the nick is longer than `max_len`: Err( (input, TooLarge) )
+pub fn nick_rfc2812_max<'a, E: ParseError<&'a [u8]>>(
+    max_len: usize,
+) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], &'a [u8], E> {
+    move |input: &'a [u8]| {
+        let (_, _) = nom::branch::alt((letter, special_rfc2812))(input)?;
+        let len = input
+            .iter()
+            .take_while(|&&c| {
+                c.is_ascii_alphanumeric()
+                    || (0x5B..=0x60).contains(&c)
+                    || (0x7B..=0x7D).contains(&c)
+                    || c == b'-'
+            })
+            .count();
+        let (rest, nick) = (&input[len..], &input[..len]);
+        if nick.len() > max_len {
+            return Err(Err::Error(E::from_error_kind(input, ErrorKind::TooLarge)));
+        }
+        Ok((rest, nick))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use nom::error::{context, VerboseError, VerboseErrorKind};
+
+    type Error<'a> = (&'a [u8], ErrorKind);
 
     #[test]
     fn space_empty() {
@@ -105,13 +244,13 @@ mod tests {
     #[test]
     fn space_spaces() {
         let s: &[u8] = b"    ";
-        assert_eq!(space(s), Ok((&b""[..], s)));
+        assert_eq!(space::<Error>(s), Ok((&b""[..], s)));
     }
 
     #[test]
     fn space_spaces_and_chars() {
         let s: &[u8] = b"    abcd";
-        assert_eq!(space(s), Ok((&b"abcd"[..], &b"    "[..])));
+        assert_eq!(space::<Error>(s), Ok((&b"abcd"[..], &b"    "[..])));
     }
 
     #[test]
@@ -123,13 +262,29 @@ mod tests {
     #[test]
     fn crlf_alone() {
         let c: &[u8] = b"\r\n";
-        assert_eq!(crlf(c), Ok((&b""[..], c)));
+        assert_eq!(crlf::<Error>(c), Ok((&b""[..], c)));
     }
 
     #[test]
     fn crlf_with_chars() {
         let c: &[u8] = b"\r\nabcd";
-        assert_eq!(crlf(c), Ok((&b"abcd"[..], &b"\r\n"[..])));
+        assert_eq!(crlf::<Error>(c), Ok((&b"abcd"[..], &b"\r\n"[..])));
+    }
+
+    #[test]
+    fn crlf_or_lf_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(crlf_or_lf(empty), Err(Err::Error((empty, ErrorKind::Tag))));
+    }
+
+    #[test]
+    fn crlf_or_lf_with_chars() {
+        let c: &[u8] = b"\r\nabcd";
+        assert_eq!(crlf_or_lf::<Error>(c), Ok((&b"abcd"[..], &b"\r\n"[..])));
+        let c: &[u8] = b"\nabcd";
+        assert_eq!(crlf_or_lf::<Error>(c), Ok((&b"abcd"[..], &b"\n"[..])));
+        let c: &[u8] = b"\rabcd";
+        assert_eq!(crlf_or_lf(c), Err(Err::Error((c, ErrorKind::Tag))));
     }
 
     #[test]
@@ -141,13 +296,13 @@ mod tests {
     #[test]
     fn one_char_alone() {
         let a: &[u8] = b"a";
-        assert_eq!(one_char(a), Ok((&b""[..], a)));
+        assert_eq!(one_char::<Error>(a), Ok((&b""[..], a)));
     }
 
     #[test]
     fn one_char_with_chars() {
         let a: &[u8] = b"ab1-";
-        assert_eq!(one_char(a), Ok((&b"b1-"[..], &b"a"[..])));
+        assert_eq!(one_char::<Error>(a), Ok((&b"b1-"[..], &b"a"[..])));
     }
 
     #[test]
@@ -159,13 +314,13 @@ mod tests {
     #[test]
     fn letter_alone() {
         let a: &[u8] = b"a";
-        assert_eq!(letter(a), Ok((&b""[..], a)));
+        assert_eq!(letter::<Error>(a), Ok((&b""[..], a)));
     }
 
     #[test]
     fn letter_with_num() {
         let a: &[u8] = b"ab1-";
-        assert_eq!(letter(a), Ok((&b"b1-"[..], &b"a"[..])));
+        assert_eq!(letter::<Error>(a), Ok((&b"b1-"[..], &b"a"[..])));
         let a: &[u8] = b"1";
         assert_eq!(letter(a), Err(Err::Error((a, ErrorKind::Char))));
     }
@@ -179,13 +334,13 @@ mod tests {
     #[test]
     fn number_alone() {
         let a: &[u8] = b"1";
-        assert_eq!(number(a), Ok((&b""[..], a)));
+        assert_eq!(number::<Error>(a), Ok((&b""[..], a)));
     }
 
     #[test]
     fn number_with_char() {
         let a: &[u8] = b"12a-";
-        assert_eq!(number(a), Ok((&b"2a-"[..], &b"1"[..])));
+        assert_eq!(number::<Error>(a), Ok((&b"2a-"[..], &b"1"[..])));
         let a: &[u8] = b"a";
         assert_eq!(number(a), Err(Err::Error((a, ErrorKind::Char))));
     }
@@ -199,17 +354,36 @@ mod tests {
     #[test]
     fn special_alone() {
         let a: &[u8] = b"-";
-        assert_eq!(special(a), Ok((&b""[..], a)));
+        assert_eq!(special::<Error>(a), Ok((&b""[..], a)));
     }
 
     #[test]
     fn special_with_char() {
         let a: &[u8] = b"[2a-";
-        assert_eq!(special(a), Ok((&b"2a-"[..], &b"["[..])));
+        assert_eq!(special::<Error>(a), Ok((&b"2a-"[..], &b"["[..])));
         let a: &[u8] = b"a";
         assert_eq!(special(a), Err(Err::Error((a, ErrorKind::Char))));
     }
 
+    #[test]
+    fn special_rfc2812_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(
+            special_rfc2812(empty),
+            Err(Err::Error((empty, ErrorKind::Char)))
+        );
+    }
+
+    #[test]
+    fn special_rfc2812_with_char() {
+        for c in b"[]\\`_^{|}" {
+            let a = [*c, b'a'];
+            assert_eq!(special_rfc2812::<Error>(&a), Ok((&b"a"[..], &a[..1])));
+        }
+        let a: &[u8] = b"-";
+        assert_eq!(special_rfc2812(a), Err(Err::Error((a, ErrorKind::Char))));
+    }
+
     #[test]
     fn nonwhite_empty() {
         let empty: &[u8] = b"";
@@ -219,14 +393,125 @@ mod tests {
     #[test]
     fn nonwhite_alone() {
         let a: &[u8] = b"a";
-        assert_eq!(nonwhite(a), Ok((&b""[..], a)));
+        assert_eq!(nonwhite::<Error>(a), Ok((&b""[..], a)));
     }
 
     #[test]
     fn nonwhite_with_char() {
         let a: &[u8] = b"\t2a-";
-        assert_eq!(nonwhite(a), Ok((&b"2a-"[..], &b"\t"[..])));
+        assert_eq!(nonwhite::<Error>(a), Ok((&b"2a-"[..], &b"\t"[..])));
         let a: &[u8] = b" ";
         assert_eq!(nonwhite(a), Err(Err::Error((a, ErrorKind::Char))));
     }
+
+    #[test]
+    fn nick_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(nick(empty), Err(Err::Error((empty, ErrorKind::Char))));
+    }
+
+    #[test]
+    fn nick_alone() {
+        let n: &[u8] = b"a";
+        assert_eq!(nick::<Error>(n), Ok((&b""[..], n)));
+        let n: &[u8] = b"a1[-]`^{}";
+        assert_eq!(nick::<Error>(n), Ok((&b""[..], n)));
+    }
+
+    #[test]
+    fn nick_with_char() {
+        let n: &[u8] = b"nick!user@host";
+        assert_eq!(nick::<Error>(n), Ok((&b"!user@host"[..], &b"nick"[..])));
+        let n: &[u8] = b"1nick";
+        assert_eq!(nick(n), Err(Err::Error((n, ErrorKind::Char))));
+        let n: &[u8] = b"[nick]";
+        assert_eq!(nick(n), Err(Err::Error((n, ErrorKind::Char))));
+    }
+
+    #[test]
+    fn nick_too_long() {
+        let n: &[u8] = b"abcdefghij";
+        assert_eq!(nick(n), Err(Err::Error((n, ErrorKind::TooLarge))));
+        assert_eq!(nick_max::<Error>(30)(n), Ok((&b""[..], n)));
+        assert_eq!(nick_max(3)(n), Err(Err::Error((n, ErrorKind::TooLarge))));
+    }
+
+    #[test]
+    fn user_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(user(empty), Err(Err::Error((empty, ErrorKind::Char))));
+    }
+
+    #[test]
+    fn user_alone() {
+        let u: &[u8] = b"~user";
+        assert_eq!(user::<Error>(u), Ok((&b""[..], u)));
+    }
+
+    #[test]
+    fn user_with_char() {
+        let u: &[u8] = b"~user@host";
+        assert_eq!(user::<Error>(u), Ok((&b"@host"[..], &b"~user"[..])));
+        let u: &[u8] = b"user 0 * :Real name";
+        assert_eq!(
+            user::<Error>(u),
+            Ok((&b" 0 * :Real name"[..], &b"user"[..]))
+        );
+        let u: &[u8] = b"@host";
+        assert_eq!(user(u), Err(Err::Error((u, ErrorKind::Verify))));
+    }
+
+    #[test]
+    fn bulk_parsers() {
+        let a: &[u8] = b"abc1 d";
+        assert_eq!(letters::<Error>(a), Ok((&b"1 d"[..], &b"abc"[..])));
+        assert_eq!(numbers(a), Err(Err::Error((a, ErrorKind::TakeWhile1))));
+        assert_eq!(nonwhites::<Error>(a), Ok((&b" d"[..], &b"abc1"[..])));
+        let n: &[u8] = b"12ab\r\n";
+        assert_eq!(numbers::<Error>(n), Ok((&b"ab\r\n"[..], &b"12"[..])));
+        assert_eq!(nonwhites::<Error>(n), Ok((&b"\r\n"[..], &b"12ab"[..])));
+    }
+
+    #[test]
+    fn take_nick_scan() {
+        let n: &[u8] = b"n1ck[away]-^!user@host";
+        assert_eq!(
+            take_nick::<Error>(n),
+            Ok((&b"!user@host"[..], &b"n1ck[away]-^"[..]))
+        );
+        let n: &[u8] = b"1nick";
+        assert_eq!(take_nick(n), Err(Err::Error((n, ErrorKind::Char))));
+    }
+
+    #[test]
+    fn nick_rfc2812() {
+        let n: &[u8] = b"_nick|away-1 ";
+        assert_eq!(
+            nick_rfc2812_max::<Error>(20)(n),
+            Ok((&b" "[..], &b"_nick|away-1"[..]))
+        );
+        let n: &[u8] = b"-nick";
+        assert_eq!(
+            nick_rfc2812_max(9)(n),
+            Err(Err::Error((n, ErrorKind::Char)))
+        );
+        let n: &[u8] = b"_nick|away-1";
+        assert_eq!(
+            nick_rfc2812_max(9)(n),
+            Err(Err::Error((n, ErrorKind::TooLarge)))
+        );
+    }
+
+    #[test]
+    fn verbose_error() {
+        let n: &[u8] = b"1nick";
+        let error = VerboseError {
+            errors: vec![
+                (n, VerboseErrorKind::Nom(ErrorKind::Char)),
+                (n, VerboseErrorKind::Context("nick")),
+            ],
+        };
+        assert_eq!(context("nick", nick)(n), Err(Err::Error(error)));
+        assert_eq!(space::<()>(b"nick"), Err(Err::Error(())),);
+    }
 }
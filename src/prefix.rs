@@ -0,0 +1,210 @@
+use crate::config::{ParserConfig, Rfc};
+use crate::host::servername;
+#[cfg(feature = "std")]
+use crate::output::Writer;
+use crate::output::{to_vec, Output};
+use crate::parser::{nick_max, nick_rfc2812_max, nonwhite, nonwhites, user};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{not, opt};
+use nom::sequence::preceded;
+use nom::IResult;
+
+/// The origin of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix<'a> {
+    /// The message comes from a server: `irc.example.com`
+    Server(&'a [u8]),
+    /// The message comes from a user: `nick!user@host`
+    User {
+        nick: &'a [u8],
+        user: Option<&'a [u8]>,
+        host: Option<&'a [u8]>,
+    },
+}
+
+impl<'a> Prefix<'a> {
+    /// Serialize the prefix as it is sent, without the leading ':'
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_vec(|bytes| self.encode(bytes))
+    }
+
+    /// Same as `to_bytes` but write into `writer` without allocating
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.encode(&mut Writer(writer))
+    }
+
+    pub(crate) fn encode<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        match *self {
+            Prefix::Server(server) => out.put(server),
+            Prefix::User { nick, user, host } => {
+                out.put(nick)?;
+                if let Some(user) = user {
+                    out.put(b"!")?;
+                    out.put(user)?;
+                }
+                if let Some(host) = host {
+                    out.put(b"@")?;
+                    out.put(host)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Display the prefix without the leading ':'
+impl<'a> fmt::Display for Prefix<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// <prefix>   ::= <servername> | <nick> [ '!' <user> ] [ '@' <host> ]
+/// A lone word without any dot is considered to be a nick.
+/// Return an error if the input is neither a servername nor a nick.
+pub fn prefix(input: &[u8]) -> IResult<&[u8], Prefix<'_>> {
+    prefix_with(&ParserConfig::default())(input)
+}
+
+/// Same as `prefix` but the nick follows the grammar of the configured RFC
+pub fn prefix_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Prefix<'_>> {
+    let rfc = config.rfc;
+    let max_nick_len = config.max_nick_len;
+    move |input: &[u8]| alt((|input| user_prefix(input, rfc, max_nick_len), server_prefix))(input)
+}
+
+fn user_prefix(input: &[u8], rfc: Rfc, max_nick_len: usize) -> IResult<&[u8], Prefix<'_>> {
+    let (input, nick) = match rfc {
+        Rfc::Rfc1459 => nick_max(max_nick_len)(input)?,
+        Rfc::Rfc2812 => nick_rfc2812_max(max_nick_len)(input)?,
+    };
+    let (input, user) = opt(preceded(tag("!"), user))(input)?;
+    let (input, host) = opt(preceded(tag("@"), host))(input)?;
+    // if something is still stuck to the nick it was a servername
+    let (input, _) = not(nonwhite)(input)?;
+    Ok((input, Prefix::User { nick, user, host }))
+}
+
+fn server_prefix(input: &[u8]) -> IResult<&[u8], Prefix<'_>> {
+    let (input, servername) = servername(input)?;
+    let (input, _) = not(nonwhite)(input)?;
+    Ok((input, Prefix::Server(servername)))
+}
+
+/// <host>       ::= see RFC 952 [DNS:4] for details on allowed hostnames
+fn host(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    nonwhites(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ErrorKind;
+    use nom::Err;
+
+    #[test]
+    fn prefix_empty() {
+        let empty: &[u8] = b"";
+        assert_eq!(
+            prefix(empty),
+            Err(Err::Error((empty, ErrorKind::TakeWhile1)))
+        );
+    }
+
+    #[test]
+    fn prefix_server() {
+        let p: &[u8] = b"irc.example.com PING";
+        assert_eq!(
+            prefix(p),
+            Ok((&b" PING"[..], Prefix::Server(&b"irc.example.com"[..])))
+        );
+    }
+
+    #[test]
+    fn prefix_nick() {
+        let p: &[u8] = b"nick";
+        assert_eq!(
+            prefix(p),
+            Ok((
+                &b""[..],
+                Prefix::User {
+                    nick: &b"nick"[..],
+                    user: None,
+                    host: None
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn prefix_nick_user_host() {
+        let p: &[u8] = b"ni[c]k!~user@host.example.com JOIN";
+        assert_eq!(
+            prefix(p),
+            Ok((
+                &b" JOIN"[..],
+                Prefix::User {
+                    nick: &b"ni[c]k"[..],
+                    user: Some(&b"~user"[..]),
+                    host: Some(&b"host.example.com"[..])
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn prefix_nick_host() {
+        let p: &[u8] = b"nick@127.0.0.1";
+        assert_eq!(
+            prefix(p),
+            Ok((
+                &b""[..],
+                Prefix::User {
+                    nick: &b"nick"[..],
+                    user: None,
+                    host: Some(&b"127.0.0.1"[..])
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn prefix_long_nick() {
+        let p: &[u8] = b"averyverylongnick!user@host";
+        assert!(matches!(prefix(p), Ok((_, Prefix::User { .. }))));
+    }
+
+    #[test]
+    fn prefix_rfc2812() {
+        let p: &[u8] = b"nick_|away!user@host";
+        assert!(prefix(p).is_err());
+        let config = ParserConfig {
+            rfc: Rfc::Rfc2812,
+            ..ParserConfig::default()
+        };
+        assert_eq!(
+            prefix_with(&config)(p),
+            Ok((
+                &b""[..],
+                Prefix::User {
+                    nick: &b"nick_|away"[..],
+                    user: Some(&b"user"[..]),
+                    host: Some(&b"host"[..])
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn prefix_invalid() {
+        let p: &[u8] = b"1nick!user@host";
+        assert!(prefix(p).is_err());
+        let p: &[u8] = b"nick!@host";
+        assert!(prefix(p).is_err());
+    }
+}
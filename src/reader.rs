@@ -0,0 +1,128 @@
+use crate::config::ParserConfig;
+use crate::error::ReadError;
+use crate::message::{parse_message_with, Message};
+use crate::scan::find_byte;
+use std::io::{self, BufRead};
+
+/// Read the messages of a `BufRead` one line at a time
+/// The messages borrow the internal buffer of the reader so they can't be
+/// yielded by an `Iterator`, call `read_message` in a loop instead:
+/// ```
+/// use irc_parser::reader::MessageReader;
+///
+/// let mut reader = MessageReader::new(&b"PING :a\r\nPING :b\r\n"[..]);
+/// while let Some(message) = reader.read_message() {
+///     let message = message.unwrap();
+///     assert_eq!(message.command.as_str(), "PING");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MessageReader<R> {
+    reader: R,
+    config: ParserConfig,
+    line: Vec<u8>,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    /// Read with the default configuration
+    pub fn new(reader: R) -> Self {
+        Self::with_config(reader, ParserConfig::default())
+    }
+
+    pub fn with_config(reader: R, config: ParserConfig) -> Self {
+        MessageReader {
+            reader,
+            config,
+            line: Vec::new(),
+        }
+    }
+
+    /// Return the underlying reader, the data buffered by it is kept
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read and parse the next line, return `None` at the end of the input
+    /// A line longer than the configuration allows is skipped without being
+    /// kept in memory and reported as `ParseError::TagsTooLong` or
+    /// `ParseError::LineTooLong`. An unterminated last line is reported as
+    /// `ParseError::UnterminatedLine`.
+    pub fn read_message(&mut self) -> Option<Result<Message<'_>, ReadError>> {
+        let max = self.config.max_len();
+        self.line.clear();
+        let mut len = 0;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(ReadError::Io(e))),
+            };
+            if available.is_empty() {
+                if len == 0 {
+                    return None;
+                }
+                break;
+            }
+            let (chunk, done) = match find_byte(b'\n', available) {
+                Some(pos) => (&available[..=pos], true),
+                None => (available, false),
+            };
+            let kept = chunk.len().min(max.saturating_sub(self.line.len()));
+            self.line.extend_from_slice(&chunk[..kept]);
+            len += chunk.len();
+            let consumed = chunk.len();
+            self.reader.consume(consumed);
+            if done {
+                break;
+            }
+        }
+        if len > self.line.len() {
+            let error = self.config.too_long(&self.line, len);
+            return Some(Err(ReadError::Parse(error)));
+        }
+        Some(parse_message_with(&self.line, &self.config).map_err(ReadError::Parse))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+    use crate::error::ParseError;
+    use std::io::BufReader;
+
+    #[test]
+    fn read_partial() {
+        let input: &[u8] = b"PING :a\r\nPRIVMSG #chan :hello world\r\nPING";
+        // a tiny buffer forces the lines to be read in multiple parts
+        let mut reader = MessageReader::new(BufReader::with_capacity(4, input));
+        assert_eq!(reader.read_message().unwrap().unwrap().params[0], b"a");
+        let m = reader.read_message().unwrap().unwrap();
+        assert_eq!(m.command, Command::Privmsg);
+        assert_eq!(m.params[1], b"hello world");
+        assert!(matches!(
+            reader.read_message(),
+            Some(Err(ReadError::Parse(ParseError::UnterminatedLine {
+                offset: 4
+            })))
+        ));
+        assert!(reader.read_message().is_none());
+    }
+
+    #[test]
+    fn read_too_long() {
+        let mut input = b"PRIVMSG #chan :".to_vec();
+        input.resize(1000, b'a');
+        input.extend_from_slice(b"\r\nPING :a\r\n");
+        let mut reader = MessageReader::new(&input[..]);
+        assert!(matches!(
+            reader.read_message(),
+            Some(Err(ReadError::Parse(ParseError::LineTooLong {
+                offset: 0,
+                len: 1002,
+                max: 512
+            })))
+        ));
+        assert_eq!(reader.read_message().unwrap().unwrap().params[0], b"a");
+    }
+}
@@ -0,0 +1,102 @@
+//! Resilient parsing for bouncers and log ingestion
+//! A malformed line is skipped up to the next line ending and reported along
+//! with its raw bytes instead of stopping the whole stream.
+
+use crate::config::ParserConfig;
+use crate::error::ParseError;
+use crate::message::{parse_message_with, Message};
+
+/// A line of the input, parsed or recovered from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Parsed<'a> {
+    /// The line is a valid message
+    Message(Message<'a>),
+    /// The line is malformed, `raw_line` is the whole line as found in the
+    /// input, line ending included
+    Recovered {
+        raw_line: &'a [u8],
+        error: ParseError,
+    },
+}
+
+impl<'a> Parsed<'a> {
+    /// Return the message if the line was valid
+    pub fn ok(self) -> Option<Message<'a>> {
+        match self {
+            Parsed::Message(message) => Some(message),
+            Parsed::Recovered { .. } => None,
+        }
+    }
+}
+
+/// Parse the first line of the input and return it with the rest of the
+/// input, or `None` if the input does not contain a whole line yet
+/// ```
+/// use irc_parser::recover::{next_line, Parsed};
+/// use irc_parser::{ParseError, ParserConfig};
+///
+/// let input = b":nick 12 :oops\r\nPING :a\r\n";
+/// let (line, rest) = next_line(input, &ParserConfig::default()).unwrap();
+/// assert_eq!(
+///     line,
+///     Parsed::Recovered {
+///         raw_line: b":nick 12 :oops\r\n",
+///         error: ParseError::InvalidCommand { offset: 8 },
+///     }
+/// );
+/// let (line, rest) = next_line(rest, &ParserConfig::default()).unwrap();
+/// assert!(matches!(line, Parsed::Message(_)));
+/// assert!(rest.is_empty());
+/// ```
+pub fn next_line<'a>(input: &'a [u8], config: &ParserConfig) -> Option<(Parsed<'a>, &'a [u8])> {
+    let len = config.line_len(input)?;
+    let (line, rest) = input.split_at(len);
+    Some((parse_or_recover(line, config), rest))
+}
+
+/// Parse a single whole line
+pub(crate) fn parse_or_recover<'a>(line: &'a [u8], config: &ParserConfig) -> Parsed<'a> {
+    match parse_message_with(line, config) {
+        Ok(message) => Parsed::Message(message),
+        Err(error) => Parsed::Recovered {
+            raw_line: line,
+            error,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+
+    #[test]
+    fn recover_lines() {
+        let mut input: &[u8] = b"PING :a\r\n:nick !PING\r\nPING :a\0b\r\nPING :b\r\nPING";
+        let config = ParserConfig::default();
+        let mut lines = Vec::new();
+        while let Some((line, rest)) = next_line(input, &config) {
+            lines.push(line);
+            input = rest;
+        }
+        assert_eq!(input, &b"PING"[..]);
+        assert_eq!(lines.len(), 4);
+        assert!(matches!(&lines[0], Parsed::Message(m) if m.params[0] == b"a"));
+        assert_eq!(
+            lines[1],
+            Parsed::Recovered {
+                raw_line: b":nick !PING\r\n",
+                error: ParseError::InvalidCommand { offset: 6 },
+            }
+        );
+        assert_eq!(
+            lines[2],
+            Parsed::Recovered {
+                raw_line: b"PING :a\0b\r\n",
+                error: ParseError::IllegalByteInParam { offset: 7, byte: 0 },
+            }
+        );
+        let last = lines.pop().unwrap().ok().unwrap();
+        assert_eq!(last.command, Command::Ping);
+    }
+}
@@ -0,0 +1,466 @@
+//! The registration of a client: capability negotiation, SASL
+//! authentication and the choice of a free nick
+//! ```
+//! use irc_parser::message::parse_message;
+//! use irc_parser::registration::{Registration, RegistrationOutcome, Sasl};
+//!
+//! let mut registration = Registration::new(b"nick", b"user", b"Real Name")
+//!     .cap(b"server-time")
+//!     .sasl(Sasl::Plain { account: b"nick".to_vec(), password: b"hunter2".to_vec() });
+//! assert_eq!(registration.start()[0], b"CAP LS 302\r\n");
+//! let replies = [
+//!     &b":irc CAP * LS :sasl server-time multi-prefix\r\n"[..],
+//!     b":irc CAP * ACK :server-time sasl\r\n",
+//!     b"AUTHENTICATE +\r\n",
+//!     b":irc 900 nick nick!user@host nick :You are now logged in as nick\r\n",
+//!     b":irc 903 nick :SASL authentication successful\r\n",
+//!     b":irc 001 nick :Welcome\r\n",
+//! ];
+//! let sent: Vec<_> = replies
+//!     .iter()
+//!     .flat_map(|line| registration.feed(&parse_message(line).unwrap()))
+//!     .collect();
+//! assert_eq!(sent[0], b"CAP REQ :server-time sasl\r\n");
+//! assert_eq!(sent[1], b"AUTHENTICATE PLAIN\r\n");
+//! assert_eq!(sent[3], b"CAP END\r\n");
+//! match registration.outcome() {
+//!     Some(RegistrationOutcome::Registered { account, .. }) => {
+//!         assert_eq!(account.as_deref(), Some(&b"nick"[..]))
+//!     }
+//!     outcome => panic!("{:?}", outcome),
+//! }
+//! ```
+
+use crate::cap::{Cap, CapLsAccumulator};
+use crate::command::Command;
+use crate::isupport::ISupport;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use crate::sasl::{authenticate_lines, external, plain, Authenticate};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How to authenticate with SASL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sasl {
+    /// The account and password
+    Plain { account: Vec<u8>, password: Vec<u8> },
+    /// The TLS client certificate
+    External,
+}
+
+impl Sasl {
+    fn mechanism(&self) -> &'static [u8] {
+        match self {
+            Sasl::Plain { .. } => b"PLAIN",
+            Sasl::External => b"EXTERNAL",
+        }
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        match self {
+            Sasl::Plain { account, password } => plain(b"", account, password),
+            Sasl::External => external(None),
+        }
+    }
+}
+
+/// How the registration ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistrationOutcome {
+    /// RPL_WELCOME (001): the client is registered
+    Registered {
+        /// The nick given by the server
+        nick: Vec<u8>,
+        /// The capabilities acknowledged by the server
+        caps: Vec<Vec<u8>>,
+        /// The account logged in with SASL
+        account: Option<Vec<u8>>,
+    },
+    /// Every nick was refused, the last one tried is given
+    NickRejected { nick: Vec<u8> },
+    /// The SASL authentication failed or is not supported by the server
+    /// while it was required, with the numeric of the failure if any
+    SaslFailed { numeric: Option<Numeric> },
+    /// ERROR: the server closed the connection
+    Error { message: Vec<u8> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for the list of the capabilities
+    CapLs,
+    /// Waiting for the ACK or NAK of the requested capabilities
+    CapReq,
+    /// Authenticating
+    Sasl,
+    /// CAP END was sent, or the server doesn't support CAP
+    Waiting,
+    Done,
+}
+
+/// The state machine of the registration of a client
+/// Send the lines of `start`, then feed every message received to `feed`
+/// and send the lines it returns until `outcome` is known.
+#[derive(Debug, Clone)]
+pub struct Registration {
+    nick: Vec<u8>,
+    user: Vec<u8>,
+    realname: Vec<u8>,
+    password: Option<Vec<u8>>,
+    alt_nicks: Vec<Vec<u8>>,
+    caps: Vec<Vec<u8>>,
+    sasl: Option<Sasl>,
+    require_sasl: bool,
+    state: State,
+    ls: CapLsAccumulator,
+    acked: Vec<Vec<u8>>,
+    account: Option<Vec<u8>>,
+    isupport: ISupport,
+    outcome: Option<RegistrationOutcome>,
+}
+
+impl Registration {
+    /// Register with `nick`, or `nick_` and `nick__` if it's taken
+    pub fn new(nick: &[u8], user: &[u8], realname: &[u8]) -> Self {
+        Registration {
+            nick: nick.to_vec(),
+            user: user.to_vec(),
+            realname: realname.to_vec(),
+            password: None,
+            alt_nicks: vec![[nick, b"_"].concat(), [nick, b"__"].concat()],
+            caps: Vec::new(),
+            sasl: None,
+            require_sasl: false,
+            state: State::CapLs,
+            ls: CapLsAccumulator::new(),
+            acked: Vec::new(),
+            account: None,
+            isupport: ISupport::new(),
+            outcome: None,
+        }
+    }
+
+    /// Send a PASS with the connection password
+    pub fn password(mut self, password: &[u8]) -> Self {
+        self.password = Some(password.to_vec());
+        self
+    }
+
+    /// Replace the nicks tried, in order, when the nick is taken or invalid
+    pub fn alt_nicks(mut self, nicks: &[&[u8]]) -> Self {
+        self.alt_nicks = nicks.iter().map(|nick| nick.to_vec()).collect();
+        self
+    }
+
+    /// Request a capability if the server offers it
+    pub fn cap(mut self, cap: &[u8]) -> Self {
+        self.caps.push(cap.to_vec());
+        self
+    }
+
+    /// Authenticate with SASL if the server offers it
+    pub fn sasl(mut self, sasl: Sasl) -> Self {
+        self.sasl = Some(sasl);
+        self
+    }
+
+    /// Give up with `SaslFailed` rather than registering without account
+    /// when the authentication fails
+    pub fn require_sasl(mut self, require_sasl: bool) -> Self {
+        self.require_sasl = require_sasl;
+        self
+    }
+
+    /// Return the lines opening the registration, crlf included
+    pub fn start(&mut self) -> Vec<Vec<u8>> {
+        let mut lines = vec![b"CAP LS 302\r\n".to_vec()];
+        if let Some(password) = &self.password {
+            lines.push([&b"PASS "[..], password, b"\r\n"].concat());
+        }
+        lines.push(nick_line(&self.nick));
+        lines.push(
+            [
+                &b"USER "[..],
+                &self.user,
+                b" 0 * :",
+                &self.realname,
+                b"\r\n",
+            ]
+            .concat(),
+        );
+        lines
+    }
+
+    /// Return the outcome once the registration is over
+    pub fn outcome(&self) -> Option<&RegistrationOutcome> {
+        self.outcome.as_ref()
+    }
+
+    /// Return the RPL_ISUPPORT tokens received so far
+    /// They keep being collected after the registration.
+    pub fn isupport(&self) -> &ISupport {
+        &self.isupport
+    }
+
+    /// Feed a message received from the server and return the lines to send
+    pub fn feed(&mut self, message: &Message) -> Vec<Vec<u8>> {
+        if self.isupport.update(message) || self.state == State::Done {
+            return Vec::new();
+        }
+        match message.command {
+            Command::Ping => {
+                let token = message.params.first().copied().unwrap_or_default();
+                return vec![[&b"PONG :"[..], token, b"\r\n"].concat()];
+            }
+            Command::Error => {
+                let message = message.params.first().copied().unwrap_or_default();
+                self.finish(RegistrationOutcome::Error {
+                    message: message.to_vec(),
+                });
+                return Vec::new();
+            }
+            Command::Cap => return self.feed_cap(message),
+            Command::Authenticate => return self.feed_authenticate(message),
+            _ => (),
+        }
+        let numeric = match message.command.numeric() {
+            Some(numeric) => numeric,
+            None => return Vec::new(),
+        };
+        match numeric {
+            Numeric::RplWelcome => {
+                let nick = message.params.first().map_or(&self.nick[..], |nick| nick);
+                self.finish(RegistrationOutcome::Registered {
+                    nick: nick.to_vec(),
+                    caps: self.acked.clone(),
+                    account: self.account.clone(),
+                });
+                Vec::new()
+            }
+            Numeric::ErrNicknameInUse
+            | Numeric::ErrErroneousNickname
+            | Numeric::ErrNickCollision => {
+                if self.alt_nicks.is_empty() {
+                    let nick = self.nick.clone();
+                    self.finish(RegistrationOutcome::NickRejected { nick });
+                    return Vec::new();
+                }
+                self.nick = self.alt_nicks.remove(0);
+                vec![nick_line(&self.nick)]
+            }
+            Numeric::RplLoggedIn => {
+                self.account = message.params.get(2).map(|account| account.to_vec());
+                Vec::new()
+            }
+            Numeric::RplSaslSuccess if self.state == State::Sasl => self.cap_end(),
+            Numeric::ErrNickLocked
+            | Numeric::ErrSaslFail
+            | Numeric::ErrSaslTooLong
+            | Numeric::ErrSaslAborted
+            | Numeric::ErrSaslAlready
+                if self.state == State::Sasl =>
+            {
+                self.sasl_failed(Some(numeric))
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn feed_cap(&mut self, message: &Message) -> Vec<Vec<u8>> {
+        let cap = match Cap::from_message(message) {
+            Some(cap) => cap,
+            None => return Vec::new(),
+        };
+        match (&cap, self.state) {
+            (Cap::Ls { .. }, State::CapLs) => {
+                let available = match self.ls.feed(&cap) {
+                    Some(available) => available,
+                    None => return Vec::new(),
+                };
+                let offered = |name: &[u8]| available.iter().any(|(cap, _)| cap == name);
+                let mut wanted: Vec<&[u8]> = self
+                    .caps
+                    .iter()
+                    .map(|cap| &cap[..])
+                    .filter(|&cap| offered(cap))
+                    .collect();
+                if self.sasl.is_some() && offered(b"sasl") {
+                    wanted.push(b"sasl");
+                }
+                if wanted.is_empty() {
+                    return self.without_sasl();
+                }
+                self.state = State::CapReq;
+                vec![[&b"CAP REQ :"[..], &wanted.join(&b' '), b"\r\n"].concat()]
+            }
+            (Cap::Ack { caps, .. }, State::CapReq) => {
+                self.acked.extend(
+                    caps.iter()
+                        .filter(|cap| !cap.disable)
+                        .map(|cap| cap.name.to_vec()),
+                );
+                match &self.sasl {
+                    Some(sasl) if self.acked.iter().any(|cap| cap == b"sasl") => {
+                        self.state = State::Sasl;
+                        vec![[&b"AUTHENTICATE "[..], sasl.mechanism(), b"\r\n"].concat()]
+                    }
+                    _ => self.without_sasl(),
+                }
+            }
+            (Cap::Nak { .. }, State::CapReq) => self.without_sasl(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn feed_authenticate(&mut self, message: &Message) -> Vec<Vec<u8>> {
+        match (Authenticate::from_message(message), &self.sasl, self.state) {
+            (Some(Authenticate::Empty), Some(sasl), State::Sasl) => {
+                authenticate_lines(&sasl.payload())
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// End the negotiation, unless SASL was required
+    fn without_sasl(&mut self) -> Vec<Vec<u8>> {
+        if self.sasl.is_some() {
+            self.sasl_failed(None)
+        } else {
+            self.cap_end()
+        }
+    }
+
+    fn sasl_failed(&mut self, numeric: Option<Numeric>) -> Vec<Vec<u8>> {
+        if self.require_sasl {
+            self.finish(RegistrationOutcome::SaslFailed { numeric });
+            return vec![b"QUIT\r\n".to_vec()];
+        }
+        self.cap_end()
+    }
+
+    fn cap_end(&mut self) -> Vec<Vec<u8>> {
+        self.state = State::Waiting;
+        vec![b"CAP END\r\n".to_vec()]
+    }
+
+    fn finish(&mut self, outcome: RegistrationOutcome) {
+        self.state = State::Done;
+        self.outcome = Some(outcome);
+    }
+}
+
+fn nick_line(nick: &[u8]) -> Vec<u8> {
+    [&b"NICK "[..], nick, b"\r\n"].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_all;
+
+    fn run(registration: &mut Registration, input: &[u8]) -> Vec<Vec<u8>> {
+        parse_all(input)
+            .flat_map(|message| registration.feed(&message.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn register_without_cap() {
+        let mut registration = Registration::new(b"nick", b"user", b"Real").password(b"pw");
+        assert_eq!(
+            registration.start(),
+            vec![
+                b"CAP LS 302\r\n".to_vec(),
+                b"PASS pw\r\n".to_vec(),
+                b"NICK nick\r\n".to_vec(),
+                b"USER user 0 * :Real\r\n".to_vec()
+            ]
+        );
+        let sent = run(
+            &mut registration,
+            b"PING :cookie\r\n\
+            :irc 433 * nick :Nickname is already in use\r\n\
+            :irc 421 nick_ CAP :Unknown command\r\n\
+            :irc 001 nick_ :Welcome\r\n\
+            :irc 005 nick_ NETWORK=Example :are supported\r\n\
+            :irc 433 nick_ nick :Nickname is already in use\r\n",
+        );
+        assert_eq!(
+            sent,
+            vec![b"PONG :cookie\r\n".to_vec(), b"NICK nick_\r\n".to_vec()]
+        );
+        assert_eq!(
+            registration.outcome(),
+            Some(&RegistrationOutcome::Registered {
+                nick: b"nick_".to_vec(),
+                caps: Vec::new(),
+                account: None
+            })
+        );
+        assert_eq!(
+            registration.isupport().network.as_deref(),
+            Some(&b"Example"[..])
+        );
+    }
+
+    #[test]
+    fn register_nick_rejected() {
+        let mut registration = Registration::new(b"nick", b"user", b"Real").alt_nicks(&[b"other"]);
+        let sent = run(
+            &mut registration,
+            b":irc CAP * LS :multi-prefix\r\n\
+            :irc 432 * nick :Erroneous nickname\r\n\
+            :irc 433 * other :Nickname is already in use\r\n",
+        );
+        assert_eq!(
+            sent,
+            vec![b"CAP END\r\n".to_vec(), b"NICK other\r\n".to_vec()]
+        );
+        assert_eq!(
+            registration.outcome(),
+            Some(&RegistrationOutcome::NickRejected {
+                nick: b"other".to_vec()
+            })
+        );
+    }
+
+    #[test]
+    fn register_sasl_failure() {
+        let input = b":irc CAP * LS * :multi-prefix\r\n\
+            :irc CAP * LS :sasl=EXTERNAL\r\n\
+            :irc CAP * ACK :multi-prefix sasl\r\n\
+            AUTHENTICATE +\r\n\
+            :irc 904 nick :SASL authentication failed\r\n\
+            :irc 001 nick :Welcome\r\n";
+        let mut registration = Registration::new(b"nick", b"user", b"Real")
+            .cap(b"multi-prefix")
+            .sasl(Sasl::External);
+        let sent = run(&mut registration, input);
+        assert_eq!(
+            sent,
+            vec![
+                b"CAP REQ :multi-prefix sasl\r\n".to_vec(),
+                b"AUTHENTICATE EXTERNAL\r\n".to_vec(),
+                b"AUTHENTICATE +\r\n".to_vec(),
+                b"CAP END\r\n".to_vec()
+            ]
+        );
+        assert!(matches!(
+            registration.outcome(),
+            Some(RegistrationOutcome::Registered { account: None, caps, .. }) if caps.len() == 2
+        ));
+
+        let mut registration = Registration::new(b"nick", b"user", b"Real")
+            .sasl(Sasl::External)
+            .require_sasl(true);
+        let sent = run(&mut registration, input);
+        assert_eq!(sent.last().unwrap(), b"QUIT\r\n");
+        assert_eq!(
+            registration.outcome(),
+            Some(&RegistrationOutcome::SaslFailed {
+                numeric: Some(Numeric::ErrSaslFail)
+            })
+        );
+    }
+}
@@ -0,0 +1,622 @@
+//! The TS6 server to server protocol, as spoken by charybdis, solanum and
+//! ratbox, for services and pseudoservers linking to a network
+//! Servers and users are known by their ids rather than their names: the
+//! SID of a server is a digit followed by two alphanumerics, `42X`, and the
+//! UID of a user is the SID of its server followed by six alphanumerics,
+//! `42XAAAAAB`. Messages are sourced by these ids.
+//! ```
+//! use irc_parser::message::parse_message;
+//! use irc_parser::s2s::{ServerMessage, Sid, Source};
+//!
+//! let line = b":42X EUID nick 1 1600000000 +i user host 192.0.2.1 42XAAAAAB * account :Real\r\n";
+//! let message = parse_message(line).unwrap();
+//! let sid = Sid::parse(b"42X").unwrap();
+//! assert_eq!(Source::from_message(&message), Some(Source::Server(sid)));
+//! let user = match ServerMessage::from_message(&message) {
+//!     Some(ServerMessage::Euid(user)) => user,
+//!     other => panic!("{:?}", other),
+//! };
+//! assert_eq!(user.uid.sid(), sid);
+//! assert_eq!(user.account, Some(&b"account"[..]));
+//! assert_eq!(ServerMessage::Euid(user).to_bytes(b"42X"), &line[..]);
+//! ```
+
+use crate::command::Command;
+use crate::message::Message;
+use crate::mode::{channel_modes_with, ChanModes, ModeEntry, PrefixModes};
+use crate::params::Params;
+use crate::prefix::Prefix;
+use crate::tags::Tags;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+use nom::bytes::complete::take_while_m_n;
+use nom::combinator::{map, recognize};
+use nom::sequence::pair;
+use nom::IResult;
+
+/// The id of a server: a digit followed by two uppercase letters or digits,
+/// `42X`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sid([u8; 3]);
+
+impl Sid {
+    /// Return `None` if `sid` is not a whole SID
+    pub fn parse(sid: &[u8]) -> Option<Self> {
+        match self::sid(sid) {
+            Ok((b"", sid)) => Some(sid),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+/// The id of a user: the SID of its server followed by an uppercase letter
+/// and five uppercase letters or digits, `42XAAAAAB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uid([u8; 9]);
+
+impl Uid {
+    /// Return `None` if `uid` is not a whole UID
+    pub fn parse(uid: &[u8]) -> Option<Self> {
+        match self::uid(uid) {
+            Ok((b"", uid)) => Some(uid),
+            _ => None,
+        }
+    }
+
+    /// Return the UID of the user `id` of the server `sid`, or `None` if
+    /// `id` is not made of an uppercase letter and five uppercase letters or
+    /// digits
+    pub fn new(sid: Sid, id: [u8; 6]) -> Option<Self> {
+        let mut uid = [0; 9];
+        uid[..3].copy_from_slice(&sid.0);
+        uid[3..].copy_from_slice(&id);
+        Self::parse(&uid)
+    }
+
+    /// Return the SID of the server of the user
+    pub fn sid(&self) -> Sid {
+        Sid([self.0[0], self.0[1], self.0[2]])
+    }
+
+    /// Return the part of the UID following the SID
+    pub fn id(&self) -> &[u8] {
+        &self.0[3..]
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Uid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+/// <sid>  ::= <digit> 2( <upper> | <digit> )
+/// Return an error if the input does not start with a SID:
+/// Err( (input, ErrorKind) )
+pub fn sid(input: &[u8]) -> IResult<&[u8], Sid> {
+    map(
+        recognize(pair(
+            take_while_m_n(1, 1, |c: u8| c.is_ascii_digit()),
+            take_while_m_n(2, 2, is_id_char),
+        )),
+        |sid: &[u8]| Sid([sid[0], sid[1], sid[2]]),
+    )(input)
+}
+
+/// <uid>  ::= <sid> <upper> 5( <upper> | <digit> )
+/// Return an error if the input does not start with a UID:
+/// Err( (input, ErrorKind) )
+pub fn uid(input: &[u8]) -> IResult<&[u8], Uid> {
+    let (rest, sid) = sid(input)?;
+    let (rest, id) = recognize(pair(
+        take_while_m_n(1, 1, |c: u8| c.is_ascii_uppercase()),
+        take_while_m_n(5, 5, is_id_char),
+    ))(rest)?;
+    let mut uid = [0; 9];
+    uid[..3].copy_from_slice(&sid.0);
+    uid[3..].copy_from_slice(id);
+    Ok((rest, Uid(uid)))
+}
+
+fn is_id_char(c: u8) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit()
+}
+
+/// Give the UIDs of the users introduced by a server, in order:
+/// `42XAAAAAA`, `42XAAAAAB`, ..., `42XAAAAAZ`, `42XAAAAA0`, ..., `42XAAAAA9`,
+/// `42XAAAABA`
+/// ```
+/// use irc_parser::s2s::{Sid, UidGenerator};
+///
+/// let mut uids = UidGenerator::new(Sid::parse(b"00A").unwrap());
+/// assert_eq!(uids.next().unwrap().as_bytes(), b"00AAAAAAA");
+/// assert_eq!(uids.nth(26).unwrap().as_bytes(), b"00AAAAAA1");
+/// ```
+#[derive(Debug, Clone)]
+pub struct UidGenerator {
+    sid: Sid,
+    next: Option<[u8; 6]>,
+}
+
+impl UidGenerator {
+    pub fn new(sid: Sid) -> Self {
+        UidGenerator {
+            sid,
+            next: Some(*b"AAAAAA"),
+        }
+    }
+
+    /// Continue after `uid`, to keep the ids given before a restart
+    pub fn after(uid: Uid) -> Self {
+        let mut id = [0; 6];
+        id.copy_from_slice(uid.id());
+        UidGenerator {
+            sid: uid.sid(),
+            next: increment(id),
+        }
+    }
+}
+
+impl Iterator for UidGenerator {
+    type Item = Uid;
+
+    /// Return `None` once the 26 * 36^5 UIDs were given
+    fn next(&mut self) -> Option<Uid> {
+        let id = self.next?;
+        self.next = increment(id);
+        Uid::new(self.sid, id)
+    }
+}
+
+/// Return the id following `id`, the last char goes from `A` to `Z` then
+/// from `0` to `9` before carrying
+fn increment(mut id: [u8; 6]) -> Option<[u8; 6]> {
+    for i in (0..id.len()).rev() {
+        match id[i] {
+            b'Z' if i == 0 => return None,
+            b'Z' => {
+                id[i] = b'0';
+                return Some(id);
+            }
+            b'9' => id[i] = b'A',
+            c => {
+                id[i] = c + 1;
+                return Some(id);
+            }
+        }
+    }
+    None
+}
+
+/// The source of a message on a server link
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source<'a> {
+    /// A server by its SID: `42X`
+    Server(Sid),
+    /// A user by its UID: `42XAAAAAB`
+    User(Uid),
+    /// A server or a nick by its name, as sent before the SIDs are known
+    Name(&'a [u8]),
+}
+
+impl<'a> Source<'a> {
+    /// Return `None` if the message has no prefix
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        let source = match message.prefix? {
+            Prefix::Server(name) => name,
+            Prefix::User {
+                nick,
+                user: None,
+                host: None,
+            } => nick,
+            Prefix::User { nick, .. } => return Some(Source::Name(nick)),
+        };
+        Some(match (Sid::parse(source), Uid::parse(source)) {
+            (Some(sid), _) => Source::Server(sid),
+            (_, Some(uid)) => Source::User(uid),
+            _ => Source::Name(source),
+        })
+    }
+}
+
+/// `SID <name> <hopcount> <sid> :<description>`: a server joined the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerIntro<'a> {
+    pub name: &'a [u8],
+    pub hops: u32,
+    pub sid: Sid,
+    pub description: &'a [u8],
+}
+
+/// A user joined the network
+/// `UID <nick> <hopcount> <nickTS> <umodes> <username> <host> <ip> <uid>
+/// :<realname>`
+/// `EUID` adds the real host and the account before the realname.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserIntro<'a> {
+    pub nick: &'a [u8],
+    pub hops: u32,
+    /// When the nick was taken, in seconds since the epoch
+    pub ts: u64,
+    /// The user modes, `+` included
+    pub umodes: &'a [u8],
+    pub user: &'a [u8],
+    /// The host shown to the other users
+    pub host: &'a [u8],
+    /// `None` when the address is hidden, sent as `0`
+    pub ip: Option<&'a [u8]>,
+    pub uid: Uid,
+    /// The host before any cloak, EUID only, `None` when it's the same as
+    /// `host`
+    pub realhost: Option<&'a [u8]>,
+    /// The account the user is logged in, EUID only
+    pub account: Option<&'a [u8]>,
+    pub realname: &'a [u8],
+}
+
+/// A member in the list of a SJOIN: `@+42XAAAAAB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SjoinMember<'a> {
+    /// The membership prefixes, highest first
+    pub prefixes: &'a [u8],
+    pub uid: Uid,
+}
+
+/// `SJOIN <channelTS> <channel> <modes> [<argument>...] :<members>`: the
+/// users of a server joined a channel, sent by the server
+/// Lower timestamps win: when the channel is older on the other side, the
+/// modes and prefixes of the newer side are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sjoin<'a> {
+    /// When the channel was created, in seconds since the epoch
+    pub ts: u64,
+    pub channel: &'a [u8],
+    /// The mode string followed by its arguments, no list modes
+    pub modes: Vec<&'a [u8]>,
+    pub members: Vec<SjoinMember<'a>>,
+}
+
+impl<'a> Sjoin<'a> {
+    /// Interpret the modes, see `mode::channel_modes_with`
+    pub fn changes(&self, chanmodes: &ChanModes, prefixes: &PrefixModes) -> Vec<ModeEntry<'a>> {
+        channel_modes_with(&self.modes, chanmodes, prefixes)
+    }
+}
+
+/// `TMODE <channelTS> <channel> <modes> [<argument>...]`: a channel MODE
+/// ignored when the channel is older than the timestamp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tmode<'a> {
+    pub ts: u64,
+    pub channel: &'a [u8],
+    /// The mode string followed by its arguments, user ids for the
+    /// membership modes
+    pub modes: Vec<&'a [u8]>,
+}
+
+impl<'a> Tmode<'a> {
+    /// Interpret the modes, see `mode::channel_modes_with`
+    pub fn changes(&self, chanmodes: &ChanModes, prefixes: &PrefixModes) -> Vec<ModeEntry<'a>> {
+        channel_modes_with(&self.modes, chanmodes, prefixes)
+    }
+}
+
+/// A message of a server link introducing servers, users and channels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage<'a> {
+    Sid(ServerIntro<'a>),
+    Uid(UserIntro<'a>),
+    /// An UID with the real host and the account
+    Euid(UserIntro<'a>),
+    Sjoin(Sjoin<'a>),
+    Tmode(Tmode<'a>),
+}
+
+impl<'a> ServerMessage<'a> {
+    /// Return `None` if it's not one of these commands or if it's malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        Some(match (message.command, &message.params[..]) {
+            (Command::Sid, &[name, hops, sid, description]) => ServerMessage::Sid(ServerIntro {
+                name,
+                hops: number(hops)?,
+                sid: Sid::parse(sid)?,
+                description,
+            }),
+            (Command::Uid, &[nick, hops, ts, umodes, user, host, ip, uid, realname]) => {
+                ServerMessage::Uid(UserIntro {
+                    nick,
+                    hops: number(hops)?,
+                    ts: number(ts)?,
+                    umodes,
+                    user,
+                    host,
+                    ip: known(ip, b"0"),
+                    uid: Uid::parse(uid)?,
+                    realhost: None,
+                    account: None,
+                    realname,
+                })
+            }
+            (
+                Command::Euid,
+                &[nick, hops, ts, umodes, user, host, ip, uid, realhost, account, realname],
+            ) => ServerMessage::Euid(UserIntro {
+                nick,
+                hops: number(hops)?,
+                ts: number(ts)?,
+                umodes,
+                user,
+                host,
+                ip: known(ip, b"0"),
+                uid: Uid::parse(uid)?,
+                realhost: known(realhost, b"*"),
+                account: known(account, b"*"),
+                realname,
+            }),
+            (Command::Sjoin, &[ts, channel, ref modes @ .., members]) if !modes.is_empty() => {
+                ServerMessage::Sjoin(Sjoin {
+                    ts: number(ts)?,
+                    channel,
+                    modes: modes.to_vec(),
+                    members: members
+                        .split(|&c| c == b' ')
+                        .filter(|member| !member.is_empty())
+                        .map(|member| {
+                            let len = member
+                                .iter()
+                                .take_while(|c| !c.is_ascii_alphanumeric())
+                                .count();
+                            Some(SjoinMember {
+                                prefixes: &member[..len],
+                                uid: Uid::parse(&member[len..])?,
+                            })
+                        })
+                        .collect::<Option<_>>()?,
+                })
+            }
+            (Command::Tmode, &[ts, channel, ref modes @ ..]) if !modes.is_empty() => {
+                ServerMessage::Tmode(Tmode {
+                    ts: number(ts)?,
+                    channel,
+                    modes: modes.to_vec(),
+                })
+            }
+            _ => return None,
+        })
+    }
+
+    /// Return the line sourced by `source`, a SID or an UID, crlf included
+    pub fn to_bytes(&self, source: &[u8]) -> Vec<u8> {
+        let (hops, ts) = match self {
+            ServerMessage::Sid(server) => (server.hops.to_string(), Default::default()),
+            ServerMessage::Uid(user) | ServerMessage::Euid(user) => {
+                (user.hops.to_string(), user.ts.to_string())
+            }
+            ServerMessage::Sjoin(Sjoin { ts, .. }) | ServerMessage::Tmode(Tmode { ts, .. }) => {
+                (Default::default(), ts.to_string())
+            }
+        };
+        let (hops, ts) = (hops.as_bytes(), ts.as_bytes());
+        let members: Vec<u8>;
+        let mut params = Params::new();
+        let command = match self {
+            ServerMessage::Sid(server) => {
+                params.push(server.name);
+                params.push(hops);
+                params.push(server.sid.as_bytes());
+                params.push_trailing(server.description);
+                Command::Sid
+            }
+            ServerMessage::Uid(user) | ServerMessage::Euid(user) => {
+                for param in &[user.nick, hops, ts, user.umodes, user.user, user.host] {
+                    params.push(param);
+                }
+                params.push(user.ip.unwrap_or(b"0"));
+                params.push(user.uid.as_bytes());
+                if let ServerMessage::Euid(_) = self {
+                    params.push(user.realhost.unwrap_or(b"*"));
+                    params.push(user.account.unwrap_or(b"*"));
+                }
+                params.push_trailing(user.realname);
+                match self {
+                    ServerMessage::Uid(_) => Command::Uid,
+                    _ => Command::Euid,
+                }
+            }
+            ServerMessage::Sjoin(sjoin) => {
+                params.push(ts);
+                params.push(sjoin.channel);
+                for mode in &sjoin.modes {
+                    params.push(mode);
+                }
+                members = sjoin
+                    .members
+                    .iter()
+                    .map(|member| [member.prefixes, member.uid.as_bytes()].concat())
+                    .collect::<Vec<_>>()
+                    .join(&b' ');
+                params.push_trailing(&members);
+                Command::Sjoin
+            }
+            ServerMessage::Tmode(tmode) => {
+                params.push(ts);
+                params.push(tmode.channel);
+                for mode in &tmode.modes {
+                    params.push(mode);
+                }
+                Command::Tmode
+            }
+        };
+        Message {
+            tags: Tags::new(),
+            prefix: Some(Prefix::Server(source)),
+            command,
+            params,
+        }
+        .to_bytes()
+    }
+}
+
+fn number<T: FromStr>(param: &[u8]) -> Option<T> {
+    core::str::from_utf8(param).ok()?.parse().ok()
+}
+
+/// Return `None` for the placeholder of an unknown field
+fn known<'a>(param: &'a [u8], placeholder: &[u8]) -> Option<&'a [u8]> {
+    Some(param).filter(|&param| param != placeholder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+    use crate::mode::ModeChange::*;
+
+    #[test]
+    fn ids() {
+        assert_eq!(sid(b"42X 1"), Ok((&b" 1"[..], Sid(*b"42X"))));
+        assert_eq!(Sid::parse(b"0AA"), Some(Sid(*b"0AA")));
+        assert_eq!(Sid::parse(b"A42"), None);
+        assert_eq!(Sid::parse(b"42x"), None);
+        assert_eq!(Sid::parse(b"42XA"), None);
+        let uid = Uid::parse(b"42XAB0C9Z").unwrap();
+        assert_eq!(uid.sid(), Sid(*b"42X"));
+        assert_eq!(uid.id(), b"AB0C9Z");
+        assert_eq!(Uid::parse(b"42X0AAAAA"), None);
+        assert_eq!(Uid::parse(b"42XAAAAA"), None);
+        assert_eq!(Uid::new(Sid(*b"42X"), *b"AB0C9Z"), Some(uid));
+        assert_eq!(Uid::new(Sid(*b"42X"), *b"AB0C9z"), None);
+    }
+
+    #[test]
+    fn generate_uids() {
+        let uids: Vec<_> = UidGenerator::after(Uid(*b"42XAAAAZ8")).take(4).collect();
+        assert_eq!(
+            uids,
+            vec![
+                Uid(*b"42XAAAAZ9"),
+                Uid(*b"42XAAAA0A"),
+                Uid(*b"42XAAAA0B"),
+                Uid(*b"42XAAAA0C")
+            ]
+        );
+        assert_eq!(UidGenerator::after(Uid(*b"42XZ99999")).next(), None);
+        let mut uids = UidGenerator::after(Uid(*b"42XA99999"));
+        assert_eq!(uids.next(), Some(Uid(*b"42XBAAAAA")));
+    }
+
+    #[test]
+    fn source() {
+        let source = |line: &'static [u8]| Source::from_message(&parse_message(line).unwrap());
+        assert_eq!(
+            source(b":42X PING :42X\r\n"),
+            Some(Source::Server(Sid(*b"42X")))
+        );
+        assert_eq!(
+            source(b":42XAAAAAB PRIVMSG #a :hi\r\n"),
+            Some(Source::User(Uid(*b"42XAAAAAB")))
+        );
+        assert_eq!(
+            source(b":irc.example.com PING :x\r\n"),
+            Some(Source::Name(b"irc.example.com"))
+        );
+        assert_eq!(
+            source(b":nick!u@h PING :x\r\n"),
+            Some(Source::Name(b"nick"))
+        );
+        assert_eq!(source(b"PING :x\r\n"), None);
+    }
+
+    #[test]
+    fn introductions() {
+        let line = b":42X SID services.example.com 1 00A :Services\r\n";
+        let message = parse_message(line).unwrap();
+        let sid = ServerMessage::from_message(&message).unwrap();
+        assert_eq!(
+            sid,
+            ServerMessage::Sid(ServerIntro {
+                name: b"services.example.com",
+                hops: 1,
+                sid: Sid(*b"00A"),
+                description: b"Services"
+            })
+        );
+        assert_eq!(sid.to_bytes(b"42X"), &line[..]);
+
+        let line = b":42X UID nick 1 1600000000 +iw user host 0 42XAAAAAB :Real Name\r\n";
+        let message = parse_message(line).unwrap();
+        let uid = ServerMessage::from_message(&message).unwrap();
+        match &uid {
+            ServerMessage::Uid(user) => {
+                assert_eq!(user.ts, 1600000000);
+                assert_eq!(user.ip, None);
+                assert_eq!(user.realname, b"Real Name");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(uid.to_bytes(b"42X"), &line[..]);
+
+        let message = parse_message(b":42X UID nick x 1 + u h 0 42XAAAAAB :r\r\n").unwrap();
+        assert_eq!(ServerMessage::from_message(&message), None);
+    }
+
+    #[test]
+    fn channels() {
+        let line = b":42X SJOIN 1500000000 #chan +ntkl key 10 :@+42XAAAAAB 42XAAAAAC\r\n";
+        let message = parse_message(line).unwrap();
+        let sjoin = match ServerMessage::from_message(&message) {
+            Some(ServerMessage::Sjoin(sjoin)) => sjoin,
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(
+            sjoin.members,
+            vec![
+                SjoinMember {
+                    prefixes: b"@+",
+                    uid: Uid(*b"42XAAAAAB")
+                },
+                SjoinMember {
+                    prefixes: b"",
+                    uid: Uid(*b"42XAAAAAC")
+                }
+            ]
+        );
+        let chanmodes = ChanModes::parse(b"beI,k,l,imnpst").unwrap();
+        assert_eq!(
+            sjoin.changes(&chanmodes, &PrefixModes::default())[2..],
+            [(Add, 'k', Some(&b"key"[..])), (Add, 'l', Some(&b"10"[..]))]
+        );
+        assert_eq!(ServerMessage::Sjoin(sjoin).to_bytes(b"42X"), &line[..]);
+
+        let line = b":42XAAAAAB TMODE 1500000000 #chan +o-v 42XAAAAAC 42XAAAAAC\r\n";
+        let message = parse_message(line).unwrap();
+        let tmode = ServerMessage::from_message(&message).unwrap();
+        assert_eq!(
+            tmode,
+            ServerMessage::Tmode(Tmode {
+                ts: 1500000000,
+                channel: b"#chan",
+                modes: vec![b"+o-v", b"42XAAAAAC", b"42XAAAAAC"]
+            })
+        );
+        assert_eq!(tmode.to_bytes(b"42XAAAAAB"), &line[..]);
+        let message = parse_message(b":42X TMODE 1500000000 #chan\r\n").unwrap();
+        assert_eq!(ServerMessage::from_message(&message), None);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::command::Command;
+use crate::message::Message;
+use alloc::vec::Vec;
+
+/// The maximum length of the base64 payload of one AUTHENTICATE message
+pub const CHUNK_LEN: usize = 400;
+
+/// The parameter of an AUTHENTICATE message
+/// The mechanism name sent by the client first is returned as `Data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Authenticate<'a> {
+    /// `+`: an empty payload or the end of a payload of a multiple of 400 bytes
+    Empty,
+    /// `*`: the client aborts the authentication
+    Abort,
+    /// A chunk of base64 encoded data
+    Data(&'a [u8]),
+}
+
+impl<'a> Authenticate<'a> {
+    /// Interpret an AUTHENTICATE message
+    /// Return `None` if it's not an AUTHENTICATE message or if it's malformed.
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        match message.command {
+            Command::Authenticate => (),
+            _ => return None,
+        }
+        match &message.params[..] {
+            [b"+"] => Some(Authenticate::Empty),
+            [b"*"] => Some(Authenticate::Abort),
+            [data] if !data.is_empty() && data.len() <= CHUNK_LEN => Some(Authenticate::Data(data)),
+            _ => None,
+        }
+    }
+}
+
+/// The payload is not valid base64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBase64;
+
+/// Reassemble a payload sent in multiple AUTHENTICATE messages
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PayloadBuffer {
+    encoded: Vec<u8>,
+}
+
+impl PayloadBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the parameter of an AUTHENTICATE message
+    /// Return the decoded payload once its last chunk arrived, a chunk shorter
+    /// than 400 bytes or a `+`, and start a new payload.
+    /// `Abort` discards the buffered chunks.
+    pub fn feed(&mut self, chunk: Authenticate<'_>) -> Result<Option<Vec<u8>>, InvalidBase64> {
+        match chunk {
+            Authenticate::Abort => {
+                self.encoded.clear();
+                Ok(None)
+            }
+            Authenticate::Data(data) if data.len() == CHUNK_LEN => {
+                self.encoded.extend_from_slice(data);
+                Ok(None)
+            }
+            Authenticate::Data(data) => {
+                self.encoded.extend_from_slice(data);
+                self.finish()
+            }
+            Authenticate::Empty => self.finish(),
+        }
+    }
+
+    fn finish(&mut self) -> Result<Option<Vec<u8>>, InvalidBase64> {
+        let encoded = core::mem::take(&mut self.encoded);
+        base64_decode(&encoded).map(Some)
+    }
+}
+
+/// Encode a payload and split it in the parameters of AUTHENTICATE messages
+/// An empty payload is sent as `+` and a payload whose encoding is a
+/// multiple of 400 bytes is followed by a `+`.
+pub fn chunks(payload: &[u8]) -> Vec<Vec<u8>> {
+    let encoded = base64_encode(payload);
+    let mut chunks: Vec<Vec<u8>> = encoded.chunks(CHUNK_LEN).map(<[u8]>::to_vec).collect();
+    if encoded.len().is_multiple_of(CHUNK_LEN) {
+        chunks.push(b"+".to_vec());
+    }
+    chunks
+}
+
+/// Build the AUTHENTICATE lines, crlf included, sending the payload
+pub fn authenticate_lines(payload: &[u8]) -> Vec<Vec<u8>> {
+    chunks(payload)
+        .into_iter()
+        .map(|chunk| [&b"AUTHENTICATE "[..], &chunk, b"\r\n"].concat())
+        .collect()
+}
+
+/// The PLAIN initial response: `authzid NUL authcid NUL password`
+/// An empty `authzid` means the same identity as `authcid`.
+pub fn plain(authzid: &[u8], authcid: &[u8], password: &[u8]) -> Vec<u8> {
+    [authzid, b"\0", authcid, b"\0", password].concat()
+}
+
+/// The EXTERNAL initial response: the optional authorization identity
+pub fn external(authzid: Option<&[u8]>) -> Vec<u8> {
+    authzid.unwrap_or_default().to_vec()
+}
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]);
+            } else {
+                output.push(b'=');
+            }
+        }
+    }
+    output
+}
+
+fn base64_decode(input: &[u8]) -> Result<Vec<u8>, InvalidBase64> {
+    if !input.len().is_multiple_of(4) {
+        return Err(InvalidBase64);
+    }
+    let mut output = Vec::with_capacity(input.len() / 4 * 3);
+    let last = input.len() / 4;
+    for (i, chunk) in input.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i + 1 != last) {
+            return Err(InvalidBase64);
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = ALPHABET.iter().position(|&a| a == c).ok_or(InvalidBase64)?;
+            n = n << 6 | value as u32;
+        }
+        n <<= 6 * padding as u32;
+        output.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn base64_roundtrip() {
+        for (plain, encoded) in &[
+            (&b""[..], &b""[..]),
+            (b"f", b"Zg=="),
+            (b"fo", b"Zm8="),
+            (b"foo", b"Zm9v"),
+            (b"foob", b"Zm9vYg=="),
+        ] {
+            assert_eq!(base64_encode(plain), encoded.to_vec());
+            assert_eq!(base64_decode(encoded), Ok(plain.to_vec()));
+        }
+        assert_eq!(base64_decode(b"Zm9"), Err(InvalidBase64));
+        assert_eq!(base64_decode(b"Zg==Zg=="), Err(InvalidBase64));
+        assert_eq!(base64_decode(b"Zm9!"), Err(InvalidBase64));
+    }
+
+    #[test]
+    fn authenticate_message() {
+        let msg = parse_message(b"AUTHENTICATE +\r\n").unwrap();
+        assert_eq!(Authenticate::from_message(&msg), Some(Authenticate::Empty));
+        let msg = parse_message(b"AUTHENTICATE *\r\n").unwrap();
+        assert_eq!(Authenticate::from_message(&msg), Some(Authenticate::Abort));
+        let msg = parse_message(b"AUTHENTICATE PLAIN\r\n").unwrap();
+        assert_eq!(
+            Authenticate::from_message(&msg),
+            Some(Authenticate::Data(b"PLAIN"))
+        );
+        let msg = parse_message(b"AUTHENTICATE\r\n").unwrap();
+        assert_eq!(Authenticate::from_message(&msg), None);
+    }
+
+    #[test]
+    fn chunking() {
+        assert_eq!(chunks(b""), vec![b"+".to_vec()]);
+        // 300 bytes encode to exactly 400 bytes
+        let payload = vec![b'a'; 300];
+        let split = chunks(&payload);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].len(), 400);
+        assert_eq!(split[1], b"+".to_vec());
+        let payload = vec![b'a'; 301];
+        let split = chunks(&payload);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[1].len(), 4);
+        assert_eq!(
+            authenticate_lines(b"foo"),
+            vec![b"AUTHENTICATE Zm9v\r\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn reassembly() {
+        for len in &[0, 1, 299, 300, 301, 600, 1000] {
+            let payload: Vec<u8> = (0..*len).map(|i| i as u8).collect();
+            let mut buffer = PayloadBuffer::new();
+            let mut result = None;
+            for line in authenticate_lines(&payload) {
+                let msg = parse_message(&line).unwrap();
+                assert!(result.is_none());
+                result = buffer
+                    .feed(Authenticate::from_message(&msg).unwrap())
+                    .unwrap();
+            }
+            assert_eq!(result, Some(payload));
+        }
+    }
+
+    #[test]
+    fn mechanisms() {
+        assert_eq!(plain(b"", b"user", b"pass"), b"\0user\0pass".to_vec());
+        assert_eq!(external(None), b"".to_vec());
+        assert_eq!(external(Some(b"admin")), b"admin".to_vec());
+    }
+}
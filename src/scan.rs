@@ -0,0 +1,50 @@
+//! Delimiter searches used to split lines, vectorized with the `memchr`
+//! feature
+
+/// Return the position of the first `needle` in `haystack`
+#[cfg(feature = "memchr")]
+pub(crate) fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    memchr::memchr(needle, haystack)
+}
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn find_byte(needle: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&c| c == needle)
+}
+
+/// Return the position of the first `a`, `b` or `c` in `haystack`
+#[cfg(feature = "memchr")]
+pub(crate) fn find_any(a: u8, b: u8, c: u8, haystack: &[u8]) -> Option<usize> {
+    memchr::memchr3(a, b, c, haystack)
+}
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn find_any(a: u8, b: u8, c: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&x| x == a || x == b || x == c)
+}
+
+/// Return the position of the first crlf in `haystack`
+#[cfg(feature = "memchr")]
+pub(crate) fn find_crlf(haystack: &[u8]) -> Option<usize> {
+    memchr::memmem::find(haystack, b"\r\n")
+}
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) fn find_crlf(haystack: &[u8]) -> Option<usize> {
+    haystack.windows(2).position(|w| w == b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_delimiters() {
+        assert_eq!(find_byte(b' ', b"PING :a b"), Some(4));
+        assert_eq!(find_byte(b'\n', b"PING"), None);
+        assert_eq!(find_any(0, b'\r', b'\n', b"a b\nc\r"), Some(3));
+        assert_eq!(find_any(0, b'\r', b'\n', b"a b"), None);
+        assert_eq!(find_crlf(b"PING\r :a\r\n"), Some(8));
+        assert_eq!(find_crlf(b"PING\r"), None);
+    }
+}
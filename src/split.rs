@@ -0,0 +1,323 @@
+use crate::command::Command;
+use crate::error::BuildError;
+use crate::format::{code_len, Style, COLOR, HEX_COLOR};
+use crate::isupport::ISupport;
+use crate::message::Message;
+use crate::params::Params;
+use crate::prefix::Prefix;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Split `text` in as many `PRIVMSG target :chunk\r\n` lines as needed so
+/// that every line still fits in `limit` bytes (512 in the RFC) once the
+/// server relays it with the `:prefix ` of the sender.
+/// The chunks never cut a UTF-8 sequence and are cut on the last space when
+/// possible, this space is then dropped.
+/// A chunk always contains at least one char even if the prefix and target
+/// leave no room for it.
+/// The formatting codes are never cut either, and the formatting still
+/// active at the end of a chunk is set again at the start of the next one.
+/// Return `BuildError::InvalidParam` if the target is not a valid middle
+/// parameter or if the text contains a NUL, CR or LF, as they would end the
+/// line.
+pub fn split_privmsg(
+    prefix: &Prefix,
+    target: &[u8],
+    text: &[u8],
+    limit: usize,
+) -> Result<Vec<Vec<u8>>, BuildError> {
+    let forbidden = |c: &u8| matches!(c, 0x00 | b'\r' | b'\n');
+    if target.is_empty() || target[0] == b':' || target.iter().any(|c| forbidden(c) || *c == b' ') {
+        return Err(BuildError::InvalidParam { index: 0 });
+    }
+    if text.iter().any(forbidden) {
+        return Err(BuildError::InvalidParam { index: 1 });
+    }
+    // `:prefix PRIVMSG target :` and the crlf
+    let overhead = 1 + prefix.to_bytes().len() + 1 + b"PRIVMSG ".len() + target.len() + 2 + 2;
+    let room = limit.saturating_sub(overhead);
+    let mut lines = Vec::new();
+    let mut style = Style::default();
+    let mut text = text;
+    loop {
+        let codes = style.codes(text);
+        let (chunk, rest) = next_chunk(text, room.saturating_sub(codes.len()));
+        style = style.apply(chunk);
+        let mut line = b"PRIVMSG ".to_vec();
+        line.extend_from_slice(target);
+        line.extend_from_slice(b" :");
+        line.extend_from_slice(&codes);
+        line.extend_from_slice(chunk);
+        line.extend_from_slice(b"\r\n");
+        lines.push(line);
+        text = rest;
+        if text.is_empty() {
+            return Ok(lines);
+        }
+    }
+}
+
+/// Split a message with more comma separated targets than the server
+/// allows, see `ISupport::max_targets`, in as many messages as needed and
+/// serialize them
+/// The targets are the first parameter, except for KICK whose targets are
+/// the nicks of its second parameter. The keys of a JOIN are split along its
+/// channels, and so are the channels of a KICK when there is one per nick.
+/// ```
+/// use irc_parser::isupport::ISupport;
+/// use irc_parser::message::parse_message;
+/// use irc_parser::split::split_targets;
+///
+/// let mut isupport = ISupport::new();
+/// isupport.apply(b"TARGMAX=JOIN:2");
+/// let join = parse_message(b"JOIN #a,#b,#c key\r\n").unwrap();
+/// assert_eq!(
+///     split_targets(&join, &isupport),
+///     vec![b"JOIN #a,#b key\r\n".to_vec(), b"JOIN #c\r\n".to_vec()]
+/// );
+/// ```
+pub fn split_targets(message: &Message, isupport: &ISupport) -> Vec<Vec<u8>> {
+    let index = match message.command {
+        Command::Kick => 1,
+        _ => 0,
+    };
+    let limit = isupport.max_targets(message.command.as_str().as_bytes());
+    let targets: Vec<&[u8]> = match message.params.get(index) {
+        Some(targets) => targets.split(|&c| c == b',').collect(),
+        None => Vec::new(),
+    };
+    let limit = match limit {
+        Some(limit) if limit > 0 && targets.len() > limit => limit,
+        _ => return vec![message.to_bytes()],
+    };
+    // the list whose items go along the targets: the keys of a JOIN or the
+    // channels of a KICK, which are only paired with the users one to one
+    let (paired_index, paired): (usize, Vec<&[u8]>) = match (&message.command, &message.params[..])
+    {
+        (Command::Join, [_, keys, ..]) => (1, keys.split(|&c| c == b',').collect()),
+        (Command::Kick, [channels, ..])
+            if channels.split(|&c| c == b',').count() == targets.len() =>
+        {
+            (0, channels.split(|&c| c == b',').collect())
+        }
+        _ => (index, Vec::new()),
+    };
+    targets
+        .chunks(limit)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let targets = chunk.join(&b',');
+            let paired = paired
+                .get(i * limit..paired.len().min((i + 1) * limit))
+                .unwrap_or_default()
+                .join(&b',');
+            let count = message.params.len();
+            let mut params = Params::new();
+            for (j, param) in message.params.iter().enumerate() {
+                let param: &[u8] = match (j, &message.command) {
+                    (j, _) if j == index => &targets,
+                    // the last channels may have no key
+                    (1, Command::Join) if paired.is_empty() => continue,
+                    (j, _) if j == paired_index => &paired,
+                    _ => param,
+                };
+                if j + 1 == count && message.params.has_trailing() {
+                    params.push_trailing(param);
+                } else {
+                    params.push(param);
+                }
+            }
+            let mut split = message.clone();
+            split.params = params;
+            split.to_bytes()
+        })
+        .collect()
+}
+
+/// Return the first chunk of the text and the text following it
+fn next_chunk(text: &[u8], room: usize) -> (&[u8], &[u8]) {
+    if text.len() <= room {
+        return (text, b"");
+    }
+    let mut end = room;
+    while end > 0 && is_continuation(text[end]) {
+        end -= 1;
+    }
+    // move the end before a color code it would cut
+    let code = (end.saturating_sub(13)..end)
+        .find(|&i| matches!(text[i], COLOR | HEX_COLOR) && i + code_len(&text[i..]) > end);
+    if let Some(start) = code {
+        end = start;
+    }
+    if end == 0 {
+        // not even one char fits, send it alone
+        end = match code_len(text) {
+            0 => {
+                1 + text[1..]
+                    .iter()
+                    .take_while(|&&c| is_continuation(c))
+                    .count()
+            }
+            len => len,
+        };
+    }
+    // a space right after the chunk is a fine place to cut too
+    let searched = &text[..text.len().min(end + 1)];
+    match searched.iter().rposition(|&c| c == b' ') {
+        Some(space) if space > 0 => (&text[..space], &text[space + 1..]),
+        _ => text.split_at(end),
+    }
+}
+
+fn is_continuation(c: u8) -> bool {
+    c & 0b1100_0000 == 0b1000_0000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREFIX: Prefix<'static> = Prefix::User {
+        nick: b"nick",
+        user: Some(b"user"),
+        host: Some(b"host"),
+    };
+
+    #[test]
+    fn split_short() {
+        assert_eq!(
+            split_privmsg(&PREFIX, b"#a", b"hello", 512).unwrap(),
+            vec![b"PRIVMSG #a :hello\r\n".to_vec()]
+        );
+        assert_eq!(
+            split_privmsg(&PREFIX, b"#a", b"", 512).unwrap(),
+            vec![b"PRIVMSG #a :\r\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn split_refuses_line_breaks() {
+        assert_eq!(
+            split_privmsg(&PREFIX, b"#a", b"hi\r\nQUIT :bye", 512),
+            Err(BuildError::InvalidParam { index: 1 })
+        );
+        assert_eq!(
+            split_privmsg(&PREFIX, b"#a", b"a\0b", 512),
+            Err(BuildError::InvalidParam { index: 1 })
+        );
+        for target in [&b"#a b"[..], b":a", b"", b"#a\n"].iter() {
+            assert_eq!(
+                split_privmsg(&PREFIX, target, b"hi", 512),
+                Err(BuildError::InvalidParam { index: 0 })
+            );
+        }
+    }
+
+    #[test]
+    fn split_on_spaces() {
+        // `:nick!user@host PRIVMSG #a :` and the crlf take 30 bytes
+        let lines = split_privmsg(&PREFIX, b"#a", b"aaaa bbbb cc", 39).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                b"PRIVMSG #a :aaaa bbbb\r\n".to_vec(),
+                b"PRIVMSG #a :cc\r\n".to_vec()
+            ]
+        );
+        let lines = split_privmsg(&PREFIX, b"#a", b"aaaaaaaaaaaa", 35).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                b"PRIVMSG #a :aaaaa\r\n".to_vec(),
+                b"PRIVMSG #a :aaaaa\r\n".to_vec(),
+                b"PRIVMSG #a :aa\r\n".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn split_utf8_boundaries() {
+        let text = "ééé".as_bytes();
+        let lines = split_privmsg(&PREFIX, b"#a", text, 35).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                "PRIVMSG #a :éé\r\n".as_bytes().to_vec(),
+                "PRIVMSG #a :é\r\n".as_bytes().to_vec()
+            ]
+        );
+        let lines = split_privmsg(&PREFIX, b"#a", text, 0).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "PRIVMSG #a :é\r\n".as_bytes().to_vec());
+    }
+
+    #[test]
+    fn split_formatting() {
+        // `:nick!user@host PRIVMSG #a :` and the crlf take 30 bytes
+        let lines = split_privmsg(&PREFIX, b"#a", b"\x02\x0304,12bold red\x02 red", 44).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                b"PRIVMSG #a :\x02\x0304,12bold\r\n".to_vec(),
+                b"PRIVMSG #a :\x02\x0304,12red\x02\r\n".to_vec(),
+                b"PRIVMSG #a :\x0304,12red\r\n".to_vec()
+            ]
+        );
+        // a color code is never cut
+        let lines = split_privmsg(&PREFIX, b"#a", b"aaa\x0304,12bb", 38).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                b"PRIVMSG #a :aaa\r\n".to_vec(),
+                b"PRIVMSG #a :\x0304,12bb\r\n".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn split_by_targmax() {
+        use crate::message::parse_message;
+
+        let mut isupport = ISupport::new();
+        isupport.apply(b"TARGMAX=PRIVMSG:2,KICK:1,JOIN:");
+        let privmsg = parse_message(b"@a=b PRIVMSG a,b,c,d,e :hello\r\n").unwrap();
+        assert_eq!(
+            split_targets(&privmsg, &isupport),
+            vec![
+                b"@a=b PRIVMSG a,b :hello\r\n".to_vec(),
+                b"@a=b PRIVMSG c,d :hello\r\n".to_vec(),
+                b"@a=b PRIVMSG e :hello\r\n".to_vec()
+            ]
+        );
+        let kick = parse_message(b"KICK #chan a,b :bye\r\n").unwrap();
+        assert_eq!(
+            split_targets(&kick, &isupport),
+            vec![
+                b"KICK #chan a :bye\r\n".to_vec(),
+                b"KICK #chan b :bye\r\n".to_vec()
+            ]
+        );
+        let kick = parse_message(b"KICK #a,#b u1,u2 :bye\r\n").unwrap();
+        assert_eq!(
+            split_targets(&kick, &isupport),
+            vec![
+                b"KICK #a u1 :bye\r\n".to_vec(),
+                b"KICK #b u2 :bye\r\n".to_vec()
+            ]
+        );
+        let kick = parse_message(b"KICK #a,#b u1,u2,u3 :bye\r\n").unwrap();
+        assert_eq!(
+            split_targets(&kick, &isupport),
+            vec![
+                b"KICK #a,#b u1 :bye\r\n".to_vec(),
+                b"KICK #a,#b u2 :bye\r\n".to_vec(),
+                b"KICK #a,#b u3 :bye\r\n".to_vec()
+            ]
+        );
+        // no limit
+        let join = parse_message(b"JOIN #a,#b,#c\r\n").unwrap();
+        assert_eq!(split_targets(&join, &isupport), vec![join.to_bytes()]);
+        let notice = parse_message(b"NOTICE a,b,c :hi\r\n").unwrap();
+        assert_eq!(split_targets(&notice, &isupport), vec![notice.to_bytes()]);
+    }
+}
@@ -0,0 +1,793 @@
+//! Follow the state of the connection from the messages received
+//! Every client needs to know who is in its channels: feed every message to
+//! a `ChannelTracker` and query it. A `UserTracker` follows who the users
+//! are across their nick changes.
+//! ```
+//! use irc_parser::message::parse_all;
+//! use irc_parser::state::ChannelTracker;
+//!
+//! let input = b":me!u@h JOIN #rust\r\n\
+//!     :irc 353 me = #rust :me @op\r\n\
+//!     :irc 366 me #rust :End of /NAMES list.\r\n\
+//!     :op!u@h MODE #rust +v me\r\n";
+//! let mut tracker = ChannelTracker::new(b"me");
+//! for message in parse_all(input) {
+//!     tracker.feed(&message.unwrap());
+//! }
+//! let members: Vec<_> = tracker.members("#rust").map(|m| (&m.prefixes[..], &m.nick[..])).collect();
+//! assert_eq!(members, vec![(&b"+"[..], &b"me"[..]), (b"@", b"op")]);
+//! ```
+
+use crate::casemap::{CaseMapping, IrcKey};
+use crate::command::Command;
+use crate::isupport::ISupport;
+use crate::message::Message;
+use crate::mode::{channel_modes_with, ModeChange, PrefixModes};
+use crate::names::Names;
+use crate::numeric::Numeric;
+use crate::prefix::Prefix;
+use crate::typed::{Account, Chghost, Nick, Setname, TypedMessage};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// A member of a tracked channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelMember {
+    pub nick: Vec<u8>,
+    /// The membership prefixes, highest first: `@+`
+    pub prefixes: Vec<u8>,
+}
+
+/// The topic of a channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicState {
+    pub text: Vec<u8>,
+    /// The nick or full prefix of who set the topic
+    pub set_by: Option<Vec<u8>>,
+    /// When the topic was set, in seconds since the epoch
+    pub set_at: Option<u64>,
+}
+
+/// A channel the tracked client is in
+#[derive(Debug, Clone)]
+pub struct ChannelState {
+    name: Vec<u8>,
+    members: BTreeMap<IrcKey<'static>, ChannelMember>,
+    topic: Option<TopicState>,
+    /// A RPL_NAMREPLY arrived and its RPL_ENDOFNAMES didn't yet
+    receiving_names: bool,
+}
+
+impl ChannelState {
+    fn new(name: &[u8]) -> Self {
+        ChannelState {
+            name: name.to_vec(),
+            members: BTreeMap::new(),
+            topic: None,
+            receiving_names: false,
+        }
+    }
+
+    /// Return the name of the channel as sent in the JOIN
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// Return the members ordered by nick
+    pub fn members(&self) -> impl Iterator<Item = &ChannelMember> + '_ {
+        self.members.values()
+    }
+
+    /// Return the topic, `None` if the channel has none or the server didn't
+    /// send it yet
+    pub fn topic(&self) -> Option<&TopicState> {
+        self.topic.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// The channels of a client and their members, updated by JOIN, PART, KICK,
+/// QUIT, NICK, MODE and the NAMES replies
+/// The topics are updated by TOPIC, RPL_NOTOPIC, RPL_TOPIC and
+/// RPL_TOPICWHOTIME.
+/// RPL_WELCOME and RPL_ISUPPORT are followed too, for the nick of the client,
+/// the case mapping and the membership prefixes.
+#[derive(Debug, Clone)]
+pub struct ChannelTracker {
+    nick: Vec<u8>,
+    isupport: ISupport,
+    channels: BTreeMap<IrcKey<'static>, ChannelState>,
+}
+
+impl ChannelTracker {
+    /// Track the channels of the client using `nick`
+    pub fn new(nick: &[u8]) -> Self {
+        ChannelTracker {
+            nick: nick.to_vec(),
+            isupport: ISupport::new(),
+            channels: BTreeMap::new(),
+        }
+    }
+
+    /// Return the current nick of the client
+    pub fn nick(&self) -> &[u8] {
+        &self.nick
+    }
+
+    /// Return the RPL_ISUPPORT tokens received so far
+    pub fn isupport(&self) -> &ISupport {
+        &self.isupport
+    }
+
+    /// Return the channels the client is in, ordered by name
+    pub fn channels(&self) -> impl Iterator<Item = &ChannelState> + '_ {
+        self.channels.values()
+    }
+
+    pub fn channel<N: AsRef<[u8]> + ?Sized>(&self, name: &N) -> Option<&ChannelState> {
+        self.channels.get(&self.key(name.as_ref()))
+    }
+
+    /// Return the members of a channel, none if the client is not in it
+    pub fn members<N: AsRef<[u8]> + ?Sized>(
+        &self,
+        channel: &N,
+    ) -> impl Iterator<Item = &ChannelMember> + '_ {
+        self.channel(channel)
+            .into_iter()
+            .flat_map(|state| state.members())
+    }
+
+    pub fn member<N, M>(&self, channel: &N, nick: &M) -> Option<&ChannelMember>
+    where
+        N: AsRef<[u8]> + ?Sized,
+        M: AsRef<[u8]> + ?Sized,
+    {
+        let channel = self.channel(channel)?;
+        channel.members.get(&self.key(nick.as_ref()))
+    }
+
+    /// Update the state with a message received from the server, the
+    /// messages changing nothing are ignored
+    pub fn feed(&mut self, message: &Message) {
+        let mapping = self.mapping();
+        if self.isupport.update(message) {
+            if self.mapping() != mapping {
+                let mapping = self.mapping();
+                let channels = core::mem::take(&mut self.channels);
+                self.channels = rekey(channels, mapping);
+                for channel in self.channels.values_mut() {
+                    let members = core::mem::take(&mut channel.members);
+                    channel.members = rekey(members, mapping);
+                }
+            }
+            return;
+        }
+        let params = &message.params[..];
+        match (message.command, params) {
+            (Command::Join, [channels, ..]) => {
+                let nick = match source_nick(message) {
+                    Some(nick) => nick,
+                    None => return,
+                };
+                for channel in channels.split(|&c| c == b',') {
+                    if self.is_me(nick) {
+                        let key = self.key(channel);
+                        self.channels.insert(key, ChannelState::new(channel));
+                    }
+                    self.add_member(channel, nick, Vec::new());
+                }
+            }
+            (Command::Part, [channels, ..]) => {
+                let nick = match source_nick(message) {
+                    Some(nick) => nick,
+                    None => return,
+                };
+                for channel in channels.split(|&c| c == b',') {
+                    self.remove_member(channel, nick);
+                }
+            }
+            (Command::Kick, [channels, users, ..]) => {
+                let channels: Vec<_> = channels.split(|&c| c == b',').collect();
+                for (i, nick) in users.split(|&c| c == b',').enumerate() {
+                    // a single channel applies to every user
+                    match channels.get(i).or_else(|| channels.last()) {
+                        Some(channel) => self.remove_member(channel, nick),
+                        None => return,
+                    }
+                }
+            }
+            (Command::Quit, _) => match source_nick(message) {
+                Some(nick) if self.is_me(nick) => self.channels.clear(),
+                Some(nick) => {
+                    let key = self.key(nick);
+                    for channel in self.channels.values_mut() {
+                        channel.members.remove(&key);
+                    }
+                }
+                None => (),
+            },
+            (Command::Nick, [new, ..]) => {
+                let old = match source_nick(message) {
+                    Some(nick) => nick,
+                    None => return,
+                };
+                if self.is_me(old) {
+                    self.nick = new.to_vec();
+                }
+                let (old, new_key) = (self.key(old), self.key(new));
+                for channel in self.channels.values_mut() {
+                    if let Some(mut member) = channel.members.remove(&old) {
+                        member.nick = new.to_vec();
+                        channel.members.insert(new_key.clone(), member);
+                    }
+                }
+            }
+            (Command::Mode, [target, modes @ ..]) => self.apply_modes(target, modes),
+            (Command::Topic, [channel, text]) => {
+                let set_by = message.prefix.as_ref().map(|prefix| prefix.to_bytes());
+                // the server time is the best guess of when it was set
+                let set_at = message.tags.time().map(|time| time.unix_millis() / 1000);
+                let topic = match text {
+                    [] => None,
+                    text => Some(TopicState {
+                        text: text.to_vec(),
+                        set_by,
+                        set_at: set_at.and_then(|time| u64::try_from(time).ok()),
+                    }),
+                };
+                if let Some(channel) = self.channel_mut(channel) {
+                    channel.topic = topic;
+                }
+            }
+            (Command::Numeric(_), _) => self.feed_numeric(message),
+            _ => (),
+        }
+    }
+
+    fn feed_numeric(&mut self, message: &Message) {
+        match (message.command.numeric(), &message.params[..]) {
+            (Some(Numeric::RplWelcome), [nick, ..]) => self.nick = nick.to_vec(),
+            (Some(Numeric::RplNoTopic), [_, channel, ..]) => {
+                if let Some(channel) = self.channel_mut(channel) {
+                    channel.topic = None;
+                }
+            }
+            (Some(Numeric::RplTopic), [_, channel, text]) => {
+                if let Some(channel) = self.channel_mut(channel) {
+                    channel.topic = Some(TopicState {
+                        text: text.to_vec(),
+                        set_by: None,
+                        set_at: None,
+                    });
+                }
+            }
+            (Some(Numeric::RplTopicWhoTime), [_, channel, set_by, set_at]) => {
+                let set_at = core::str::from_utf8(set_at)
+                    .ok()
+                    .and_then(|n| n.parse().ok());
+                let topic = self
+                    .channel_mut(channel)
+                    .and_then(|channel| channel.topic.as_mut());
+                if let Some(topic) = topic {
+                    topic.set_by = Some(set_by.to_vec());
+                    topic.set_at = set_at;
+                }
+            }
+            (Some(Numeric::RplNamReply), _) => self.add_names(message),
+            (Some(Numeric::RplEndOfNames), [_, channel, ..]) => {
+                if let Some(channel) = self.channel_mut(channel) {
+                    channel.receiving_names = false;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn add_names(&mut self, message: &Message) {
+        let prefixes = self.isupport.prefixes();
+        let names = match Names::from_message_with(message, &prefixes.symbols()) {
+            Some(names) => names,
+            None => return,
+        };
+        let mapping = self.mapping();
+        let channel = match self.channel_mut(names.channel) {
+            Some(channel) => channel,
+            None => return,
+        };
+        // a new NAMES replaces the members
+        if !channel.receiving_names {
+            channel.members.clear();
+            channel.receiving_names = true;
+        }
+        for member in names.members {
+            let mut symbols = member.prefixes.to_vec();
+            sort_prefixes(&mut symbols, &prefixes);
+            channel.members.insert(
+                IrcKey::new(member.nick.to_vec(), mapping),
+                ChannelMember {
+                    nick: member.nick.to_vec(),
+                    prefixes: symbols,
+                },
+            );
+        }
+    }
+
+    fn channel_mut(&mut self, name: &[u8]) -> Option<&mut ChannelState> {
+        let key = self.key(name);
+        self.channels.get_mut(&key)
+    }
+
+    fn apply_modes(&mut self, target: &[u8], modes: &[&[u8]]) {
+        let (chanmodes, prefixes) = (self.isupport.chanmodes(), self.isupport.prefixes());
+        let key = self.key(target);
+        let mapping = self.mapping();
+        let channel = match self.channels.get_mut(&key) {
+            Some(channel) => channel,
+            None => return,
+        };
+        for (change, mode, arg) in channel_modes_with(modes, &chanmodes, &prefixes) {
+            let (symbol, nick) = match (prefixes.symbol(mode), arg) {
+                (Some(symbol), Some(nick)) => (symbol, nick),
+                _ => continue,
+            };
+            let member = match channel
+                .members
+                .get_mut(&IrcKey::new(nick.to_vec(), mapping))
+            {
+                Some(member) => member,
+                None => continue,
+            };
+            member.prefixes.retain(|&c| c != symbol);
+            if change == ModeChange::Add {
+                member.prefixes.push(symbol);
+                sort_prefixes(&mut member.prefixes, &prefixes);
+            }
+        }
+    }
+
+    fn add_member(&mut self, channel: &[u8], nick: &[u8], prefixes: Vec<u8>) {
+        let (key, nick_key) = (self.key(channel), self.key(nick));
+        if let Some(channel) = self.channels.get_mut(&key) {
+            let nick = nick.to_vec();
+            channel
+                .members
+                .insert(nick_key, ChannelMember { nick, prefixes });
+        }
+    }
+
+    fn remove_member(&mut self, channel: &[u8], nick: &[u8]) {
+        let (key, nick_key) = (self.key(channel), self.key(nick));
+        if self.is_me(nick) {
+            self.channels.remove(&key);
+        } else if let Some(channel) = self.channels.get_mut(&key) {
+            channel.members.remove(&nick_key);
+        }
+    }
+
+    fn is_me(&self, nick: &[u8]) -> bool {
+        self.mapping().eq_ignore_case(nick, &self.nick)
+    }
+
+    fn mapping(&self) -> CaseMapping {
+        self.isupport.casemapping.unwrap_or_default()
+    }
+
+    fn key(&self, name: &[u8]) -> IrcKey<'static> {
+        IrcKey::new(name.to_vec(), self.mapping())
+    }
+}
+
+/// What is known about a user
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub nick: Vec<u8>,
+    pub user: Option<Vec<u8>>,
+    pub host: Option<Vec<u8>>,
+    /// Only known from an extended JOIN or a SETNAME
+    pub realname: Option<Vec<u8>>,
+    /// The services account, `None` when logged out or unknown
+    pub account: Option<Vec<u8>>,
+    /// The away message, `None` when the user is here
+    pub away: Option<Vec<u8>>,
+}
+
+impl Identity {
+    fn new(nick: &[u8]) -> Self {
+        Identity {
+            nick: nick.to_vec(),
+            user: None,
+            host: None,
+            realname: None,
+            account: None,
+            away: None,
+        }
+    }
+}
+
+/// A change of the identity of a user reported by `UserTracker::feed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserEvent {
+    /// NICK
+    Renamed { old: Vec<u8>, new: Vec<u8> },
+    /// QUIT, the user is forgotten
+    Quit { nick: Vec<u8> },
+    /// ACCOUNT with account-notify, or the account of an extended-join
+    Account {
+        nick: Vec<u8>,
+        account: Option<Vec<u8>>,
+    },
+    /// CHGHOST
+    HostChanged {
+        nick: Vec<u8>,
+        user: Vec<u8>,
+        host: Vec<u8>,
+    },
+    /// SETNAME
+    RealnameChanged { nick: Vec<u8>, realname: Vec<u8> },
+    /// AWAY with away-notify, the message is `None` when the user is back
+    Away {
+        nick: Vec<u8>,
+        message: Option<Vec<u8>>,
+    },
+}
+
+/// The identity of every user seen, by current nick
+/// The users are learned from the prefixes of their messages and the
+/// `account` tag, then followed through NICK, QUIT, ACCOUNT, CHGHOST,
+/// SETNAME, AWAY and the extended JOIN. Nothing tells when a user stops
+/// sharing a channel with the client, `forget` them when needed.
+/// ```
+/// use irc_parser::message::parse_all;
+/// use irc_parser::state::{UserEvent, UserTracker};
+///
+/// let input = b":bob!b@host JOIN #rust bob_account :Bob\r\n\
+///     :bob!b@host NICK robert\r\n";
+/// let mut tracker = UserTracker::new();
+/// let events: Vec<_> = parse_all(input).filter_map(|m| tracker.feed(&m.unwrap())).collect();
+/// assert_eq!(events[1], UserEvent::Renamed { old: b"bob".to_vec(), new: b"robert".to_vec() });
+/// let robert = tracker.get("Robert").unwrap();
+/// assert_eq!(robert.realname.as_deref(), Some(&b"Bob"[..]));
+/// assert_eq!(robert.account.as_deref(), Some(&b"bob_account"[..]));
+/// assert_eq!(robert.host.as_deref(), Some(&b"host"[..]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UserTracker {
+    mapping: CaseMapping,
+    users: BTreeMap<IrcKey<'static>, Identity>,
+}
+
+impl UserTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get<N: AsRef<[u8]> + ?Sized>(&self, nick: &N) -> Option<&Identity> {
+        self.users.get(&self.key(nick.as_ref()))
+    }
+
+    /// Return every known user, ordered by nick
+    pub fn users(&self) -> impl Iterator<Item = &Identity> + '_ {
+        self.users.values()
+    }
+
+    /// Stop following a user, when it left the last shared channel
+    pub fn forget<N: AsRef<[u8]> + ?Sized>(&mut self, nick: &N) -> Option<Identity> {
+        let key = self.key(nick.as_ref());
+        self.users.remove(&key)
+    }
+
+    /// Update the identities with a message received from the server and
+    /// return the change it made, if any
+    /// The case mapping of RPL_ISUPPORT is followed.
+    pub fn feed(&mut self, message: &Message) -> Option<UserEvent> {
+        if let Some(mapping) = CaseMapping::from_message(message) {
+            if mapping != self.mapping {
+                self.mapping = mapping;
+                self.users = rekey(core::mem::take(&mut self.users), mapping);
+            }
+            return None;
+        }
+        let (nick, user, host) = match message.prefix {
+            Some(Prefix::User { nick, user, host }) => (nick, user, host),
+            _ => return None,
+        };
+        let key = self.key(nick);
+        let identity = self
+            .users
+            .entry(key.clone())
+            .or_insert_with(|| Identity::new(nick));
+        if let Some(user) = user {
+            identity.user = Some(user.to_vec());
+        }
+        if let Some(host) = host {
+            identity.host = Some(host.to_vec());
+        }
+        if let Some(account) = message.tags.account() {
+            identity.account = Some(account.as_bytes().to_vec());
+        }
+        let nick = identity.nick.clone();
+        match message.typed() {
+            TypedMessage::Nick(Nick { nick: new }) => {
+                let mut identity = self.users.remove(&key)?;
+                identity.nick = new.to_vec();
+                self.users.insert(self.key(new), identity);
+                Some(UserEvent::Renamed {
+                    old: nick,
+                    new: new.to_vec(),
+                })
+            }
+            TypedMessage::Quit(_) => {
+                self.users.remove(&key);
+                Some(UserEvent::Quit { nick })
+            }
+            TypedMessage::Chghost(Chghost { user, host }) => {
+                identity.user = Some(user.to_vec());
+                identity.host = Some(host.to_vec());
+                Some(UserEvent::HostChanged {
+                    nick,
+                    user: user.to_vec(),
+                    host: host.to_vec(),
+                })
+            }
+            TypedMessage::Setname(Setname { realname }) => {
+                identity.realname = Some(realname.to_vec());
+                Some(UserEvent::RealnameChanged {
+                    nick,
+                    realname: realname.to_vec(),
+                })
+            }
+            TypedMessage::Away(away) => {
+                let message = away.message.filter(|_| away.is_away()).map(<[u8]>::to_vec);
+                identity.away = message.clone();
+                Some(UserEvent::Away { nick, message })
+            }
+            TypedMessage::Account(Account { account }) => {
+                let account = account.map(<[u8]>::to_vec);
+                identity.account = account.clone();
+                Some(UserEvent::Account { nick, account })
+            }
+            TypedMessage::Join(join) if join.is_extended() => {
+                let account = join.account().map(<[u8]>::to_vec);
+                identity.account = account.clone();
+                identity.realname = join.realname().map(<[u8]>::to_vec);
+                Some(UserEvent::Account { nick, account })
+            }
+            _ => None,
+        }
+    }
+
+    fn key(&self, nick: &[u8]) -> IrcKey<'static> {
+        IrcKey::new(nick.to_vec(), self.mapping)
+    }
+}
+
+/// Return the nick of the user who sent the message
+fn source_nick<'a>(message: &Message<'a>) -> Option<&'a [u8]> {
+    match message.prefix {
+        Some(Prefix::User { nick, .. }) => Some(nick),
+        _ => None,
+    }
+}
+
+/// Fold the keys of a map with a new case mapping, the names equal with the
+/// new mapping are merged
+fn rekey<V>(
+    map: BTreeMap<IrcKey<'static>, V>,
+    mapping: CaseMapping,
+) -> BTreeMap<IrcKey<'static>, V> {
+    map.into_iter()
+        .map(|(key, value)| (IrcKey::new(key.as_bytes().to_vec(), mapping), value))
+        .collect()
+}
+
+/// Order the prefixes of a member, highest first
+fn sort_prefixes(symbols: &mut [u8], prefixes: &PrefixModes) {
+    symbols.sort_by_key(|&c| prefixes.prefix(c).map_or(usize::MAX, |prefix| prefix.rank));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_all;
+
+    fn tracker(input: &[u8]) -> ChannelTracker {
+        let mut tracker = ChannelTracker::new(b"me");
+        for message in parse_all(input) {
+            tracker.feed(&message.unwrap());
+        }
+        tracker
+    }
+
+    fn nicks(tracker: &ChannelTracker, channel: &str) -> Vec<Vec<u8>> {
+        tracker.members(channel).map(|m| m.nick.clone()).collect()
+    }
+
+    #[test]
+    fn track_join_part() {
+        let tracker = tracker(
+            b":irc 001 Me :Welcome\r\n\
+            :irc 005 Me PREFIX=(qov)~@+ CASEMAPPING=ascii :are supported\r\n\
+            :Me!u@h JOIN #a\r\n\
+            :Me!u@h JOIN #b\r\n\
+            :irc 353 Me = #a :~@Me +bob carl\r\n\
+            :irc 366 Me #a :End of /NAMES list.\r\n\
+            :dan!u@h JOIN #A\r\n\
+            :carl!u@h PART #a :bye\r\n\
+            :Me!u@h PART #b\r\n\
+            :x!u@h JOIN #c\r\n",
+        );
+        assert_eq!(tracker.nick(), b"Me");
+        let channels: Vec<_> = tracker.channels().map(ChannelState::name).collect();
+        assert_eq!(channels, vec![&b"#a"[..]]);
+        assert_eq!(
+            nicks(&tracker, "#a"),
+            vec![b"bob".to_vec(), b"dan".to_vec(), b"Me".to_vec()]
+        );
+        assert_eq!(tracker.member("#a", "me").unwrap().prefixes, b"~@");
+        assert_eq!(tracker.members("#c").count(), 0);
+    }
+
+    #[test]
+    fn track_kick_quit_nick() {
+        let tracker = tracker(
+            b":me!u@h JOIN #a\r\n\
+            :me!u@h JOIN #b\r\n\
+            :irc 353 me = #a :@me a b c\r\n\
+            :irc 353 me = #a :d\r\n\
+            :irc 366 me #a :End of /NAMES list.\r\n\
+            :irc 353 me = #b :me a b\r\n\
+            :irc 366 me #b :End of /NAMES list.\r\n\
+            :me!u@h KICK #a c :out\r\n\
+            :d!u@h QUIT :gone\r\n\
+            :a!u@h QUIT :gone\r\n\
+            :b!u@h NICK B[2]\r\n\
+            :me!u@h NICK me2\r\n",
+        );
+        assert_eq!(tracker.nick(), b"me2");
+        assert_eq!(
+            nicks(&tracker, "#a"),
+            vec![b"B[2]".to_vec(), b"me2".to_vec()]
+        );
+        assert!(tracker.member("#b", "b{2}").is_some());
+        assert_eq!(tracker.member("#a", "me2").unwrap().prefixes, b"@");
+    }
+
+    #[test]
+    fn track_modes_and_names_refresh() {
+        let mut tracker = tracker(
+            b":me!u@h JOIN #a\r\n\
+            :irc 353 me = #a :me a\r\n\
+            :irc 366 me #a :End of /NAMES list.\r\n\
+            :me!u@h MODE #a +vo-v+b a me a *!*@h\r\n",
+        );
+        assert_eq!(tracker.member("#a", "a").unwrap().prefixes, b"");
+        assert_eq!(tracker.member("#a", "me").unwrap().prefixes, b"@");
+        for message in parse_all(
+            b":irc 353 me = #a :+@me\r\n\
+            :irc 366 me #a :End of /NAMES list.\r\n\
+            :me!u@h KICK #a me\r\n",
+        ) {
+            let message = message.unwrap();
+            tracker.feed(&message);
+            if message.command.numeric() == Some(Numeric::RplEndOfNames) {
+                assert_eq!(nicks(&tracker, "#a"), vec![b"me".to_vec()]);
+                assert_eq!(tracker.member("#a", "me").unwrap().prefixes, b"@+");
+            }
+        }
+        assert!(tracker.channel("#a").is_none());
+    }
+
+    #[test]
+    fn track_users() {
+        let mut tracker = UserTracker::new();
+        let input = b":irc 005 me CASEMAPPING=ascii :are supported\r\n\
+            @account=acc :a!u@h PRIVMSG #chan :hi\r\n\
+            :a!u@h AWAY :lunch\r\n\
+            :a!u@h CHGHOST u2 h2\r\n\
+            :a!u2@h2 SETNAME :Real A\r\n\
+            :a!u2@h2 ACCOUNT *\r\n\
+            :a!u2@h2 AWAY :\r\n\
+            :b!u@h QUIT :bye\r\n";
+        let events: Vec<_> = parse_all(input)
+            .filter_map(|m| tracker.feed(&m.unwrap()))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                UserEvent::Away {
+                    nick: b"a".to_vec(),
+                    message: Some(b"lunch".to_vec())
+                },
+                UserEvent::HostChanged {
+                    nick: b"a".to_vec(),
+                    user: b"u2".to_vec(),
+                    host: b"h2".to_vec()
+                },
+                UserEvent::RealnameChanged {
+                    nick: b"a".to_vec(),
+                    realname: b"Real A".to_vec()
+                },
+                UserEvent::Account {
+                    nick: b"a".to_vec(),
+                    account: None
+                },
+                UserEvent::Away {
+                    nick: b"a".to_vec(),
+                    message: None
+                },
+                UserEvent::Quit {
+                    nick: b"b".to_vec()
+                },
+            ]
+        );
+        let a = tracker.get("A").unwrap();
+        assert_eq!(a.user.as_deref(), Some(&b"u2"[..]));
+        assert_eq!(a.account, None);
+        assert_eq!(a.away, None);
+        assert!(tracker.get("b").is_none());
+        assert!(tracker.forget("a").is_some());
+        assert_eq!(tracker.users().count(), 0);
+        // ascii doesn't fold the brackets
+        tracker.feed(&crate::message::parse_message(b":x[1]!u@h PART #chan\r\n").unwrap());
+        assert!(tracker.get("x[1]").is_some() && tracker.get("x{1}").is_none());
+    }
+
+    #[test]
+    fn track_casemapping_change() {
+        let input = b":me!u@h JOIN #a[b]\r\n\
+            :Nick[a]!u@h JOIN #a[b]\r\n\
+            :irc 005 me CASEMAPPING=ascii :are supported\r\n";
+        let tracker = tracker(input);
+        assert!(tracker.member("#A[b]", "nick[A]").is_some());
+        assert!(tracker.channel("#a{b}").is_none());
+
+        let mut users = UserTracker::new();
+        for message in parse_all(input) {
+            users.feed(&message.unwrap());
+        }
+        assert!(users.get("Nick[a]").is_some());
+        assert!(users.get("nick{a}").is_none());
+        assert!(users.forget("NICK[A]").is_some());
+    }
+
+    #[test]
+    fn track_topic() {
+        let mut tracker = tracker(
+            b":me!u@h JOIN #a\r\n\
+            :me!u@h JOIN #b\r\n\
+            :irc 332 me #a :the topic\r\n\
+            :irc 333 me #a op!u@h 1600000000\r\n\
+            :irc 331 me #b :No topic is set\r\n\
+            :irc 332 me #c :not tracked\r\n",
+        );
+        assert_eq!(
+            tracker.channel("#a").unwrap().topic(),
+            Some(&TopicState {
+                text: b"the topic".to_vec(),
+                set_by: Some(b"op!u@h".to_vec()),
+                set_at: Some(1600000000),
+            })
+        );
+        assert_eq!(tracker.channel("#b").unwrap().topic(), None);
+        for message in parse_all(
+            b"@time=2020-09-13T12:26:40.000Z :op!u@h TOPIC #b :new\r\n\
+            :op!u@h TOPIC #a :\r\n",
+        ) {
+            tracker.feed(&message.unwrap());
+        }
+        let topic = tracker.channel("#b").unwrap().topic().unwrap();
+        assert_eq!(topic.text, b"new");
+        assert_eq!(topic.set_by.as_deref(), Some(&b"op!u@h"[..]));
+        assert_eq!(topic.set_at, Some(1600000000));
+        assert_eq!(tracker.channel("#a").unwrap().topic(), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Streaming version of the parsers
+//! Instead of failing when the input ends too early they return
+//! `Err::Incomplete(Needed)`, so the caller can read more data and retry.
+
+use crate::command::Command;
+use crate::config::ParserConfig;
+use crate::message::Message;
+use crate::params::Params;
+use crate::prefix::Prefix;
+use crate::recover::{parse_or_recover, Parsed};
+use crate::scan::find_crlf;
+use nom::{Err, IResult, Needed};
+
+/// Streaming version of `parser::space`
+/// Return `Incomplete` if the input only contains spaces.
+pub fn space(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    nom::bytes::streaming::take_while1(|item| item == b' ')(input)
+}
+
+/// Streaming version of `parser::crlf`
+/// Return `Incomplete` if the input is empty or only contains a CR.
+pub fn crlf(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    nom::character::streaming::crlf(input)
+}
+
+/// Streaming version of `parser::one_char`
+pub fn one_char(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    one(crate::parser::one_char)(input)
+}
+
+/// Streaming version of `parser::letter`
+pub fn letter(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    one(crate::parser::letter)(input)
+}
+
+/// Streaming version of `parser::number`
+pub fn number(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    one(crate::parser::number)(input)
+}
+
+/// Streaming version of `parser::special`
+pub fn special(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    one(crate::parser::special)(input)
+}
+
+/// Streaming version of `parser::nonwhite`
+pub fn nonwhite(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    one(crate::parser::nonwhite)(input)
+}
+
+/// Streaming version of `prefix::prefix`
+/// Return `Incomplete` if the prefix could continue after the input.
+pub fn prefix(input: &[u8]) -> IResult<&[u8], Prefix<'_>> {
+    until_end(crate::prefix::prefix)(input)
+}
+
+/// Streaming version of `command::command`
+/// Return `Incomplete` if the command could continue after the input.
+pub fn command(input: &[u8]) -> IResult<&[u8], Command<'_>> {
+    until_end(crate::command::command)(input)
+}
+
+/// Streaming version of `params::params`
+/// Return `Incomplete` if more parameters could follow the input.
+pub fn params(input: &[u8]) -> IResult<&[u8], Params<'_>> {
+    until_end(crate::params::params)(input)
+}
+
+/// Streaming version of `message::message`
+/// Return `Incomplete` until a whole line terminated by a crlf is available.
+pub fn message(input: &[u8]) -> IResult<&[u8], Message<'_>> {
+    match find_crlf(input) {
+        Some(_) => crate::message::message(input),
+        None if input.last() == Some(&b'\r') => Err(Err::Incomplete(Needed::Size(1))),
+        None => Err(Err::Incomplete(Needed::Unknown)),
+    }
+}
+
+/// Resilient version of `message`
+/// A malformed line is consumed and returned as `Parsed::Recovered` instead
+/// of failing, so the next call starts on the following line.
+pub fn message_or_recover(input: &[u8]) -> IResult<&[u8], Parsed<'_>> {
+    message_or_recover_with(input, &ParserConfig::default())
+}
+
+/// Same as `message_or_recover` but with a custom configuration
+/// Once more than `max_tags_len + max_line_len` bytes are available without
+/// a line ending they are consumed and returned as `Parsed::Recovered` with
+/// `ParseError::TagsTooLong` or `ParseError::LineTooLong`, the caller must
+/// then drop the rest of the line with `skip_line_with`.
+pub fn message_or_recover_with<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> IResult<&'a [u8], Parsed<'a>> {
+    match config.line_len(input) {
+        Some(len) => {
+            let (line, rest) = input.split_at(len);
+            Ok((rest, parse_or_recover(line, config)))
+        }
+        None if input.len() > config.max_len() => {
+            // keep a last CR, it could be the start of the line ending
+            let len = input.len() - (input.last() == Some(&b'\r')) as usize;
+            let (line, rest) = input.split_at(len);
+            let error = config.too_long(line, input.len());
+            Ok((
+                rest,
+                Parsed::Recovered {
+                    raw_line: line,
+                    error,
+                },
+            ))
+        }
+        None if input.last() == Some(&b'\r') => Err(Err::Incomplete(Needed::Size(1))),
+        None => Err(Err::Incomplete(Needed::Unknown)),
+    }
+}
+
+/// Drop the input up to the end of the current line
+/// Return `true` once the line ending has been consumed, `false` if the whole
+/// input belongs to the line and more must be skipped.
+pub fn skip_line_with<'a>(input: &'a [u8], config: &ParserConfig) -> IResult<&'a [u8], bool> {
+    match config.line_len(input) {
+        Some(len) => Ok((&input[len..], true)),
+        None if input.last() == Some(&b'\r') => Ok((&input[input.len() - 1..], false)),
+        None => Ok((&input[input.len()..], false)),
+    }
+}
+
+/// A single char parser only needs more data when the input is empty
+fn one<'a, F>(parser: F) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], &'a [u8]>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], &'a [u8]>,
+{
+    move |input: &'a [u8]| {
+        if input.is_empty() {
+            return Err(Err::Incomplete(Needed::Size(1)));
+        }
+        parser(input)
+    }
+}
+
+/// A parser eating a variable amount of bytes needs more data when it
+/// stopped on the end of the input, whether it succeeded or not
+fn until_end<'a, O, F>(parser: F) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], O>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    move |input: &'a [u8]| match parser(input) {
+        Ok((rest, _)) | Err(Err::Error((rest, _))) if rest.is_empty() => {
+            Err(Err::Incomplete(Needed::Unknown))
+        }
+        res => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+
+    #[test]
+    fn space_streaming() {
+        let s: &[u8] = b"   ";
+        assert_eq!(space(s), Err(Err::Incomplete(Needed::Size(1))));
+        let s: &[u8] = b"  a";
+        assert_eq!(space(s), Ok((&b"a"[..], &b"  "[..])));
+    }
+
+    #[test]
+    fn crlf_streaming() {
+        let c: &[u8] = b"\r";
+        assert_eq!(crlf(c), Err(Err::Incomplete(Needed::Size(2))));
+        let c: &[u8] = b"\r\n";
+        assert_eq!(crlf(c), Ok((&b""[..], c)));
+    }
+
+    #[test]
+    fn one_char_streaming() {
+        let empty: &[u8] = b"";
+        assert_eq!(letter(empty), Err(Err::Incomplete(Needed::Size(1))));
+        assert_eq!(nonwhite(empty), Err(Err::Incomplete(Needed::Size(1))));
+        let a: &[u8] = b"a";
+        assert_eq!(letter(a), Ok((&b""[..], a)));
+        assert_eq!(number(a), Err(Err::Error((a, nom::error::ErrorKind::Char))));
+    }
+
+    #[test]
+    fn command_streaming() {
+        let c: &[u8] = b"PRIV";
+        assert_eq!(command(c), Err(Err::Incomplete(Needed::Unknown)));
+        let c: &[u8] = b"00";
+        assert_eq!(command(c), Err(Err::Incomplete(Needed::Unknown)));
+        let c: &[u8] = b"001";
+        assert_eq!(command(c), Err(Err::Incomplete(Needed::Unknown)));
+        let c: &[u8] = b"001 ";
+        assert_eq!(command(c), Ok((&b" "[..], Command::Numeric(1))));
+    }
+
+    #[test]
+    fn params_streaming() {
+        let p: &[u8] = b" #chan :hel";
+        assert_eq!(params(p), Err(Err::Incomplete(Needed::Unknown)));
+        let p: &[u8] = b" #chan :hello\r\n";
+        assert!(params(p).is_ok());
+    }
+
+    #[test]
+    fn message_streaming() {
+        let m: &[u8] = b"PRIVMSG #chan :hel";
+        assert_eq!(message(m), Err(Err::Incomplete(Needed::Unknown)));
+        let m: &[u8] = b"PRIVMSG #chan :hello\r";
+        assert_eq!(message(m), Err(Err::Incomplete(Needed::Size(1))));
+        let m: &[u8] = b"PRIVMSG #chan :hello\r\nPING";
+        let (rest, m) = message(m).unwrap();
+        assert_eq!(rest, &b"PING"[..]);
+        assert_eq!(m.command, Command::Privmsg);
+    }
+
+    #[test]
+    fn message_or_recover_streaming() {
+        let m: &[u8] = b"PRIVMSG #chan :hel";
+        assert_eq!(message_or_recover(m), Err(Err::Incomplete(Needed::Unknown)));
+        let m: &[u8] = b":nick 12 :oops\r\nPING";
+        let (rest, line) = message_or_recover(m).unwrap();
+        assert_eq!(rest, &b"PING"[..]);
+        assert!(matches!(line, Parsed::Recovered { raw_line, .. } if raw_line.len() == 16));
+
+        let mut m = b"PRIVMSG #chan :".to_vec();
+        m.resize(9000, b'a');
+        m.push(b'\r');
+        let (rest, line) = message_or_recover(&m).unwrap();
+        assert_eq!(rest, &b"\r"[..]);
+        assert!(matches!(
+            line,
+            Parsed::Recovered {
+                error: ParseError::LineTooLong {
+                    offset: 0,
+                    len: 9001,
+                    max: 512
+                },
+                ..
+            }
+        ));
+        let config = ParserConfig::default();
+        assert_eq!(skip_line_with(b"aaa", &config), Ok((&b""[..], false)));
+        let (rest, done) = skip_line_with(b"\r\nPING :a\r\n", &config).unwrap();
+        assert!(done);
+        assert!(message_or_recover(rest).unwrap().1.ok().is_some());
+    }
+}
@@ -0,0 +1,591 @@
+use crate::host::hostname;
+#[cfg(feature = "std")]
+use crate::output::Writer;
+use crate::output::{to_vec, Output};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::combinator::{opt, recognize};
+use nom::multi::separated_nonempty_list;
+use nom::sequence::{preceded, terminated, tuple};
+use nom::IResult;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A single IRCv3 message tag
+/// A missing value and an empty value are equivalent and both stored as an
+/// empty value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag<'a> {
+    /// The whole key, client prefix and vendor included: `+example.com/key`
+    pub key: &'a [u8],
+    /// The unescaped value
+    pub value: Cow<'a, [u8]>,
+}
+
+impl<'a> Tag<'a> {
+    /// Return true if the key starts with the client-only prefix '+'
+    pub fn is_client_only(&self) -> bool {
+        self.key.starts_with(b"+")
+    }
+
+    /// Return the vendor of the key if there is one: `example.com`
+    pub fn vendor(&self) -> Option<&'a [u8]> {
+        let key = self.key.strip_prefix(b"+").unwrap_or(self.key);
+        let slash = key.iter().position(|&c| c == b'/')?;
+        Some(&key[..slash])
+    }
+
+    /// Return the name of the key without the client prefix and the vendor
+    pub fn name(&self) -> &'a [u8] {
+        let key = self.key.strip_prefix(b"+").unwrap_or(self.key);
+        match key.iter().position(|&c| c == b'/') {
+            Some(slash) => &key[slash + 1..],
+            None => key,
+        }
+    }
+}
+
+/// The tags of a message, in the order they were sent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags<'a>(Vec<Tag<'a>>);
+
+impl<'a> Tags<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the value of the tag `key`
+    /// If the key was sent multiple times the last value is returned.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .rev()
+            .find(|tag| tag.key == key.as_bytes())
+            .map(|tag| &*tag.value)
+    }
+
+    /// Return true if there is a tag `key`
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.iter().any(|tag| tag.key == key.as_bytes())
+    }
+
+    /// Add a tag at the end
+    pub fn push(&mut self, key: &'a [u8], value: impl Into<Cow<'a, [u8]>>) {
+        self.0.push(Tag {
+            key,
+            value: value.into(),
+        });
+    }
+
+    /// Add a tag at the end, removing the tags with the same key
+    pub fn set(&mut self, key: &'a [u8], value: impl Into<Cow<'a, [u8]>>) {
+        self.0.retain(|tag| tag.key != key);
+        self.push(key, value);
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> core::slice::IterMut<'_, Tag<'a>> {
+        self.0.iter_mut()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Tag<'a>> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the value of the tag `key` if it's valid UTF-8
+    fn get_str(&self, key: &str) -> Option<&str> {
+        str::from_utf8(self.get(key)?).ok()
+    }
+
+    /// Return the `time` tag: when the server received the message
+    /// Return `None` if the tag is missing or malformed.
+    pub fn time(&self) -> Option<ServerTime> {
+        ServerTime::parse(self.get("time")?)
+    }
+
+    /// Return the `msgid` tag: the unique id of the message
+    pub fn msgid(&self) -> Option<&str> {
+        self.get_str("msgid")
+    }
+
+    /// Return the `account` tag: the account of the sender
+    pub fn account(&self) -> Option<&str> {
+        self.get_str("account")
+    }
+
+    /// Return the `label` tag: the label of the request answered by the
+    /// message
+    pub fn label(&self) -> Option<&str> {
+        self.get_str("label")
+    }
+
+    /// Return the `batch` tag: the reference of the batch containing the
+    /// message
+    pub fn batch(&self) -> Option<&str> {
+        self.get_str("batch")
+    }
+
+    /// Serialize the tags as they are sent, without the leading '@'
+    /// The values are escaped and empty values are sent without '='.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_vec(|bytes| self.encode(bytes))
+    }
+
+    /// Same as `to_bytes` but write into `writer` without allocating
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.encode(&mut Writer(writer))
+    }
+
+    pub(crate) fn encode<O: Output>(&self, out: &mut O) -> Result<(), O::Error> {
+        for (i, tag) in self.0.iter().enumerate() {
+            if i > 0 {
+                out.put(b";")?;
+            }
+            out.put(tag.key)?;
+            if !tag.value.is_empty() {
+                out.put(b"=")?;
+                encode_escaped(out, &tag.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Display the tags without the leading '@'
+impl<'a> fmt::Display for Tags<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.to_bytes()))
+    }
+}
+
+/// The value of a `time` tag, always in UTC: `2019-02-28T19:30:01.727Z`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ServerTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl ServerTime {
+    /// Parse a `YYYY-MM-DDThh:mm:ss.sssZ` timestamp, the fraction of second
+    /// is optional and can have any number of digits
+    pub fn parse(value: &[u8]) -> Option<Self> {
+        let number = |range: core::ops::Range<usize>| -> Option<u16> {
+            let digits = value.get(range)?;
+            if !digits.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            Some(
+                digits
+                    .iter()
+                    .fold(0, |acc, d| acc * 10 + u16::from(d - b'0')),
+            )
+        };
+        let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+        if separators
+            .iter()
+            .any(|&(i, sep)| value.get(i) != Some(&sep))
+        {
+            return None;
+        }
+        let (fraction, end) = match value.get(19) {
+            Some(b'.') => {
+                let digits = value[20..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                if digits == 0 {
+                    return None;
+                }
+                let millis = value[20..20 + digits.min(3)]
+                    .iter()
+                    .chain(b"00")
+                    .take(3)
+                    .fold(0, |acc, d| acc * 10 + u16::from(d - b'0'));
+                (millis, 20 + digits)
+            }
+            _ => (0, 19),
+        };
+        if value.get(end..) != Some(&b"Z"[..]) {
+            return None;
+        }
+        let time = ServerTime {
+            year: number(0..4)?,
+            month: number(5..7)? as u8,
+            day: number(8..10)? as u8,
+            hour: number(11..13)? as u8,
+            minute: number(14..16)? as u8,
+            second: number(17..19)? as u8,
+            millisecond: fraction,
+        };
+        let valid = (1..=12).contains(&time.month)
+            && (1..=31).contains(&time.day)
+            && time.hour < 24
+            && time.minute < 60
+            && time.second < 61;
+        if valid {
+            Some(time)
+        } else {
+            None
+        }
+    }
+
+    /// Return the number of milliseconds since the unix epoch
+    pub fn unix_millis(&self) -> i64 {
+        // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - if month <= 2 { 1 } else { 0 };
+        let era = if year >= 0 { year } else { year - 399 } / 400;
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+        let seconds = days * 86_400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second);
+        seconds * 1000 + i64::from(self.millisecond)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ServerTime> for SystemTime {
+    fn from(time: ServerTime) -> Self {
+        let millis = time.unix_millis();
+        if millis >= 0 {
+            UNIX_EPOCH + Duration::from_millis(millis as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_millis(millis.unsigned_abs())
+        }
+    }
+}
+
+impl<'a, 't> IntoIterator for &'t Tags<'a> {
+    type Item = &'t Tag<'a>;
+    type IntoIter = core::slice::Iter<'t, Tag<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// <tags>          ::= <tag> [';' <tag>]*
+/// Return an error if the input does not start with a tag.
+pub fn tags(input: &[u8]) -> IResult<&[u8], Tags<'_>> {
+    let (input, tags) = separated_nonempty_list(tag(";"), single_tag)(input)?;
+    Ok((input, Tags(tags)))
+}
+
+/// Same as `tags` but the values are kept escaped, the caller unescapes
+/// them where it wants with `unescape_into`
+pub(crate) fn escaped_tags(input: &[u8]) -> IResult<&[u8], Tags<'_>> {
+    let (input, tags) = separated_nonempty_list(tag(";"), escaped_tag)(input)?;
+    Ok((input, Tags(tags)))
+}
+
+/// <tag>           ::= <key> ['=' <escaped_value>]
+pub fn single_tag(input: &[u8]) -> IResult<&[u8], Tag<'_>> {
+    let (input, tag) = escaped_tag(input)?;
+    Ok((
+        input,
+        Tag {
+            key: tag.key,
+            value: match tag.value {
+                Cow::Borrowed(value) => unescape_tag_value(value),
+                value => value,
+            },
+        },
+    ))
+}
+
+fn escaped_tag(input: &[u8]) -> IResult<&[u8], Tag<'_>> {
+    let (input, key) = key(input)?;
+    let (input, value) = opt(preceded(tag("="), escaped_value))(input)?;
+    Ok((
+        input,
+        Tag {
+            key,
+            value: Cow::Borrowed(value.unwrap_or(b"")),
+        },
+    ))
+}
+
+/// <key>           ::= [ <client_prefix> ] [ <vendor> '/' ] <key_name>
+/// <client_prefix> ::= '+'
+/// <vendor>        ::= <host>
+pub fn key(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(tuple((
+        opt(tag("+")),
+        opt(terminated(hostname, tag("/"))),
+        key_name,
+    )))(input)
+}
+
+/// <key_name>      ::= <non-empty sequence of ascii letters, digits, hyphens ('-')>
+fn key_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|c: u8| c.is_ascii_alphanumeric() || c == b'-')(input)
+}
+
+/// <escaped_value> ::= <sequence of zero or more utf8 characters except NUL,
+///                     CR, LF, semicolon (`;`) and SPACE>
+fn escaped_value(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while(|c| !matches!(c, 0x00 | b'\r' | b'\n' | b';' | b' '))(input)
+}
+
+/// Replace the escape sequences of a tag value, only allocates if the value
+/// contains a '\'
+/// `\:` becomes ';', `\s` a space, `\\` a '\', `\r` a CR and `\n` a LF. Any
+/// other escaped char is kept as is and a trailing '\' is dropped.
+pub fn unescape_tag_value(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value.contains(&b'\\') {
+        return Cow::Borrowed(value);
+    }
+    let mut unescaped = Vec::with_capacity(value.len());
+    unescape_into(value, &mut unescaped);
+    Cow::Owned(unescaped)
+}
+
+/// Same as `unescape_tag_value` but append the value to `out`
+pub(crate) fn unescape_into(value: &[u8], out: &mut Vec<u8>) {
+    let mut chars = value.iter();
+    while let Some(&c) = chars.next() {
+        if c != b'\\' {
+            out.push(c);
+            continue;
+        }
+        // a trailing backslash is dropped
+        if let Some(&c) = chars.next() {
+            out.push(match c {
+                b':' => b';',
+                b's' => b' ',
+                b'r' => b'\r',
+                b'n' => b'\n',
+                c => c,
+            });
+        }
+    }
+}
+
+/// Same as `unescape_tag_value` but unescape the value where it is and
+/// return its new length, the bytes after it are left as they were
+pub(crate) fn unescape_in_place(value: &mut [u8]) -> usize {
+    let mut read = 0;
+    let mut write = 0;
+    while read < value.len() {
+        let c = value[read];
+        read += 1;
+        if c != b'\\' {
+            value[write] = c;
+            write += 1;
+            continue;
+        }
+        // a trailing backslash is dropped
+        if let Some(&c) = value.get(read) {
+            value[write] = match c {
+                b':' => b';',
+                b's' => b' ',
+                b'r' => b'\r',
+                b'n' => b'\n',
+                c => c,
+            };
+            read += 1;
+            write += 1;
+        }
+    }
+    write
+}
+
+/// Escape a tag value so it can be sent, the reverse of `unescape_tag_value`
+/// Only allocates if the value contains a char that needs to be escaped.
+pub fn escape_tag_value(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value.iter().any(|&c| escape(c).is_some()) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = Vec::with_capacity(value.len() + 8);
+    for &c in value {
+        match escape(c) {
+            Some(sequence) => escaped.extend_from_slice(sequence),
+            None => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// Write an escaped tag value without allocating
+fn encode_escaped<O: Output>(out: &mut O, mut value: &[u8]) -> Result<(), O::Error> {
+    while let Some(pos) = value.iter().position(|&c| escape(c).is_some()) {
+        out.put(&value[..pos])?;
+        out.put(escape(value[pos]).unwrap_or_default())?;
+        value = &value[pos + 1..];
+    }
+    out.put(value)
+}
+
+fn escape(c: u8) -> Option<&'static [u8]> {
+    match c {
+        b';' => Some(b"\\:"),
+        b' ' => Some(b"\\s"),
+        b'\\' => Some(b"\\\\"),
+        b'\r' => Some(b"\\r"),
+        b'\n' => Some(b"\\n"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_empty() {
+        let empty: &[u8] = b"";
+        assert!(tags(empty).is_err());
+    }
+
+    #[test]
+    fn tags_simple() {
+        let t: &[u8] = b"aaa=bbb;ccc;example.com/ddd=eee :nick PRIVMSG";
+        let (rest, t) = tags(t).unwrap();
+        assert_eq!(rest, &b" :nick PRIVMSG"[..]);
+        assert_eq!(t.len(), 3);
+        assert_eq!(t.get("aaa"), Some(&b"bbb"[..]));
+        assert_eq!(t.get("ccc"), Some(&b""[..]));
+        assert_eq!(t.get("example.com/ddd"), Some(&b"eee"[..]));
+        assert_eq!(t.get("ddd"), None);
+        let keys: Vec<_> = t.iter().map(|tag| tag.key).collect();
+        assert_eq!(keys, vec![&b"aaa"[..], b"ccc", b"example.com/ddd"]);
+    }
+
+    #[test]
+    fn tags_duplicate_key() {
+        let (_, t) = tags(b"a=1;a=2").unwrap();
+        assert_eq!(t.get("a"), Some(&b"2"[..]));
+    }
+
+    #[test]
+    fn tag_key_parts() {
+        let (_, t) = single_tag(b"+example.com/typing=active").unwrap();
+        assert!(t.is_client_only());
+        assert_eq!(t.vendor(), Some(&b"example.com"[..]));
+        assert_eq!(t.name(), &b"typing"[..]);
+        let (_, t) = single_tag(b"msgid=abc").unwrap();
+        assert!(!t.is_client_only());
+        assert_eq!(t.vendor(), None);
+        assert_eq!(t.name(), &b"msgid"[..]);
+    }
+
+    #[test]
+    fn tag_invalid_key() {
+        assert!(single_tag(b"=value").is_err());
+        assert!(single_tag(b"+=value").is_err());
+        let (rest, _) = single_tag(b"a_b=c").unwrap();
+        assert_eq!(rest, &b"_b=c"[..]);
+    }
+
+    #[test]
+    fn tag_unescape() {
+        let (_, t) = single_tag(b"k=a\\:b\\sc\\\\d\\re\\nf\\xg\\").unwrap();
+        assert_eq!(&*t.value, &b"a;b c\\d\re\nfxg"[..]);
+        let (_, t) = single_tag(b"k=plain").unwrap();
+        assert!(matches!(t.value, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn tag_escape() {
+        assert!(matches!(escape_tag_value(b"plain"), Cow::Borrowed(_)));
+        assert_eq!(
+            &*escape_tag_value(b"a;b c\\d\re\nf"),
+            &b"a\\:b\\sc\\\\d\\re\\nf"[..]
+        );
+    }
+
+    #[test]
+    fn tag_escape_round_trip() {
+        let value: &[u8] = b"; \\\r\n\\s:";
+        assert_eq!(&*unescape_tag_value(&escape_tag_value(value)), value);
+    }
+
+    #[test]
+    fn tags_to_bytes() {
+        let mut t = Tags::new();
+        t.push(b"a", &b"x;y z"[..]);
+        t.push(b"+example.com/b", &b""[..]);
+        t.push(b"c", &b"\\"[..]);
+        let bytes = t.to_bytes();
+        assert_eq!(bytes, b"a=x\\:y\\sz;+example.com/b;c=\\\\".to_vec());
+        let (rest, parsed) = tags(&bytes).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(parsed, t);
+    }
+
+    #[test]
+    fn tags_typed_accessors() {
+        let (_, t) = tags(
+            b"msgid=63E1033A051D4B41B1AB1FA3CF4B243E;account=nick;label=abc;batch=yXNAbvnRHTRBv",
+        )
+        .unwrap();
+        assert_eq!(t.msgid(), Some("63E1033A051D4B41B1AB1FA3CF4B243E"));
+        assert_eq!(t.account(), Some("nick"));
+        assert_eq!(t.label(), Some("abc"));
+        assert_eq!(t.batch(), Some("yXNAbvnRHTRBv"));
+        assert_eq!(t.time(), None);
+        let (_, t) = tags(b"label=\xff").unwrap();
+        assert_eq!(t.label(), None);
+        let (_, t) = tags(b"account").unwrap();
+        assert_eq!(t.account(), Some(""));
+    }
+
+    #[test]
+    fn tags_time() {
+        let (_, t) = tags(b"time=2019-02-28T19:30:01.727Z").unwrap();
+        let time = t.time().unwrap();
+        assert_eq!(
+            time,
+            ServerTime {
+                year: 2019,
+                month: 2,
+                day: 28,
+                hour: 19,
+                minute: 30,
+                second: 1,
+                millisecond: 727,
+            }
+        );
+        assert_eq!(time.unix_millis(), 1_551_382_201_727);
+        #[cfg(feature = "std")]
+        assert_eq!(
+            SystemTime::from(time),
+            UNIX_EPOCH + Duration::from_millis(1_551_382_201_727)
+        );
+    }
+
+    #[test]
+    fn server_time_formats() {
+        let time = ServerTime::parse(b"1970-01-01T00:00:00Z").unwrap();
+        assert_eq!(time.unix_millis(), 0);
+        let time = ServerTime::parse(b"2000-03-01T00:00:00.5Z").unwrap();
+        assert_eq!(time.unix_millis(), 951_868_800_500);
+        let time = ServerTime::parse(b"2000-03-01T00:00:00.123456Z").unwrap();
+        assert_eq!(time.millisecond, 123);
+        assert_eq!(ServerTime::parse(b"2000-03-01T00:00:00"), None);
+        assert_eq!(ServerTime::parse(b"2000-03-01T00:00:00.Z"), None);
+        assert_eq!(ServerTime::parse(b"2000-13-01T00:00:00Z"), None);
+        assert_eq!(ServerTime::parse(b"2000-03-01 00:00:00Z"), None);
+    }
+}
@@ -0,0 +1,279 @@
+use crate::channel::{channel, channel_with, Channel};
+use crate::config::ParserConfig;
+use crate::host::servername;
+use crate::mask::mask;
+use crate::parser::nick_max;
+use alloc::vec::Vec;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::{map, not, opt};
+use nom::multi::separated_nonempty_list;
+use nom::sequence::{pair, preceded, tuple};
+use nom::IResult;
+
+/// A receiver of a PRIVMSG or NOTICE
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// A channel: `#rust`
+    Channel(Channel<'a>),
+    /// The members of a channel with one of the status prefixes: `@#rust`
+    /// Only recognized with the STATUSMSG prefixes of the configuration.
+    StatusChannel {
+        status: &'a [u8],
+        channel: Channel<'a>,
+    },
+    /// A nick: `nick`
+    Nick(&'a [u8]),
+    /// A user designated by its user, host or server:
+    /// `user@server`, `user%host@server`, `user%host` or `nick!user@host`
+    UserHost {
+        nick: Option<&'a [u8]>,
+        user: &'a [u8],
+        host: Option<&'a [u8]>,
+        server: Option<&'a [u8]>,
+    },
+    /// All the users on the servers matching the mask: `$*.fr`
+    ServerMask(&'a [u8]),
+    /// All the users whose host matches the mask: `#*.edu`
+    HostMask(&'a [u8]),
+}
+
+/// <msgtarget>  ::= <msgto> { ',' <msgto> }
+/// Return an error if any of the targets is invalid.
+pub fn msgtarget(input: &[u8]) -> IResult<&[u8], Vec<Target<'_>>> {
+    separated_nonempty_list(tag(","), target)(input)
+}
+
+/// <msgto>      ::= <channel> | <user> [ '%' <host> ] '@' <servername>
+///                | <user> '%' <host> | <targetmask>
+///                | <nick> | <nick> '!' <user> '@' <host>
+/// <targetmask> ::= ( '$' | '#' ) <mask>
+/// A target starting with a '#' is a host mask when it contains a wildcard.
+/// Return an error if the input does not start with a target followed by a
+/// ',' or the end of the parameter.
+pub fn target(input: &[u8]) -> IResult<&[u8], Target<'_>> {
+    let (input, target) = alt((
+        ended(mask),
+        ended(map(channel, Target::Channel)),
+        ended(map(nick_max(usize::MAX), Target::Nick)),
+        ended(nick_user_host),
+        ended(user_host),
+    ))(input)?;
+    Ok((input, target))
+}
+
+/// Same as `msgtarget` with the channels and nicks allowed by the
+/// configuration
+pub fn msgtarget_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Vec<Target<'_>>> {
+    let target = target_with(config);
+    move |input: &[u8]| separated_nonempty_list(tag(","), &target)(input)
+}
+
+/// Same as `target` with the channel prefixes, the maximum channel length,
+/// the status prefixes and the maximum nick length of the configuration
+pub fn target_with(config: &ParserConfig) -> impl Fn(&[u8]) -> IResult<&[u8], Target<'_>> {
+    let channel = channel_with(config);
+    let statusmsg = config.statusmsg.clone();
+    let max_nick_len = config.max_nick_len;
+    move |input: &[u8]| {
+        let status_channel = map(
+            pair(take_while1(|c| statusmsg.contains(&c)), &channel),
+            |(status, channel)| Target::StatusChannel { status, channel },
+        );
+        alt((
+            ended(mask),
+            ended(status_channel),
+            ended(map(&channel, Target::Channel)),
+            ended(map(nick_max(max_nick_len), Target::Nick)),
+            ended(nick_user_host),
+            ended(user_host),
+        ))(input)
+    }
+}
+
+fn nick_user_host(input: &[u8]) -> IResult<&[u8], Target<'_>> {
+    let (input, (nick, _, user, _, host)) = tuple((
+        nick_max(usize::MAX),
+        tag("!"),
+        part(b"@!%"),
+        tag("@"),
+        part(b""),
+    ))(input)?;
+    Ok((
+        input,
+        Target::UserHost {
+            nick: Some(nick),
+            user,
+            host: Some(host),
+            server: None,
+        },
+    ))
+}
+
+fn user_host(input: &[u8]) -> IResult<&[u8], Target<'_>> {
+    let (input, user) = part(b"@!%")(input)?;
+    let (input, host) = opt(preceded(tag("%"), part(b"@!%")))(input)?;
+    let (input, server) = if host.is_some() {
+        opt(preceded(tag("@"), servername))(input)?
+    } else {
+        map(preceded(tag("@"), servername), Some)(input)?
+    };
+    Ok((
+        input,
+        Target::UserHost {
+            nick: None,
+            user,
+            host,
+            server,
+        },
+    ))
+}
+
+/// Any non-empty sequence of octets that can't end a target and that is not
+/// one of `stop`
+fn part<'s>(stop: &'s [u8]) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> + 's {
+    move |input: &[u8]| take_while1(|c| !is_end(c) && !stop.contains(&c))(input)
+}
+
+/// Make sure the parser consumed the whole target
+fn ended<'a, O, F>(parser: F) -> impl Fn(&'a [u8]) -> IResult<&'a [u8], O>
+where
+    F: Fn(&'a [u8]) -> IResult<&'a [u8], O>,
+{
+    move |input: &'a [u8]| {
+        let (input, output) = parser(input)?;
+        let (input, _) = not(take_while1(|c| !is_end(c)))(input)?;
+        Ok((input, output))
+    }
+}
+
+fn is_end(c: u8) -> bool {
+    matches!(c, b',' | b' ' | 0x00 | b'\r' | b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> Target<'_> {
+        let (rest, target) = target(input).unwrap();
+        assert_eq!(rest, &b""[..]);
+        target
+    }
+
+    #[test]
+    fn target_empty() {
+        let empty: &[u8] = b"";
+        assert!(target(empty).is_err());
+        assert!(msgtarget(empty).is_err());
+    }
+
+    #[test]
+    fn target_channel_and_nick() {
+        assert_eq!(
+            parse(b"#rust"),
+            Target::Channel(channel(b"#rust").unwrap().1)
+        );
+        assert_eq!(parse(b"nick"), Target::Nick(&b"nick"[..]));
+    }
+
+    #[test]
+    fn target_masks() {
+        assert_eq!(parse(b"$*.fr"), Target::ServerMask(&b"*.fr"[..]));
+        assert_eq!(parse(b"#*.edu"), Target::HostMask(&b"*.edu"[..]));
+    }
+
+    #[test]
+    fn target_user_host() {
+        assert_eq!(
+            parse(b"user@irc.example.com"),
+            Target::UserHost {
+                nick: None,
+                user: &b"user"[..],
+                host: None,
+                server: Some(&b"irc.example.com"[..]),
+            }
+        );
+        assert_eq!(
+            parse(b"user%host@irc.example.com"),
+            Target::UserHost {
+                nick: None,
+                user: &b"user"[..],
+                host: Some(&b"host"[..]),
+                server: Some(&b"irc.example.com"[..]),
+            }
+        );
+        assert_eq!(
+            parse(b"user%host"),
+            Target::UserHost {
+                nick: None,
+                user: &b"user"[..],
+                host: Some(&b"host"[..]),
+                server: None,
+            }
+        );
+        assert_eq!(
+            parse(b"nick!user@host"),
+            Target::UserHost {
+                nick: Some(&b"nick"[..]),
+                user: &b"user"[..],
+                host: Some(&b"host"[..]),
+                server: None,
+            }
+        );
+    }
+
+    #[test]
+    fn target_invalid() {
+        assert!(target(b"user").is_ok());
+        assert!(target(b"user@").is_err());
+        assert!(target(b"1user").is_err());
+        assert!(target(b"#chan\x07").is_err());
+    }
+
+    #[test]
+    fn msgtarget_list() {
+        let t: &[u8] = b"#a,nick,$*.fr :hello";
+        let (rest, targets) = msgtarget(t).unwrap();
+        assert_eq!(rest, &b" :hello"[..]);
+        assert_eq!(
+            targets,
+            vec![
+                Target::Channel(channel(b"#a").unwrap().1),
+                Target::Nick(&b"nick"[..]),
+                Target::ServerMask(&b"*.fr"[..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn target_config() {
+        let config = ParserConfig::default().chantypes(b"#").max_nick_len(4);
+        let target = target_with(&config);
+        assert!(matches!(target(b"#rust"), Ok((_, Target::Channel(_)))));
+        assert!(target(b"&rust").is_err());
+        assert_eq!(target(b"nick"), Ok((&b""[..], Target::Nick(b"nick"))));
+        assert!(target(b"nick_").is_err());
+        let (_, targets) = msgtarget_with(&config)(b"#a,nick").unwrap();
+        assert_eq!(targets.len(), 2);
+    }
+
+    #[test]
+    fn target_statusmsg() {
+        let config = ParserConfig::default().statusmsg(b"@+");
+        let target = target_with(&config);
+        let (_, status) = target(b"@+#rust").unwrap();
+        assert_eq!(
+            status,
+            Target::StatusChannel {
+                status: b"@+",
+                channel: channel(b"#rust").unwrap().1,
+            }
+        );
+        // a '+' channel
+        let (_, plus) = target(b"+rust").unwrap();
+        assert_eq!(plus, Target::Channel(channel(b"+rust").unwrap().1));
+        // without STATUSMSG
+        assert!(target_with(&ParserConfig::default())(b"@#rust").is_err());
+    }
+}
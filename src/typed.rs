@@ -0,0 +1,728 @@
+use crate::channel::{channel, Channel};
+use crate::command::Command;
+use crate::config::ParserConfig;
+use crate::ctcp::{ctcp, Ctcp, CtcpCommand};
+use crate::message::Message;
+use crate::mode::{channel_modes, user_modes, ModeEntry};
+use crate::target::{msgtarget_with, Target};
+use alloc::vec;
+use alloc::vec::Vec;
+use nom::bytes::complete::tag;
+use nom::combinator::all_consuming;
+use nom::multi::separated_nonempty_list;
+
+/// The command and parameters of a message, destructured into named fields
+/// The tags and the prefix stay in the `Message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedMessage<'a> {
+    Privmsg(Privmsg<'a>),
+    /// A `PRIVMSG` carrying a CTCP `ACTION`, sent by `/me`
+    Action(Action<'a>),
+    Notice(Notice<'a>),
+    Join(Join<'a>),
+    Part(Part<'a>),
+    Kick(Kick<'a>),
+    Nick(Nick<'a>),
+    Quit(Quit<'a>),
+    Ping(Ping<'a>),
+    Pong(Pong<'a>),
+    Topic(Topic<'a>),
+    Mode(Mode<'a>),
+    Invite(Invite<'a>),
+    /// Sent by a client, or by the server with away-notify
+    Away(Away<'a>),
+    /// Sent by the server with account-notify
+    Account(Account<'a>),
+    /// Sent by the server with chghost
+    Chghost(Chghost<'a>),
+    /// Sent by a client, or by the server with setname
+    Setname(Setname<'a>),
+    /// Any command not listed above, or a listed command with unexpected
+    /// parameters
+    Other {
+        command: Command<'a>,
+        params: &'a [&'a [u8]],
+    },
+}
+
+/// `PRIVMSG <target> :<text>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Privmsg<'a> {
+    pub target: &'a [u8],
+    pub text: &'a [u8],
+}
+
+impl<'a> Privmsg<'a> {
+    /// Parse the comma separated targets with the channel prefixes and the
+    /// status prefixes of the configuration
+    /// Return `None` if a target is invalid.
+    pub fn targets(&self, config: &ParserConfig) -> Option<Vec<Target<'a>>> {
+        targets(self.target, config)
+    }
+}
+
+/// `PRIVMSG <target> :\x01ACTION <text>\x01`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Action<'a> {
+    pub target: &'a [u8],
+    /// The text without the CTCP delimiters and command, still CTCP quoted
+    pub text: &'a [u8],
+}
+
+/// `NOTICE <target> :<text>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notice<'a> {
+    pub target: &'a [u8],
+    pub text: &'a [u8],
+}
+
+impl<'a> Notice<'a> {
+    /// Same as `Privmsg::targets`
+    pub fn targets(&self, config: &ParserConfig) -> Option<Vec<Target<'a>>> {
+        targets(self.target, config)
+    }
+}
+
+fn targets<'a>(list: &'a [u8], config: &ParserConfig) -> Option<Vec<Target<'a>>> {
+    all_consuming(msgtarget_with(config))(list)
+        .ok()
+        .map(|(_, targets)| targets)
+}
+
+/// `JOIN <channel>{,<channel>} [<key>{,<key>}] | JOIN 0`
+/// With extended-join the server sends `JOIN <channel> <account> :<realname>`
+/// instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Join<'a> {
+    channels: Vec<Channel<'a>>,
+    keys: Vec<&'a [u8]>,
+    account: Option<&'a [u8]>,
+    realname: Option<&'a [u8]>,
+}
+
+impl<'a> Join<'a> {
+    /// Parse the channel list and the optional key list
+    /// Return `None` if a channel is invalid.
+    pub fn new(channels: &'a [u8], keys: Option<&'a [u8]>) -> Option<Self> {
+        if channels == b"0" {
+            return Some(Join {
+                channels: Vec::new(),
+                keys: Vec::new(),
+                account: None,
+                realname: None,
+            });
+        }
+        Some(Join {
+            channels: channels_list(channels)?,
+            keys: keys.map(list).unwrap_or_default(),
+            account: None,
+            realname: None,
+        })
+    }
+
+    /// Parse the JOIN of extended-join, the account `*` means the user is
+    /// not logged in
+    /// Return `None` if the channel is invalid.
+    pub fn extended(channel: &'a [u8], account: &'a [u8], realname: &'a [u8]) -> Option<Self> {
+        Some(Join {
+            channels: vec![single_channel(channel)?],
+            keys: Vec::new(),
+            account: Some(account).filter(|&account| account != b"*"),
+            realname: Some(realname),
+        })
+    }
+
+    /// Return true for `JOIN 0`, which leaves all the channels
+    pub fn is_part_all(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Return the channels with their key
+    /// The keys are given to the first channels, the others have no key.
+    pub fn channels(&self) -> impl Iterator<Item = (&Channel<'a>, Option<&'a [u8]>)> + '_ {
+        self.channels
+            .iter()
+            .enumerate()
+            .map(move |(i, channel)| (channel, self.keys.get(i).copied()))
+    }
+
+    /// Return the account of the user, only sent with extended-join
+    pub fn account(&self) -> Option<&'a [u8]> {
+        self.account
+    }
+
+    /// Return the realname of the user, only sent with extended-join
+    pub fn realname(&self) -> Option<&'a [u8]> {
+        self.realname
+    }
+
+    /// Return true for the JOIN of extended-join
+    pub fn is_extended(&self) -> bool {
+        self.realname.is_some()
+    }
+}
+
+/// `PART <channel>{,<channel>} [:<reason>]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part<'a> {
+    channels: Vec<Channel<'a>>,
+    reason: Option<&'a [u8]>,
+}
+
+impl<'a> Part<'a> {
+    /// Return `None` if a channel is invalid
+    pub fn new(channels: &'a [u8], reason: Option<&'a [u8]>) -> Option<Self> {
+        Some(Part {
+            channels: channels_list(channels)?,
+            reason,
+        })
+    }
+
+    pub fn channels(&self) -> &[Channel<'a>] {
+        &self.channels
+    }
+
+    /// Return the reason, `PART #chan :` has an empty reason
+    pub fn reason(&self) -> Option<&'a [u8]> {
+        self.reason
+    }
+}
+
+/// `KICK <channel>{,<channel>} <user>{,<user>} [:<reason>]`
+/// There is either one channel and many users or as many channels as users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kick<'a> {
+    channels: Vec<Channel<'a>>,
+    users: Vec<&'a [u8]>,
+    reason: Option<&'a [u8]>,
+}
+
+impl<'a> Kick<'a> {
+    /// Return `None` if a channel is invalid or if the number of channels
+    /// and users don't match
+    pub fn new(channels: &'a [u8], users: &'a [u8], reason: Option<&'a [u8]>) -> Option<Self> {
+        let channels = channels_list(channels)?;
+        let users = list(users);
+        if channels.len() != 1 && channels.len() != users.len() {
+            return None;
+        }
+        Some(Kick {
+            channels,
+            users,
+            reason,
+        })
+    }
+
+    /// Return the first channel, the only one in the usual form
+    pub fn channel(&self) -> &Channel<'a> {
+        &self.channels[0]
+    }
+
+    /// Return the first user, the only one in the usual form
+    pub fn user(&self) -> &'a [u8] {
+        self.users[0]
+    }
+
+    /// Return every kicked user with the channel they are kicked from
+    pub fn kicks(&self) -> impl Iterator<Item = (&Channel<'a>, &'a [u8])> + '_ {
+        self.users.iter().enumerate().map(move |(i, user)| {
+            let channel = self.channels.get(i).unwrap_or(&self.channels[0]);
+            (channel, *user)
+        })
+    }
+
+    /// Return the comment, `KICK #chan nick :` has an empty comment
+    pub fn reason(&self) -> Option<&'a [u8]> {
+        self.reason
+    }
+}
+
+/// `NICK <nick>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nick<'a> {
+    pub nick: &'a [u8],
+}
+
+/// `QUIT [:<reason>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quit<'a> {
+    pub reason: Option<&'a [u8]>,
+}
+
+/// `PING <token> [<server>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ping<'a> {
+    pub token: &'a [u8],
+    pub server: Option<&'a [u8]>,
+}
+
+/// `PONG <server> [<token>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pong<'a> {
+    pub server: &'a [u8],
+    pub token: Option<&'a [u8]>,
+}
+
+/// `TOPIC <channel> [:<topic>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topic<'a> {
+    channel: Channel<'a>,
+    topic: Option<&'a [u8]>,
+}
+
+impl<'a> Topic<'a> {
+    /// Return `None` if the channel is invalid
+    pub fn new(channel: &'a [u8], topic: Option<&'a [u8]>) -> Option<Self> {
+        Some(Topic {
+            channel: single_channel(channel)?,
+            topic,
+        })
+    }
+
+    pub fn channel(&self) -> &Channel<'a> {
+        &self.channel
+    }
+
+    /// Return the new topic, or `None` when the topic is asked
+    pub fn topic(&self) -> Option<&'a [u8]> {
+        self.topic
+    }
+
+    /// Return true for `TOPIC #chan`, which asks for the topic
+    pub fn is_query(&self) -> bool {
+        self.topic.is_none()
+    }
+
+    /// Return true for `TOPIC #chan :`, which removes the topic
+    pub fn is_clear(&self) -> bool {
+        self.topic == Some(b"")
+    }
+}
+
+/// `MODE <target> [<modes> {<argument>}]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mode<'a> {
+    pub target: &'a [u8],
+    /// The mode string followed by its arguments
+    pub modes: &'a [&'a [u8]],
+}
+
+impl<'a> Mode<'a> {
+    /// Return true if the target is a channel rather than a nick
+    pub fn is_channel(&self) -> bool {
+        matches!(self.target.first(), Some(b'#' | b'&' | b'+' | b'!'))
+    }
+
+    /// Interpret the modes as channel modes or user modes depending on the
+    /// target, see `mode::channel_modes` and `mode::user_modes`
+    pub fn changes(&self) -> Vec<ModeEntry<'a>> {
+        if self.is_channel() {
+            channel_modes(self.modes)
+        } else {
+            user_modes(self.modes)
+        }
+    }
+}
+
+/// `INVITE <nick> <channel>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Invite<'a> {
+    nick: &'a [u8],
+    channel: Channel<'a>,
+}
+
+impl<'a> Invite<'a> {
+    /// Return `None` if the channel is invalid
+    pub fn new(nick: &'a [u8], channel: &'a [u8]) -> Option<Self> {
+        Some(Invite {
+            nick,
+            channel: single_channel(channel)?,
+        })
+    }
+
+    pub fn nick(&self) -> &'a [u8] {
+        self.nick
+    }
+
+    pub fn channel(&self) -> &Channel<'a> {
+        &self.channel
+    }
+}
+
+/// `AWAY [:<message>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Away<'a> {
+    pub message: Option<&'a [u8]>,
+}
+
+impl<'a> Away<'a> {
+    /// Return true if the user is away, false if it's back
+    /// An empty message means the user is back.
+    pub fn is_away(&self) -> bool {
+        self.message.is_some_and(|message| !message.is_empty())
+    }
+}
+
+/// `ACCOUNT <account>`: the sender logged in, or out with the account `*`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Account<'a> {
+    /// `None` when the user logged out
+    pub account: Option<&'a [u8]>,
+}
+
+/// `CHGHOST <user> <host>`: the user or host of the sender changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chghost<'a> {
+    pub user: &'a [u8],
+    pub host: &'a [u8],
+}
+
+/// `SETNAME :<realname>`: the realname of the sender changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Setname<'a> {
+    pub realname: &'a [u8],
+}
+
+fn list(param: &[u8]) -> Vec<&[u8]> {
+    param.split(|&c| c == b',').collect()
+}
+
+fn channels_list(param: &[u8]) -> Option<Vec<Channel<'_>>> {
+    all_consuming(separated_nonempty_list(tag(","), channel))(param)
+        .ok()
+        .map(|(_, channels)| channels)
+}
+
+fn single_channel(param: &[u8]) -> Option<Channel<'_>> {
+    all_consuming(channel)(param)
+        .ok()
+        .map(|(_, channel)| channel)
+}
+
+impl<'a> TypedMessage<'a> {
+    /// Destructure the command and the parameters of a message
+    /// Return `TypedMessage::Other` when the parameters don't match the
+    /// command.
+    pub fn from_parts(command: Command<'a>, params: &'a [&'a [u8]]) -> Self {
+        use TypedMessage as T;
+
+        let or_other = |typed: Option<Self>| typed.unwrap_or(T::Other { command, params });
+
+        match (command, params) {
+            (Command::Privmsg, &[target, text]) => match ctcp(text) {
+                Some(Ctcp {
+                    command: CtcpCommand::Action,
+                    params,
+                }) => T::Action(Action {
+                    target,
+                    text: params,
+                }),
+                _ => T::Privmsg(Privmsg { target, text }),
+            },
+            (Command::Notice, &[target, text]) => T::Notice(Notice { target, text }),
+            (Command::Join, &[channels]) => or_other(Join::new(channels, None).map(T::Join)),
+            (Command::Join, &[channels, keys]) => {
+                or_other(Join::new(channels, Some(keys)).map(T::Join))
+            }
+            (Command::Join, &[channel, account, realname]) => {
+                or_other(Join::extended(channel, account, realname).map(T::Join))
+            }
+            (Command::Part, &[channels]) => or_other(Part::new(channels, None).map(T::Part)),
+            (Command::Part, &[channels, reason]) => {
+                or_other(Part::new(channels, Some(reason)).map(T::Part))
+            }
+            (Command::Kick, &[channels, users]) => {
+                or_other(Kick::new(channels, users, None).map(T::Kick))
+            }
+            (Command::Kick, &[channels, users, reason]) => {
+                or_other(Kick::new(channels, users, Some(reason)).map(T::Kick))
+            }
+            (Command::Nick, &[nick]) => T::Nick(Nick { nick }),
+            (Command::Quit, &[]) => T::Quit(Quit { reason: None }),
+            (Command::Quit, &[reason]) => T::Quit(Quit {
+                reason: Some(reason),
+            }),
+            (Command::Ping, &[token]) => T::Ping(Ping {
+                token,
+                server: None,
+            }),
+            (Command::Ping, &[token, server]) => T::Ping(Ping {
+                token,
+                server: Some(server),
+            }),
+            (Command::Pong, &[server]) => T::Pong(Pong {
+                server,
+                token: None,
+            }),
+            (Command::Pong, &[server, token]) => T::Pong(Pong {
+                server,
+                token: Some(token),
+            }),
+            (Command::Topic, &[channel]) => or_other(Topic::new(channel, None).map(T::Topic)),
+            (Command::Topic, &[channel, topic]) => {
+                or_other(Topic::new(channel, Some(topic)).map(T::Topic))
+            }
+            (Command::Mode, [target, modes @ ..]) => T::Mode(Mode { target, modes }),
+            (Command::Invite, &[nick, channel]) => {
+                or_other(Invite::new(nick, channel).map(T::Invite))
+            }
+            (Command::Away, &[]) => T::Away(Away { message: None }),
+            (Command::Away, &[message]) => T::Away(Away {
+                message: Some(message),
+            }),
+            (Command::Account, &[account]) => T::Account(Account {
+                account: Some(account).filter(|&account| account != b"*"),
+            }),
+            (Command::Chghost, &[user, host]) => T::Chghost(Chghost { user, host }),
+            (Command::Setname, &[realname]) => T::Setname(Setname { realname }),
+            _ => T::Other { command, params },
+        }
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Destructure the command and the parameters, see `TypedMessage`
+    pub fn typed(&'a self) -> TypedMessage<'a> {
+        TypedMessage::from_parts(self.command, &self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn typed_privmsg() {
+        let m = parse_message(b":nick!user@host PRIVMSG #rust :hello world\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Privmsg(Privmsg {
+                target: b"#rust",
+                text: b"hello world"
+            })
+        );
+    }
+
+    #[test]
+    fn typed_part() {
+        let m = parse_message(b"PART #a,&b :bye\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Part(part) => {
+                let channels: Vec<_> = part.channels().iter().map(Channel::as_bytes).collect();
+                assert_eq!(channels, vec![&b"#a"[..], b"&b"]);
+                assert_eq!(part.reason(), Some(&b"bye"[..]));
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"PART #a\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Part(part) => assert_eq!(part.reason(), None),
+            typed => panic!("unexpected {:?}", typed),
+        }
+    }
+
+    #[test]
+    fn typed_kick() {
+        let m = parse_message(b"KICK #a nick\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Kick(kick) => {
+                assert_eq!(kick.channel().as_bytes(), b"#a");
+                assert_eq!(kick.user(), b"nick");
+                assert_eq!(kick.reason(), None);
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"KICK #a n1,n2 :\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Kick(kick) => {
+                let kicks: Vec<_> = kick.kicks().map(|(c, u)| (c.as_bytes(), u)).collect();
+                assert_eq!(kicks, vec![(&b"#a"[..], &b"n1"[..]), (b"#a", b"n2")]);
+                assert_eq!(kick.reason(), Some(&b""[..]));
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"KICK #a,#b n1,n2\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Kick(kick) => {
+                let kicks: Vec<_> = kick.kicks().map(|(c, u)| (c.as_bytes(), u)).collect();
+                assert_eq!(kicks, vec![(&b"#a"[..], &b"n1"[..]), (b"#b", b"n2")]);
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"KICK #a,#b n1,n2,n3\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Other { .. }));
+    }
+
+    #[test]
+    fn typed_topic() {
+        let m = parse_message(b"TOPIC #a\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Topic(topic) => {
+                assert_eq!(topic.channel().as_bytes(), b"#a");
+                assert!(topic.is_query());
+                assert!(!topic.is_clear());
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"TOPIC #a :\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Topic(topic) => {
+                assert!(!topic.is_query());
+                assert!(topic.is_clear());
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"TOPIC #a :new topic\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Topic(topic) => assert_eq!(topic.topic(), Some(&b"new topic"[..])),
+            typed => panic!("unexpected {:?}", typed),
+        }
+    }
+
+    #[test]
+    fn typed_invite() {
+        let m = parse_message(b"INVITE nick #a\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Invite(invite) => {
+                assert_eq!(invite.nick(), b"nick");
+                assert_eq!(invite.channel().as_bytes(), b"#a");
+            }
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"INVITE nick a\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Other { .. }));
+    }
+
+    #[test]
+    fn typed_mode() {
+        let m = parse_message(b"MODE #a +ov nick1 nick2\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Mode(Mode {
+                target: b"#a",
+                modes: &[b"+ov", b"nick1", b"nick2"],
+            })
+        );
+    }
+
+    #[test]
+    fn typed_mode_changes() {
+        use crate::mode::ModeChange::*;
+
+        let m = parse_message(b"MODE #a +o-v nick1 nick2\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Mode(mode) => assert_eq!(
+                mode.changes(),
+                vec![
+                    (Add, 'o', Some(&b"nick1"[..])),
+                    (Remove, 'v', Some(&b"nick2"[..]))
+                ]
+            ),
+            typed => panic!("unexpected {:?}", typed),
+        }
+        let m = parse_message(b"MODE nick +o\r\n").unwrap();
+        match m.typed() {
+            TypedMessage::Mode(mode) => assert_eq!(mode.changes(), vec![(Add, 'o', None)]),
+            typed => panic!("unexpected {:?}", typed),
+        }
+    }
+
+    #[test]
+    fn typed_other() {
+        let m = parse_message(b"PRIVMSG #a\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Other {
+                command: Command::Privmsg,
+                params: &[b"#a"],
+            }
+        );
+        let m = parse_message(b"001 nick :Welcome\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Other { .. }));
+    }
+
+    #[test]
+    fn typed_action() {
+        let m = parse_message(b":nick PRIVMSG #rust :\x01ACTION waves\x01\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Action(Action {
+                target: b"#rust",
+                text: b"waves"
+            })
+        );
+        let m = parse_message(b":nick PRIVMSG #rust :\x01VERSION\x01\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Privmsg(_)));
+    }
+
+    #[test]
+    fn typed_statusmsg_targets() {
+        let m = parse_message(b":nick PRIVMSG @#rust,nick :ops only\r\n").unwrap();
+        let privmsg = match m.typed() {
+            TypedMessage::Privmsg(privmsg) => privmsg,
+            other => panic!("{:?}", other),
+        };
+        let config = ParserConfig::default().statusmsg(b"@+");
+        let targets = privmsg.targets(&config).unwrap();
+        assert!(matches!(
+            targets[0],
+            Target::StatusChannel { status: b"@", channel } if channel.as_bytes() == b"#rust"
+        ));
+        assert_eq!(targets[1], Target::Nick(b"nick"));
+        assert_eq!(privmsg.targets(&ParserConfig::default()), None);
+    }
+
+    #[test]
+    fn typed_ircv3_notify() {
+        let m = parse_message(b":nick!u@h AWAY :lunch\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Away(away) if away.is_away()));
+        let m = parse_message(b":nick!u@h AWAY\r\n").unwrap();
+        assert_eq!(m.typed(), TypedMessage::Away(Away { message: None }));
+        let m = parse_message(b":nick!u@h AWAY :\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Away(away) if !away.is_away()));
+        let m = parse_message(b":nick!u@h CHGHOST user new.host\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Chghost(Chghost {
+                user: b"user",
+                host: b"new.host"
+            })
+        );
+        let m = parse_message(b":nick!u@h SETNAME :New Name\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Setname(Setname {
+                realname: b"New Name"
+            })
+        );
+        let m = parse_message(b":nick!u@h CHGHOST user\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Other { .. }));
+    }
+
+    #[test]
+    fn typed_extended_join_and_account() {
+        let m = parse_message(b":nick!u@h JOIN #rust account :Real Name\r\n").unwrap();
+        let join = match m.typed() {
+            TypedMessage::Join(join) => join,
+            other => panic!("{:?}", other),
+        };
+        assert!(join.is_extended());
+        assert_eq!(join.account(), Some(&b"account"[..]));
+        assert_eq!(join.realname(), Some(&b"Real Name"[..]));
+        assert_eq!(join.channels().count(), 1);
+        let m = parse_message(b":nick!u@h JOIN #rust * :Real Name\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Join(join) if join.account().is_none()));
+        let m = parse_message(b"JOIN #rust key\r\n").unwrap();
+        assert!(matches!(m.typed(), TypedMessage::Join(join) if !join.is_extended()));
+        let m = parse_message(b":nick!u@h ACCOUNT name\r\n").unwrap();
+        assert_eq!(
+            m.typed(),
+            TypedMessage::Account(Account {
+                account: Some(b"name")
+            })
+        );
+        let m = parse_message(b":nick!u@h ACCOUNT *\r\n").unwrap();
+        assert_eq!(m.typed(), TypedMessage::Account(Account { account: None }));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::vec::Vec;
+
+/// An entry of RPL_USERHOST: `nick*=-user@host`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Userhost<'a> {
+    pub nick: &'a [u8],
+    /// `*` after the nick
+    pub oper: bool,
+    /// `-` rather than `+` before the hostname
+    pub away: bool,
+    pub user: &'a [u8],
+    pub host: &'a [u8],
+}
+
+impl<'a> Userhost<'a> {
+    /// Parse one entry of RPL_USERHOST
+    /// <reply> ::= <nick> ['*'] '=' <'+'|'-'> <user> '@' <host>
+    pub fn parse(entry: &'a [u8]) -> Option<Self> {
+        let eq = entry.iter().position(|&c| c == b'=')?;
+        let (nick, oper) = match &entry[..eq] {
+            [nick @ .., b'*'] => (nick, true),
+            nick => (nick, false),
+        };
+        let (away, hostname) = match &entry[eq + 1..] {
+            [b'+', hostname @ ..] => (false, hostname),
+            [b'-', hostname @ ..] => (true, hostname),
+            _ => return None,
+        };
+        let at = hostname.iter().position(|&c| c == b'@')?;
+        if nick.is_empty() {
+            return None;
+        }
+        Some(Userhost {
+            nick,
+            oper,
+            away,
+            user: &hostname[..at],
+            host: &hostname[at + 1..],
+        })
+    }
+}
+
+/// Interpret a RPL_USERHOST (302): `:server 302 me :nick=+user@host`
+/// Return `None` if it's not a RPL_USERHOST or if an entry is malformed.
+pub fn userhost_reply<'a>(message: &Message<'a>) -> Option<Vec<Userhost<'a>>> {
+    if message.command.numeric() != Some(Numeric::RplUserHost) {
+        return None;
+    }
+    split(message.params.get(1)?).map(Userhost::parse).collect()
+}
+
+/// Interpret a RPL_ISON (303): `:server 303 me :nick1 nick2`
+/// Return `None` if it's not a RPL_ISON.
+pub fn ison_reply<'a>(message: &Message<'a>) -> Option<Vec<&'a [u8]>> {
+    if message.command.numeric() != Some(Numeric::RplIsOn) {
+        return None;
+    }
+    Some(split(message.params.get(1)?).collect())
+}
+
+fn split(list: &[u8]) -> impl Iterator<Item = &[u8]> {
+    list.split(|&c| c == b' ').filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn userhost_entries() {
+        let m = parse_message(b":irc 302 me :op*=+u@h away=-u2@h2 \r\n").unwrap();
+        assert_eq!(
+            userhost_reply(&m),
+            Some(vec![
+                Userhost {
+                    nick: b"op",
+                    oper: true,
+                    away: false,
+                    user: b"u",
+                    host: b"h"
+                },
+                Userhost {
+                    nick: b"away",
+                    oper: false,
+                    away: true,
+                    user: b"u2",
+                    host: b"h2"
+                },
+            ])
+        );
+        let m = parse_message(b":irc 302 me :\r\n").unwrap();
+        assert_eq!(userhost_reply(&m), Some(vec![]));
+        let m = parse_message(b":irc 302 me :nick=u@h\r\n").unwrap();
+        assert_eq!(userhost_reply(&m), None);
+    }
+
+    #[test]
+    fn ison_nicks() {
+        let m = parse_message(b":irc 303 me :a b  c\r\n").unwrap();
+        assert_eq!(ison_reply(&m), Some(vec![&b"a"[..], b"b", b"c"]));
+        let m = parse_message(b":irc 302 me :a\r\n").unwrap();
+        assert_eq!(ison_reply(&m), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! The notify lists of WATCH, the older alternative to MONITOR still used by
+//! some networks
+//! The `monitor::PresenceTracker` follows the replies of both.
+
+use crate::command::Command;
+use crate::message::Message;
+use crate::numeric::Numeric;
+use alloc::vec::Vec;
+
+/// An entry of a WATCH command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEntry<'a> {
+    /// `+nick`: follow the nick
+    Add(&'a [u8]),
+    /// `-nick`: stop following the nick
+    Remove(&'a [u8]),
+    /// `C`: stop following every nick
+    Clear,
+    /// `S`: send the statistics of the list
+    Status,
+    /// `L`: send the status of every followed nick
+    List,
+    /// `l`: send the status of the followed nicks online
+    ListOnline,
+}
+
+/// A WATCH command sent by a client: `WATCH +alice -bob l`
+/// A WATCH without entry is the same as `WATCH l`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watch<'a>(pub Vec<WatchEntry<'a>>);
+
+impl<'a> Watch<'a> {
+    /// Return `None` if it's not a WATCH or if an entry is malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        if message.command != Command::Watch {
+            return None;
+        }
+        let entries = message
+            .params
+            .iter()
+            .flat_map(|param| param.split(|&c| c == b' ' || c == b','))
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                Some(match entry {
+                    [b'+', nick @ ..] if !nick.is_empty() => WatchEntry::Add(nick),
+                    [b'-', nick @ ..] if !nick.is_empty() => WatchEntry::Remove(nick),
+                    b"C" | b"c" => WatchEntry::Clear,
+                    b"S" | b"s" => WatchEntry::Status,
+                    b"L" => WatchEntry::List,
+                    b"l" => WatchEntry::ListOnline,
+                    _ => return None,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(Watch(entries))
+    }
+
+    /// Return the line to send, crlf included
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = b"WATCH".to_vec();
+        for entry in &self.0 {
+            line.push(b' ');
+            match entry {
+                WatchEntry::Add(nick) => {
+                    line.push(b'+');
+                    line.extend_from_slice(nick);
+                }
+                WatchEntry::Remove(nick) => {
+                    line.push(b'-');
+                    line.extend_from_slice(nick);
+                }
+                WatchEntry::Clear => line.push(b'C'),
+                WatchEntry::Status => line.push(b'S'),
+                WatchEntry::List => line.push(b'L'),
+                WatchEntry::ListOnline => line.push(b'l'),
+            }
+        }
+        line.extend_from_slice(b"\r\n");
+        line
+    }
+}
+
+/// A followed user as sent in the WATCH replies:
+/// `nick user host 1600000000`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchUser<'a> {
+    pub nick: &'a [u8],
+    /// `None` when the user is offline
+    pub user: Option<&'a [u8]>,
+    /// `None` when the user is offline
+    pub host: Option<&'a [u8]>,
+    /// When the user connected or changed of status, in seconds since the
+    /// epoch, `None` when unknown
+    pub since: Option<u64>,
+}
+
+impl<'a> WatchUser<'a> {
+    fn from_params(params: &[&'a [u8]]) -> Option<Self> {
+        let (nick, user, host, since) = match *params {
+            [_, nick, user, host, since, ..] => (nick, user, host, since),
+            _ => return None,
+        };
+        let known = |field: &'a [u8]| Some(field).filter(|&field| field != b"*");
+        Some(WatchUser {
+            nick,
+            user: known(user),
+            host: known(host),
+            since: core::str::from_utf8(since)
+                .ok()
+                .and_then(|since| since.parse().ok())
+                .filter(|&since| since != 0),
+        })
+    }
+}
+
+/// A reply of the server to WATCH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchReply<'a> {
+    /// RPL_LOGON (600): a followed user connected
+    LogOn(WatchUser<'a>),
+    /// RPL_LOGOFF (601): a followed user left
+    LogOff(WatchUser<'a>),
+    /// RPL_WATCHOFF (602): the user is not followed anymore
+    WatchOff(WatchUser<'a>),
+    /// RPL_WATCHSTAT (603): the statistics of the list, as text
+    Stat(&'a [u8]),
+    /// RPL_NOWON (604): a followed user is online
+    NowOn(WatchUser<'a>),
+    /// RPL_NOWOFF (605): a followed user is offline
+    NowOff(WatchUser<'a>),
+    /// RPL_WATCHLIST (606): `:server 606 me :nick nick`
+    List(Vec<&'a [u8]>),
+    /// RPL_ENDOFWATCHLIST (607)
+    EndOfList,
+    /// RPL_CLEARWATCH (608): the list is empty
+    Cleared,
+    /// RPL_NOWISAWAY (609): a followed user is online but away
+    NowAway(WatchUser<'a>),
+}
+
+impl<'a> WatchReply<'a> {
+    /// Return `None` if the message is not one of the WATCH numerics or if
+    /// it's malformed
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        let params = &message.params[..];
+        let user = || WatchUser::from_params(params);
+        Some(match message.command.numeric()? {
+            Numeric::RplLogOn => WatchReply::LogOn(user()?),
+            Numeric::RplLogOff => WatchReply::LogOff(user()?),
+            Numeric::RplWatchOff => WatchReply::WatchOff(user()?),
+            Numeric::RplWatchStat => WatchReply::Stat(params.last()?),
+            Numeric::RplNowOn => WatchReply::NowOn(user()?),
+            Numeric::RplNowOff => WatchReply::NowOff(user()?),
+            Numeric::RplWatchList => WatchReply::List(
+                params
+                    .last()?
+                    .split(|&c| c == b' ')
+                    .filter(|nick| !nick.is_empty())
+                    .collect(),
+            ),
+            Numeric::RplEndOfWatchList => WatchReply::EndOfList,
+            Numeric::RplClearWatch => WatchReply::Cleared,
+            Numeric::RplNowIsAway => WatchReply::NowAway(user()?),
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn watch_command() {
+        let m = parse_message(b"WATCH +a -b,C l\r\n").unwrap();
+        let watch = Watch::from_message(&m).unwrap();
+        assert_eq!(
+            watch.0,
+            vec![
+                WatchEntry::Add(b"a"),
+                WatchEntry::Remove(b"b"),
+                WatchEntry::Clear,
+                WatchEntry::ListOnline
+            ]
+        );
+        assert_eq!(watch.to_bytes(), b"WATCH +a -b C l\r\n");
+        let m = parse_message(b"WATCH\r\n").unwrap();
+        assert_eq!(Watch::from_message(&m), Some(Watch(Vec::new())));
+        let m = parse_message(b"WATCH + a\r\n").unwrap();
+        assert_eq!(Watch::from_message(&m), None);
+    }
+
+    #[test]
+    fn watch_replies() {
+        let m = parse_message(b":irc 604 me nick user host 1600000000 :is online\r\n").unwrap();
+        assert_eq!(
+            WatchReply::from_message(&m),
+            Some(WatchReply::NowOn(WatchUser {
+                nick: b"nick",
+                user: Some(b"user"),
+                host: Some(b"host"),
+                since: Some(1600000000)
+            }))
+        );
+        let m = parse_message(b":irc 605 me nick * * 0 :is offline\r\n").unwrap();
+        assert_eq!(
+            WatchReply::from_message(&m),
+            Some(WatchReply::NowOff(WatchUser {
+                nick: b"nick",
+                user: None,
+                host: None,
+                since: None
+            }))
+        );
+        let m = parse_message(b":irc 606 me :a b\r\n").unwrap();
+        assert_eq!(
+            WatchReply::from_message(&m),
+            Some(WatchReply::List(vec![b"a", b"b"]))
+        );
+        let m = parse_message(b":irc 600 me nick :logged online\r\n").unwrap();
+        assert_eq!(WatchReply::from_message(&m), None);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::message::Message;
+use crate::names::DEFAULT_PREFIXES;
+use crate::numeric::Numeric;
+use alloc::vec::Vec;
+
+/// The flags of a WHO reply: `H*@`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhoFlags<'a> {
+    /// `G` (gone) rather than `H` (here)
+    pub away: bool,
+    /// `*`: the user is an IRC operator
+    pub oper: bool,
+    /// The membership prefixes in the channel: `@+`
+    pub prefixes: &'a [u8],
+    /// All the flags as sent, including the non standard ones
+    pub raw: &'a [u8],
+}
+
+impl<'a> WhoFlags<'a> {
+    /// Parse the flags with the default membership prefixes
+    pub fn parse(flags: &'a [u8]) -> Self {
+        Self::parse_with(flags, DEFAULT_PREFIXES)
+    }
+
+    /// Parse the flags with the membership prefixes advertised by the server
+    /// The prefixes are the longest run of prefix chars following the
+    /// `H`/`G` and `*` flags.
+    pub fn parse_with(flags: &'a [u8], prefixes: &[u8]) -> Self {
+        let away = flags.first() == Some(&b'G');
+        let rest = match flags.first() {
+            Some(b'H') | Some(b'G') => &flags[1..],
+            _ => flags,
+        };
+        let oper = rest.first() == Some(&b'*');
+        let rest = if oper { &rest[1..] } else { rest };
+        let len = rest.iter().take_while(|c| prefixes.contains(c)).count();
+        WhoFlags {
+            away,
+            oper,
+            prefixes: &rest[..len],
+            raw: flags,
+        }
+    }
+}
+
+/// A RPL_WHOREPLY (352)
+/// `:server 352 me #chan user host server nick H@ :0 Real Name`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhoReply<'a> {
+    /// The channel or `*`
+    pub channel: &'a [u8],
+    pub user: &'a [u8],
+    pub host: &'a [u8],
+    pub server: &'a [u8],
+    pub nick: &'a [u8],
+    pub flags: WhoFlags<'a>,
+    /// The number of hops to the server of the user
+    pub hopcount: u32,
+    pub realname: &'a [u8],
+}
+
+impl<'a> WhoReply<'a> {
+    /// Return `None` if the message is not a valid RPL_WHOREPLY
+    pub fn from_message(message: &Message<'a>) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplWhoReply) {
+            return None;
+        }
+        match message.params[..] {
+            [_, channel, user, host, server, nick, flags, trailing] => {
+                let (hopcount, realname) = hopcount_realname(trailing)?;
+                Some(WhoReply {
+                    channel,
+                    user,
+                    host,
+                    server,
+                    nick,
+                    flags: WhoFlags::parse(flags),
+                    hopcount,
+                    realname,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Split the `hopcount realname` trailing parameter
+fn hopcount_realname(trailing: &[u8]) -> Option<(u32, &[u8])> {
+    let (hopcount, realname) = match trailing.iter().position(|&c| c == b' ') {
+        Some(pos) => (&trailing[..pos], &trailing[pos + 1..]),
+        None => (trailing, &b""[..]),
+    };
+    let hopcount = core::str::from_utf8(hopcount).ok()?.parse().ok()?;
+    Some((hopcount, realname))
+}
+
+/// The fields a WHOX reply can contain, in the order they are sent
+pub const WHOX_FIELDS: &[u8] = b"tcuihsnfdlaor";
+
+/// A WHOX query: `WHO #chan %tcuhnfar,42`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhoxQuery<'a> {
+    pub mask: &'a [u8],
+    /// The requested fields, see `WHOX_FIELDS`
+    pub fields: &'a [u8],
+    /// Echoed back in the replies if the `t` field is requested, up to 3
+    /// digits
+    pub token: Option<&'a [u8]>,
+}
+
+impl<'a> WhoxQuery<'a> {
+    pub fn new(mask: &'a [u8], fields: &'a [u8]) -> Self {
+        WhoxQuery {
+            mask,
+            fields,
+            token: None,
+        }
+    }
+
+    /// Ask for the token to be sent back, the `t` field is added if needed
+    pub fn token(mut self, token: &'a [u8]) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Return the line to send, crlf included
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut line = b"WHO ".to_vec();
+        line.extend_from_slice(self.mask);
+        line.extend_from_slice(b" %");
+        if self.token.is_some() && !self.fields.contains(&b't') {
+            line.push(b't');
+        }
+        line.extend_from_slice(self.fields);
+        if let Some(token) = self.token {
+            line.push(b',');
+            line.extend_from_slice(token);
+        }
+        line.extend_from_slice(b"\r\n");
+        line
+    }
+}
+
+/// A RPL_WHOSPCRPL (354), every field is present only if it was requested
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WhoxReply<'a> {
+    pub token: Option<&'a [u8]>,
+    pub channel: Option<&'a [u8]>,
+    pub user: Option<&'a [u8]>,
+    pub ip: Option<&'a [u8]>,
+    pub host: Option<&'a [u8]>,
+    pub server: Option<&'a [u8]>,
+    pub nick: Option<&'a [u8]>,
+    pub flags: Option<WhoFlags<'a>>,
+    pub hopcount: Option<&'a [u8]>,
+    pub idle: Option<&'a [u8]>,
+    /// `0` when the user is not logged in
+    pub account: Option<&'a [u8]>,
+    pub oplevel: Option<&'a [u8]>,
+    pub realname: Option<&'a [u8]>,
+}
+
+impl<'a> WhoxReply<'a> {
+    /// Interpret a RPL_WHOSPCRPL answering a query for `fields`
+    /// Return `None` if it's not a RPL_WHOSPCRPL or if the number of
+    /// parameters doesn't match the fields.
+    pub fn from_message(message: &Message<'a>, fields: &[u8]) -> Option<Self> {
+        if message.command.numeric() != Some(Numeric::RplWhoSpcRpl) {
+            return None;
+        }
+        let requested: Vec<u8> = WHOX_FIELDS
+            .iter()
+            .copied()
+            .filter(|field| fields.contains(field))
+            .collect();
+        let values = message.params.get(1..)?;
+        if values.len() != requested.len() {
+            return None;
+        }
+        let mut reply = WhoxReply::default();
+        for (field, &value) in requested.iter().zip(values) {
+            let slot = match field {
+                b't' => &mut reply.token,
+                b'c' => &mut reply.channel,
+                b'u' => &mut reply.user,
+                b'i' => &mut reply.ip,
+                b'h' => &mut reply.host,
+                b's' => &mut reply.server,
+                b'n' => &mut reply.nick,
+                b'f' => {
+                    reply.flags = Some(WhoFlags::parse(value));
+                    continue;
+                }
+                b'd' => &mut reply.hopcount,
+                b'l' => &mut reply.idle,
+                b'a' => &mut reply.account,
+                b'o' => &mut reply.oplevel,
+                _ => &mut reply.realname,
+            };
+            *slot = Some(value);
+        }
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::parse_message;
+
+    #[test]
+    fn who_flags() {
+        let flags = WhoFlags::parse(b"G*@+");
+        assert!(flags.away);
+        assert!(flags.oper);
+        assert_eq!(flags.prefixes, b"@+");
+        let flags = WhoFlags::parse(b"HB");
+        assert!(!flags.away);
+        assert!(!flags.oper);
+        assert_eq!(flags.prefixes, b"");
+    }
+
+    #[test]
+    fn who_reply() {
+        let m =
+            parse_message(b":irc 352 me #chan user host irc.srv nick H@ :3 Real Name\r\n").unwrap();
+        let reply = WhoReply::from_message(&m).unwrap();
+        assert_eq!(reply.channel, b"#chan");
+        assert_eq!(reply.nick, b"nick");
+        assert_eq!(reply.flags.prefixes, b"@");
+        assert_eq!(reply.hopcount, 3);
+        assert_eq!(reply.realname, b"Real Name");
+        let m = parse_message(b":irc 352 me * u h s n H :x Name\r\n").unwrap();
+        assert_eq!(WhoReply::from_message(&m), None);
+    }
+
+    #[test]
+    fn whox_query() {
+        assert_eq!(
+            WhoxQuery::new(b"#chan", b"cuhnfar").to_bytes(),
+            b"WHO #chan %cuhnfar\r\n".to_vec()
+        );
+        assert_eq!(
+            WhoxQuery::new(b"#chan", b"na").token(b"42").to_bytes(),
+            b"WHO #chan %tna,42\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn whox_reply() {
+        let m = parse_message(b":irc 354 me 42 #chan nick H@ account :Real Name\r\n").unwrap();
+        let reply = WhoxReply::from_message(&m, b"tcnfar").unwrap();
+        assert_eq!(reply.token, Some(&b"42"[..]));
+        assert_eq!(reply.channel, Some(&b"#chan"[..]));
+        assert_eq!(reply.nick, Some(&b"nick"[..]));
+        assert_eq!(reply.flags.unwrap().prefixes, b"@");
+        assert_eq!(reply.account, Some(&b"account"[..]));
+        assert_eq!(reply.realname, Some(&b"Real Name"[..]));
+        assert_eq!(reply.user, None);
+        assert_eq!(WhoxReply::from_message(&m, b"tcn"), None);
+    }
+}